@@ -0,0 +1,255 @@
+//! # Bucket Bootstrap
+//!
+//! This module prepares an S3 bucket for use as Prepis temporary storage.
+//!
+//! It provides functionality for:
+//! - Creating the bucket in the right region when it does not exist
+//! - Blocking all public access to the bucket
+//! - Installing a lifecycle rule that expires leftover temporary files
+//! - Verifying that Amazon Transcribe can read media from the bucket
+//!
+//! The lifecycle rule acts as a safety net for runs where the client-side
+//! cleanup could not delete the uploaded media.
+
+use crate::aws::client::AwsClients;
+use crate::aws::transcribe::{poll_transcription_status, start_transcription_job};
+use crate::error::AppError;
+use crate::file::silent_wav;
+use crate::models::{JobOptions, PollOptions, TranscriptionStatus};
+use crate::utils::S3_TEMP_PREFIX;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::operation::create_bucket::CreateBucketError;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{
+    AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, BucketLocationConstraint,
+    CreateBucketConfiguration, ExpirationStatus, LifecycleExpiration, LifecycleRule,
+    LifecycleRuleFilter, PublicAccessBlockConfiguration,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// ID of the lifecycle rule managed by prepis
+const LIFECYCLE_RULE_ID: &str = "prepis-expire-temporary-media";
+
+/// Days after which leftover temporary files and incomplete uploads are removed
+const TEMP_EXPIRATION_DAYS: i32 = 1;
+
+/// Create (if needed) and configure a bucket for use with prepis
+pub async fn init_bucket(clients: &AwsClients, bucket: &str, verify: bool) -> Result<(), AppError> {
+    ensure_bucket_exists(&clients.s3_client, bucket).await?;
+    block_public_access(&clients.s3_client, bucket).await?;
+    install_lifecycle_rule(&clients.s3_client, bucket).await?;
+
+    if verify {
+        verify_transcribe_access(clients, bucket).await?;
+    } else {
+        info!("⏭️  Skipping Amazon Transcribe access check");
+    }
+
+    info!("🎉 Bucket {} is ready to use with prepis", bucket);
+    Ok(())
+}
+
+/// Create the bucket in the client's region unless it already exists
+async fn ensure_bucket_exists(s3_client: &aws_sdk_s3::Client, bucket: &str) -> Result<(), AppError> {
+    match s3_client.head_bucket().bucket(bucket).send().await {
+        Ok(_) => {
+            info!("✅ Bucket {} already exists", bucket);
+            return Ok(());
+        }
+        Err(e) => match e.raw_response().map(|r| r.status().as_u16()) {
+            Some(404) => {}
+            Some(403) => {
+                return Err(AppError::Aws(format!(
+                    "Bucket {} exists but you do not have access to it (it may be owned by another account)",
+                    bucket
+                )));
+            }
+            _ => {
+                return Err(AppError::s3(
+                    format!("Failed to check whether bucket {} exists", bucket),
+                    e,
+                ));
+            }
+        },
+    }
+
+    let region = s3_client
+        .config()
+        .region()
+        .map(|r| r.to_string())
+        .unwrap_or_else(|| "us-east-1".to_string());
+    info!("🪣 Creating bucket {} in {}", bucket, region);
+
+    // us-east-1 is the default location and must not be passed as a constraint
+    let mut request = s3_client.create_bucket().bucket(bucket);
+    if region != "us-east-1" {
+        request = request.create_bucket_configuration(
+            CreateBucketConfiguration::builder()
+                .location_constraint(BucketLocationConstraint::from(region.as_str()))
+                .build(),
+        );
+    }
+
+    match request.send().await {
+        Ok(_) => {
+            info!("✅ Bucket created successfully");
+            Ok(())
+        }
+        Err(e) => match e.as_service_error() {
+            Some(CreateBucketError::BucketAlreadyOwnedByYou(_)) => {
+                info!("✅ Bucket {} already exists", bucket);
+                Ok(())
+            }
+            Some(CreateBucketError::BucketAlreadyExists(_)) => Err(AppError::Config(format!(
+                "Bucket name {} is already taken by another account, please choose a different name",
+                bucket
+            ))),
+            _ => Err(AppError::s3("Failed to create bucket", e)),
+        },
+    }
+}
+
+/// Block every form of public access on the bucket
+async fn block_public_access(s3_client: &aws_sdk_s3::Client, bucket: &str) -> Result<(), AppError> {
+    let configuration = PublicAccessBlockConfiguration::builder()
+        .block_public_acls(true)
+        .ignore_public_acls(true)
+        .block_public_policy(true)
+        .restrict_public_buckets(true)
+        .build();
+
+    s3_client
+        .put_public_access_block()
+        .bucket(bucket)
+        .public_access_block_configuration(configuration)
+        .send()
+        .await
+        .map_err(|e| AppError::s3("Failed to block public access", e))?;
+
+    info!("🔒 Public access blocked");
+    Ok(())
+}
+
+/// Install the lifecycle rule for temporary files, keeping any other existing rules
+async fn install_lifecycle_rule(s3_client: &aws_sdk_s3::Client, bucket: &str) -> Result<(), AppError> {
+    // A bucket without lifecycle configuration returns an error, treat it as empty.
+    // Any other error must stop us, or the rules we could not read would be replaced.
+    let existing_rules = match s3_client
+        .get_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .send()
+        .await
+    {
+        Ok(output) => output.rules().to_vec(),
+        Err(e) if e.code() == Some("NoSuchLifecycleConfiguration") => Vec::new(),
+        Err(e) => return Err(AppError::s3("Failed to read the existing lifecycle rules", e)),
+    };
+
+    let prepis_rule = LifecycleRule::builder()
+        .id(LIFECYCLE_RULE_ID)
+        .status(ExpirationStatus::Enabled)
+        .filter(LifecycleRuleFilter::builder().prefix(S3_TEMP_PREFIX).build())
+        .expiration(LifecycleExpiration::builder().days(TEMP_EXPIRATION_DAYS).build())
+        .abort_incomplete_multipart_upload(
+            AbortIncompleteMultipartUpload::builder()
+                .days_after_initiation(TEMP_EXPIRATION_DAYS)
+                .build(),
+        )
+        .build()
+        .map_err(|e| AppError::S3(format!("Failed to build lifecycle rule: {}", e)))?;
+
+    let rules: Vec<LifecycleRule> = existing_rules
+        .into_iter()
+        .filter(|rule| rule.id() != Some(LIFECYCLE_RULE_ID))
+        .chain(std::iter::once(prepis_rule))
+        .collect();
+
+    let configuration = BucketLifecycleConfiguration::builder()
+        .set_rules(Some(rules))
+        .build()
+        .map_err(|e| AppError::S3(format!("Failed to build lifecycle configuration: {}", e)))?;
+
+    s3_client
+        .put_bucket_lifecycle_configuration()
+        .bucket(bucket)
+        .lifecycle_configuration(configuration)
+        .send()
+        .await
+        .map_err(|e| AppError::s3("Failed to install lifecycle rule", e))?;
+
+    info!(
+        "♻️  Lifecycle rule installed: {}* expires after {} day(s), incomplete uploads are aborted",
+        S3_TEMP_PREFIX, TEMP_EXPIRATION_DAYS
+    );
+    Ok(())
+}
+
+/// Run a tiny transcription job against the bucket to make sure Transcribe can read from it
+async fn verify_transcribe_access(clients: &AwsClients, bucket: &str) -> Result<(), AppError> {
+    info!("🔍 Verifying that Amazon Transcribe can read from the bucket...");
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let job_name = format!("prepis-init-check-{}", timestamp);
+    let s3_key = format!("{}{}.wav", S3_TEMP_PREFIX, job_name);
+    let s3_uri = format!("s3://{}/{}", bucket, s3_key);
+
+    clients
+        .s3_client
+        .put_object()
+        .bucket(bucket)
+        .key(&s3_key)
+        .body(ByteStream::from(silent_wav(2)))
+        .send()
+        .await
+        .map_err(|e| AppError::s3("Failed to upload test file", e))?;
+
+    let result = match start_transcription_job(
+        &clients.transcribe_client,
+        &job_name,
+        &s3_uri,
+        &JobOptions::default(),
+    )
+    .await {
+        Ok(()) => {
+            let options = PollOptions::default();
+            poll_transcription_status(&clients.transcribe_client, &job_name, &options, None).await
+        }
+        Err(e) => Err(e),
+    };
+
+    // Clean up the test job and file regardless of the outcome
+    let _ = clients
+        .transcribe_client
+        .delete_transcription_job()
+        .transcription_job_name(&job_name)
+        .send()
+        .await;
+    if let Err(e) = clients.s3_client.delete_object().bucket(bucket).key(&s3_key).send().await {
+        warn!("Failed to delete test file s3://{}/{}: {}", bucket, s3_key, e);
+    }
+
+    match result? {
+        TranscriptionStatus::Completed(_) => {
+            info!("✅ Amazon Transcribe can read from the bucket");
+            Ok(())
+        }
+        TranscriptionStatus::Failed(reason) if is_access_failure(&reason) => Err(AppError::Transcribe(
+            format!("Amazon Transcribe cannot read from bucket {}: {}", bucket, reason),
+        )),
+        TranscriptionStatus::Failed(reason) => {
+            // The job got far enough to read the media, which is all we need to know
+            info!("✅ Amazon Transcribe can read from the bucket (test job ended with: {})", reason);
+            Ok(())
+        }
+    }
+}
+
+/// Check whether a job failure reason points at missing read access to the media
+fn is_access_failure(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    reason.contains("can't be accessed") || reason.contains("access denied") || reason.contains("permission")
+}
@@ -5,46 +5,199 @@
 //!
 //! It provides:
 //! - AWS client initialization with proper configuration
-//! - Credential validation
+//! - Region, profile and custom endpoint overrides (e.g. LocalStack or MinIO)
+//! - Retries with backoff for throttled and transiently failing calls
+//! - Bucket region detection, since Transcribe needs media in its own region
 //! - Client structure for easy access to different AWS services
+//! - Debug logging of every AWS call (see [`crate::aws::request_log`])
 //!
-//! The module ensures that AWS clients are properly configured before
-//! any AWS operations are performed.
+//! Credentials and permissions are validated separately by the preflight
+//! checks in [`crate::aws::preflight`].
 
+use crate::aws::request_log::RequestLogger;
+use crate::config;
 use crate::error::AppError;
+use aws_config::retry::RetryConfig;
+use aws_config::{Region, SdkConfig};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Overrides for how the AWS clients are configured
+#[derive(Debug, Clone, Default)]
+pub struct AwsOptions {
+    pub region: Option<String>,
+    pub profile: Option<String>,
+    pub endpoint_url: Option<String>,            // Applies to every service
+    pub s3_endpoint_url: Option<String>,         // Takes precedence for S3
+    pub transcribe_endpoint_url: Option<String>, // Takes precedence for Transcribe
+    pub max_attempts: Option<u32>,               // Per call, including the first one
+}
+
+impl AwsOptions {
+    /// Custom S3 endpoint, if any
+    pub fn s3_endpoint(&self) -> Option<&str> {
+        self.s3_endpoint_url.as_deref().or(self.endpoint_url.as_deref())
+    }
+
+    fn transcribe_endpoint(&self) -> Option<&str> {
+        self.transcribe_endpoint_url
+            .as_deref()
+            .or(self.endpoint_url.as_deref())
+    }
+}
 
 /// AWS clients container
 pub struct AwsClients {
     pub s3_client: aws_sdk_s3::Client,
     pub transcribe_client: aws_sdk_transcribe::Client,
+    pub sts_client: aws_sdk_sts::Client,
+    config: SdkConfig,
+    options: AwsOptions,
 }
 
 /// Initialize AWS clients using the default credential chain
-pub async fn create_aws_clients() -> Result<AwsClients, AppError> {
-    println!("🔧 Initializing AWS clients...");
+///
+/// Region and profile come from the environment unless they are overridden.
+pub async fn create_aws_clients(options: &AwsOptions) -> Result<AwsClients, AppError> {
+    info!("🔧 Initializing AWS clients...");
 
     // Load AWS configuration from environment with behavior version
-    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+    if let Some(profile) = &options.profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = &options.region {
+        loader = loader.region(Region::new(region.clone()));
+    }
+    let config = loader.retry_config(retry_config(options)).load().await;
 
-    // Create S3 and Transcribe clients
-    let s3_client = aws_sdk_s3::Client::new(&config);
-    let transcribe_client = aws_sdk_transcribe::Client::new(&config);
+    if config.region().is_none() {
+        return Err(AppError::Config(
+            "No AWS region configured, please pass --region or set AWS_REGION".to_string(),
+        ));
+    }
 
-    // Test AWS credentials by making a simple call
-    match s3_client.list_buckets().send().await {
-        Ok(_) => {
-            println!("✅ AWS credentials validated successfully");
+    Ok(AwsClients::from_config(config, options.clone()))
+}
+
+/// Retry policy shared by every AWS call
+///
+/// The SDK's standard mode only retries throttling, transient network and
+/// server errors, with exponential backoff and full jitter. Any other error
+/// fails right away, and a retry quota shared by all calls stops retrying
+/// altogether when a service keeps failing.
+fn retry_config(options: &AwsOptions) -> RetryConfig {
+    RetryConfig::standard()
+        .with_max_attempts(options.max_attempts.unwrap_or(config::DEFAULT_MAX_ATTEMPTS))
+        .with_initial_backoff(Duration::from_secs(1))
+        .with_max_backoff(Duration::from_secs(20))
+}
+
+impl AwsClients {
+    /// Create S3, Transcribe and STS clients from a loaded configuration
+    fn from_config(config: SdkConfig, options: AwsOptions) -> Self {
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&config).interceptor(RequestLogger);
+        if let Some(endpoint) = options.s3_endpoint() {
+            // Local S3 implementations don't support virtual-hosted style addressing
+            s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
         }
-        Err(e) => {
-            return Err(AppError::Aws(format!(
-                "Failed to validate AWS credentials: {}. Please check your AWS configuration.",
-                e
-            )));
+
+        let mut transcribe_config =
+            aws_sdk_transcribe::config::Builder::from(&config).interceptor(RequestLogger);
+        if let Some(endpoint) = options.transcribe_endpoint() {
+            transcribe_config = transcribe_config.endpoint_url(endpoint);
+        }
+
+        let mut sts_config = aws_sdk_sts::config::Builder::from(&config).interceptor(RequestLogger);
+        if let Some(endpoint) = &options.endpoint_url {
+            sts_config = sts_config.endpoint_url(endpoint);
         }
+
+        Self {
+            s3_client: aws_sdk_s3::Client::from_conf(s3_config.build()),
+            transcribe_client: aws_sdk_transcribe::Client::from_conf(transcribe_config.build()),
+            sts_client: aws_sdk_sts::Client::from_conf(sts_config.build()),
+            config,
+            options,
+        }
+    }
+
+    /// Region the clients are configured for
+    pub fn region(&self) -> Option<&str> {
+        self.config.region().map(|r| r.as_ref())
+    }
+
+    /// Custom S3 endpoint the clients were configured with, if any
+    pub fn s3_endpoint(&self) -> Option<&str> {
+        self.options.s3_endpoint()
+    }
+
+    /// Make sure the clients target the region the bucket lives in
+    ///
+    /// Amazon Transcribe can only read media from a bucket in its own region.
+    /// When the region was not chosen explicitly the clients are switched to
+    /// the bucket's region, otherwise a warning is printed. Buckets behind a
+    /// custom S3 endpoint are left alone, their location says nothing about
+    /// where Transcribe runs.
+    pub async fn align_with_bucket_region(self, bucket: &str) -> AwsClients {
+        if self.s3_endpoint().is_some() {
+            return self;
+        }
+
+        let bucket_region = match detect_bucket_region(&self.s3_client, bucket).await {
+            Ok(region) => region,
+            Err(e) => {
+                warn!("Could not detect the region of bucket {}: {}", bucket, e);
+                return self;
+            }
+        };
+
+        let Some(client_region) = self.region() else {
+            return self;
+        };
+        if client_region == bucket_region {
+            return self;
+        }
+
+        if self.options.region.is_some() || self.options.transcribe_endpoint().is_some() {
+            warn!(
+                "Bucket {} is in {} but Transcribe is used in {}. Transcribe requires the media in its own region.",
+                bucket, bucket_region, client_region
+            );
+            return self;
+        }
+
+        info!(
+            "🌍 Bucket {} is in {}, switching from {} to match it",
+            bucket, bucket_region, client_region
+        );
+        let config = self
+            .config
+            .to_builder()
+            .region(Region::new(bucket_region))
+            .build();
+        AwsClients::from_config(config, self.options)
     }
+}
 
-    Ok(AwsClients {
-        s3_client,
-        transcribe_client,
-    })
+/// Look up the region a bucket was created in
+pub async fn detect_bucket_region(
+    s3_client: &aws_sdk_s3::Client,
+    bucket: &str,
+) -> Result<String, AppError> {
+    match s3_client.get_bucket_location().bucket(bucket).send().await {
+        // Buckets in us-east-1 have no location constraint, and "EU" is a legacy alias
+        Ok(output) => Ok(match output.location_constraint().map(|c| c.as_str()) {
+            None | Some("") => "us-east-1".to_string(),
+            Some("EU") => "eu-west-1".to_string(),
+            Some(region) => region.to_string(),
+        }),
+        Err(e) => {
+            // S3 redirects requests for buckets in other regions and names the region in a header
+            e.raw_response()
+                .and_then(|r| r.headers().get("x-amz-bucket-region"))
+                .map(str::to_string)
+                .ok_or_else(|| AppError::s3("Failed to get bucket location", e))
+        }
+    }
 }
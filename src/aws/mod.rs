@@ -4,20 +4,30 @@
 //!
 //! It provides:
 //! - AWS client initialization and configuration
+//! - Bucket bootstrap for temporary storage
+//! - Preflight permission checks
+//! - Debug logging of every AWS call
 //! - S3 operations for file storage and retrieval
 //! - Amazon Transcribe job management
-//! - Result processing and parsing
+//! - Parsing of results into a [`Transcript`](crate::models::Transcript)
 //!
 //! The module abstracts away the complexities of working with AWS services
 //! and provides a clean interface for the main application.
 
+pub mod bucket;
 pub mod client;
+pub mod preflight;
+pub mod request_log;
+pub mod result;
 pub mod s3;
 pub mod transcribe;
 
-pub use client::create_aws_clients;
+pub use bucket::init_bucket;
+pub use client::{create_aws_clients, AwsClients, AwsOptions};
+pub use preflight::{run_full_preflight, run_preflight};
 pub use s3::delete_file_from_s3;
 pub use s3::upload_file_to_s3;
 pub use transcribe::get_transcription_result;
+pub use transcribe::get_transcription_result_from_s3;
 pub use transcribe::poll_transcription_status;
 pub use transcribe::start_transcription_job;
@@ -0,0 +1,384 @@
+//! # Preflight Checks
+//!
+//! This module verifies that the configured AWS credentials can perform the
+//! operations a transcription run needs, before anything is uploaded.
+//!
+//! It provides:
+//! - Credential validation through STS GetCallerIdentity
+//! - Bucket access validation through HeadBucket
+//! - Non-destructive probes for the individual S3 and Transcribe permissions
+//!
+//! Every check reports the exact IAM action it depends on, so missing
+//! permissions can be added to a policy without guesswork.
+
+use crate::aws::client::AwsClients;
+use crate::error::{AppError, ErrorClass};
+use crate::utils::{JOB_NAME_PREFIX, S3_TEMP_PREFIX};
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use tracing::info;
+
+/// Outcome of a single preflight check
+#[derive(Debug)]
+pub enum CheckOutcome {
+    Passed(String),   // Contains details about what was verified
+    Denied,           // The IAM action is not allowed
+    Failed(AppError), // A failure unrelated to permissions
+}
+
+/// A single preflight check and its outcome
+#[derive(Debug)]
+pub struct PreflightCheck {
+    pub description: &'static str,
+    pub iam_action: &'static str,
+    pub outcome: CheckOutcome,
+}
+
+/// Results of all preflight checks that were run
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Check whether every check passed
+    pub fn is_ok(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| matches!(check.outcome, CheckOutcome::Passed(_)))
+    }
+
+    /// IAM actions that were denied
+    pub fn missing_permissions(&self) -> Vec<&'static str> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, CheckOutcome::Denied))
+            .map(|check| check.iam_action)
+            .collect()
+    }
+
+    /// Print one line per check
+    pub fn print(&self) {
+        for check in &self.checks {
+            match &check.outcome {
+                CheckOutcome::Passed(details) => {
+                    info!("✅ {} ({}): {}", check.description, check.iam_action, details)
+                }
+                CheckOutcome::Denied => {
+                    info!("❌ {}: missing permission {}", check.description, check.iam_action)
+                }
+                CheckOutcome::Failed(error) => {
+                    info!("❌ {} ({}): {}", check.description, check.iam_action, error)
+                }
+            }
+        }
+    }
+
+    /// Turn a failed report into an error listing what is missing
+    ///
+    /// Missing permissions take precedence. Otherwise the error of the first
+    /// failed check is returned, so e.g. a missing bucket exits as not found.
+    pub fn into_result(self) -> Result<(), AppError> {
+        let missing = self.missing_permissions();
+        if !missing.is_empty() {
+            return Err(AppError::Aws(format!(
+                "Preflight checks failed, missing IAM permissions: {}",
+                missing.join(", ")
+            )));
+        }
+
+        match self.checks.into_iter().find_map(|check| match check.outcome {
+            CheckOutcome::Failed(error) => Some(error),
+            _ => None,
+        }) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Check whether the credentials check (always the first one) passed
+    fn credentials_ok(&self) -> bool {
+        matches!(
+            self.checks.first().map(|check| &check.outcome),
+            Some(CheckOutcome::Passed(_))
+        )
+    }
+
+    fn push(&mut self, description: &'static str, iam_action: &'static str, outcome: CheckOutcome) {
+        self.checks.push(PreflightCheck {
+            description,
+            iam_action,
+            outcome,
+        });
+    }
+}
+
+/// Run the lightweight checks done before every transcription
+///
+/// These only read state: credentials, bucket access and Transcribe access.
+/// Uploading, cleaning up and starting jobs are only probed by
+/// [`run_full_preflight`], a missing permission for them fails the run later.
+pub async fn run_preflight(clients: &AwsClients, bucket: Option<&str>) -> PreflightReport {
+    run_read_checks(clients, bucket, &probe_job_name()).await
+}
+
+/// Run every check, including probes that write to the bucket and Transcribe
+pub async fn run_full_preflight(clients: &AwsClients, bucket: Option<&str>) -> PreflightReport {
+    // A name of its own keeps concurrent runs from seeing each other's probes
+    let probe_name = probe_job_name();
+    let mut report = run_read_checks(clients, bucket, &probe_name).await;
+
+    let Some(bucket) = bucket else {
+        return report;
+    };
+    if !report.credentials_ok() {
+        return report;
+    }
+
+    let probe_key = format!("{}{}", S3_TEMP_PREFIX, probe_name);
+
+    report.push(
+        "Upload media",
+        "s3:PutObject",
+        check_put_object(&clients.s3_client, bucket, &probe_key).await,
+    );
+    report.push(
+        "Clean up media",
+        "s3:DeleteObject",
+        check_delete_object(&clients.s3_client, bucket, &probe_key).await,
+    );
+    report.push(
+        "Start transcription job",
+        "transcribe:StartTranscriptionJob",
+        check_start_transcription_job(&clients.transcribe_client, &probe_name, bucket, &probe_key)
+            .await,
+    );
+
+    report
+}
+
+/// Unique name for the probe job and object of one run
+fn probe_job_name() -> String {
+    format!("{}preflight-{:016x}", JOB_NAME_PREFIX, fastrand::u64(..))
+}
+
+/// Check credentials, bucket access and Transcribe access
+async fn run_read_checks(
+    clients: &AwsClients,
+    bucket: Option<&str>,
+    probe_name: &str,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+
+    report.push(
+        "AWS credentials",
+        "sts:GetCallerIdentity",
+        check_caller_identity(&clients.sts_client).await,
+    );
+
+    // Without valid credentials every other check fails the same way
+    if !report.credentials_ok() {
+        return report;
+    }
+
+    if let Some(bucket) = bucket {
+        report.push(
+            "Bucket access",
+            "s3:ListBucket",
+            check_head_bucket(&clients.s3_client, bucket).await,
+        );
+    }
+
+    report.push(
+        "Transcription job status",
+        "transcribe:GetTranscriptionJob",
+        check_get_transcription_job(&clients.transcribe_client, probe_name).await,
+    );
+
+    report
+}
+
+/// Check whether an error returned by AWS means the action is not allowed
+fn is_access_denied(code: Option<&str>) -> bool {
+    matches!(
+        code,
+        Some("AccessDenied") | Some("AccessDeniedException") | Some("UnauthorizedOperation")
+    )
+}
+
+async fn check_caller_identity(sts_client: &aws_sdk_sts::Client) -> CheckOutcome {
+    match sts_client.get_caller_identity().send().await {
+        Ok(identity) => CheckOutcome::Passed(format!(
+            "authenticated as {}",
+            identity.arn().unwrap_or("unknown identity")
+        )),
+        Err(e) => CheckOutcome::Failed(AppError::sts("Could not verify credentials", e)),
+    }
+}
+
+async fn check_head_bucket(s3_client: &aws_sdk_s3::Client, bucket: &str) -> CheckOutcome {
+    match s3_client.head_bucket().bucket(bucket).send().await {
+        Ok(_) => CheckOutcome::Passed(format!("bucket {} is reachable", bucket)),
+        // HeadBucket has no response body, so the status code is all we get
+        Err(e) => match e.raw_response().map(|r| r.status().as_u16()) {
+            Some(403) => CheckOutcome::Denied,
+            Some(404) => CheckOutcome::Failed(AppError::NotFound(format!("Bucket {}", bucket))),
+            Some(301) => CheckOutcome::Failed(AppError::Config(format!(
+                "Bucket {} is in a different region than the configured one",
+                bucket
+            ))),
+            _ => CheckOutcome::Failed(AppError::s3(format!("Failed to reach bucket {}", bucket), e)),
+        },
+    }
+}
+
+async fn check_put_object(s3_client: &aws_sdk_s3::Client, bucket: &str, key: &str) -> CheckOutcome {
+    match s3_client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .body(ByteStream::from_static(b""))
+        .send()
+        .await
+    {
+        Ok(_) => CheckOutcome::Passed(format!("wrote s3://{}/{}", bucket, key)),
+        Err(e) if is_access_denied(e.code()) => CheckOutcome::Denied,
+        Err(e) => CheckOutcome::Failed(AppError::s3("Failed to upload the probe object", e)),
+    }
+}
+
+async fn check_delete_object(s3_client: &aws_sdk_s3::Client, bucket: &str, key: &str) -> CheckOutcome {
+    // Deleting a key that does not exist still succeeds when the action is allowed
+    match s3_client.delete_object().bucket(bucket).key(key).send().await {
+        Ok(_) => CheckOutcome::Passed(format!("deleted s3://{}/{}", bucket, key)),
+        Err(e) if is_access_denied(e.code()) => CheckOutcome::Denied,
+        Err(e) => CheckOutcome::Failed(AppError::s3("Failed to delete the probe object", e)),
+    }
+}
+
+async fn check_get_transcription_job(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    probe_name: &str,
+) -> CheckOutcome {
+    // The probe job never exists, so "not found" means the call itself is allowed
+    match transcribe_client
+        .get_transcription_job()
+        .transcription_job_name(probe_name)
+        .send()
+        .await
+    {
+        Ok(_) => CheckOutcome::Passed("Transcribe is reachable".to_string()),
+        Err(e) if is_access_denied(e.code()) => CheckOutcome::Denied,
+        Err(e) => {
+            let error = AppError::transcribe("Failed to look up the probe job", e);
+            match error.class() {
+                ErrorClass::NotFound => CheckOutcome::Passed("Transcribe is reachable".to_string()),
+                _ => CheckOutcome::Failed(error),
+            }
+        }
+    }
+}
+
+/// Whether Transcribe refused the probe job because its media does not exist
+///
+/// Transcribe answers e.g. "The S3 URI that you provided can't be accessed",
+/// which it only checks once the caller is allowed to start jobs.
+fn is_missing_media(code: Option<&str>, message: Option<&str>) -> bool {
+    code == Some("BadRequestException") && message.is_some_and(|m| m.contains("S3 URI"))
+}
+
+async fn check_start_transcription_job(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    probe_name: &str,
+    bucket: &str,
+    missing_key: &str,
+) -> CheckOutcome {
+    // Point the job at media that does not exist, so it can never transcribe anything
+    let media = aws_sdk_transcribe::types::Media::builder()
+        .media_file_uri(format!("s3://{}/{}", bucket, missing_key))
+        .build();
+
+    let outcome = match transcribe_client
+        .start_transcription_job()
+        .transcription_job_name(probe_name)
+        .media(media)
+        .language_code(aws_sdk_transcribe::types::LanguageCode::EnUs)
+        .send()
+        .await
+    {
+        Ok(_) => CheckOutcome::Passed("job submission is allowed".to_string()),
+        Err(e) if is_access_denied(e.code()) => CheckOutcome::Denied,
+        Err(e) if is_missing_media(e.code(), e.message()) => {
+            CheckOutcome::Passed("job submission is allowed".to_string())
+        }
+        Err(e) => CheckOutcome::Failed(AppError::transcribe("Failed to start the probe job", e)),
+    };
+
+    // Remove the probe job in case Transcribe accepted it
+    let _ = transcribe_client
+        .delete_transcription_job()
+        .transcription_job_name(probe_name)
+        .send()
+        .await;
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(outcomes: Vec<CheckOutcome>) -> PreflightReport {
+        let mut report = PreflightReport::default();
+        for outcome in outcomes {
+            report.push("Check", "service:Action", outcome);
+        }
+        report
+    }
+
+    fn passed() -> CheckOutcome {
+        CheckOutcome::Passed("fine".to_string())
+    }
+
+    #[test]
+    fn failed_checks_keep_their_error_class() {
+        let result = report(vec![
+            passed(),
+            CheckOutcome::Failed(AppError::NotFound("Bucket my-bucket".to_string())),
+            CheckOutcome::Failed(AppError::Config("other".to_string())),
+        ])
+        .into_result();
+
+        assert_eq!(result.unwrap_err().class(), ErrorClass::NotFound);
+    }
+
+    #[test]
+    fn missing_permissions_are_an_auth_error() {
+        let result = report(vec![
+            passed(),
+            CheckOutcome::Failed(AppError::NotFound("Bucket my-bucket".to_string())),
+            CheckOutcome::Denied,
+        ])
+        .into_result();
+
+        let error = result.unwrap_err();
+        assert_eq!(error.class(), ErrorClass::Auth);
+        assert!(error.to_string().contains("service:Action"), "{}", error);
+        assert!(report(vec![passed(), passed()]).into_result().is_ok());
+    }
+
+    #[test]
+    fn only_missing_media_passes_the_start_job_probe() {
+        let message = "The S3 URI that you provided can't be accessed. Make sure that you have read permission and try your request again.";
+        assert!(is_missing_media(Some("BadRequestException"), Some(message)));
+        assert!(!is_missing_media(Some("BadRequestException"), Some("Invalid language code")));
+        assert!(!is_missing_media(Some("ConflictException"), Some("The requested job name already exists.")));
+    }
+
+    #[test]
+    fn every_run_probes_with_its_own_job_name() {
+        let name = probe_job_name();
+        assert!(name.starts_with(JOB_NAME_PREFIX), "{}", name);
+        assert_ne!(name, probe_job_name());
+    }
+}
@@ -0,0 +1,149 @@
+//! # Request Logging
+//!
+//! This module provides an SDK interceptor that logs every S3, Transcribe
+//! and STS call at debug level (`-v`), and every retry as a warning.
+//!
+//! Each log line carries the service, the operation, the HTTP status, the
+//! AWS request ID and how long the call took including retries, which is
+//! what AWS support asks for when a call misbehaves.
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::interceptors::context::{
+    BeforeSerializationInterceptorContextRef, BeforeTransmitInterceptorContextRef,
+    FinalizerInterceptorContextRef,
+};
+use aws_smithy_runtime_api::client::orchestrator::Metadata;
+use aws_smithy_runtime_api::client::retries::RequestAttempts;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use aws_smithy_types::retry::RetryConfig;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, warn};
+
+/// Interceptor that logs AWS calls with their request ID and timing, and their retries
+#[derive(Debug, Default)]
+pub struct RequestLogger;
+
+/// What is known about the current call across its attempts
+#[derive(Debug, Clone)]
+struct CallState {
+    started: Instant,
+    last_failure: Arc<Mutex<Option<String>>>, // Why the previous attempt failed
+}
+
+impl Storable for CallState {
+    type Storer = StoreReplace<Self>;
+}
+
+impl Intercept for RequestLogger {
+    fn name(&self) -> &'static str {
+        "RequestLogger"
+    }
+
+    fn read_before_execution(
+        &self,
+        _context: &BeforeSerializationInterceptorContextRef<'_>,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        cfg.interceptor_state().store_put(CallState {
+            started: Instant::now(),
+            last_failure: Arc::default(),
+        });
+        Ok(())
+    }
+
+    fn read_before_attempt(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let attempt = cfg.load::<RequestAttempts>().map_or(1, |a| a.attempts());
+        if attempt < 2 {
+            return Ok(());
+        }
+
+        let (service, operation) = call_name(cfg);
+        let max_attempts = cfg.load::<RetryConfig>().map_or(attempt, |c| c.max_attempts());
+        let last_failure = cfg
+            .load::<CallState>()
+            .and_then(|state| state.last_failure.lock().ok()?.clone())
+            .unwrap_or_else(|| "unknown error".to_string());
+        warn!(
+            "{} {} failed ({}), retrying (attempt {} of {})",
+            service, operation, last_failure, attempt, max_attempts
+        );
+        Ok(())
+    }
+
+    fn read_after_attempt(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(Err(error)) = context.output_or_error() else {
+            return Ok(());
+        };
+        let failure = match context.response() {
+            // JSON protocols name the error code in a header, e.g. `LimitExceededException`
+            Some(response) => match response.headers().get("x-amzn-errortype") {
+                Some(error_type) => format!(
+                    "HTTP {} {}",
+                    response.status().as_u16(),
+                    error_type.split(':').next().unwrap_or(error_type)
+                ),
+                None => format!("HTTP {}", response.status().as_u16()),
+            },
+            None => error.to_string(),
+        };
+        if let Some(state) = cfg.load::<CallState>()
+            && let Ok(mut last_failure) = state.last_failure.lock()
+        {
+            *last_failure = Some(failure);
+        }
+        Ok(())
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let elapsed_ms = cfg
+            .load::<CallState>()
+            .map(|state| state.started.elapsed().as_millis() as u64)
+            .unwrap_or_default();
+        let (service, operation) = call_name(cfg);
+
+        // No response means the call never reached the service, e.g. a network error
+        let response = context.response();
+        let status = response.map(|r| r.status().as_u16()).unwrap_or_default();
+        // S3 uses its own header name for the request ID
+        let request_id = response.and_then(|r| {
+            r.headers()
+                .get("x-amzn-requestid")
+                .or_else(|| r.headers().get("x-amz-request-id"))
+        });
+
+        debug!(
+            service = %service,
+            operation = %operation,
+            status,
+            request_id = %request_id.unwrap_or("-"),
+            elapsed_ms,
+            "AWS call finished"
+        );
+        Ok(())
+    }
+}
+
+/// Service and operation of the current call, e.g. `S3` and `PutObject`
+fn call_name(cfg: &ConfigBag) -> (String, String) {
+    cfg.load::<Metadata>()
+        .map(|metadata| (metadata.service().to_string(), metadata.name().to_string()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,465 @@
+//! # Transcribe Results
+//!
+//! This module turns the result JSON of an Amazon Transcribe job into a
+//! [`Transcript`], so that nothing else needs to know its layout. The
+//! Whisper backends write their results in the same layout.
+//!
+//! It provides:
+//! - The parts of the result JSON prepis reads
+//! - Words with their punctuation attached, timings and confidence
+//! - Segments from `audio_segments`, or from sentences and speaker changes
+//!   when the result has none
+//! - Speaker and channel labels
+
+use crate::error::AppError;
+use crate::models::{Segment, Transcript, Word};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Result JSON of a transcription job
+#[derive(Debug, Deserialize)]
+struct TranscribeResult {
+    #[serde(rename = "jobName", default)]
+    job_name: String,
+    results: Results,
+}
+
+#[derive(Debug, Deserialize)]
+struct Results {
+    #[serde(default)]
+    language_code: Option<String>, // Only with language identification
+    transcripts: Vec<TranscriptText>,
+    #[serde(default)]
+    items: Vec<Item>,
+    #[serde(default)]
+    speaker_labels: Option<SpeakerLabels>,
+    #[serde(default)]
+    channel_labels: Option<ChannelLabels>,
+    #[serde(default)]
+    audio_segments: Vec<AudioSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptText {
+    transcript: String,
+}
+
+/// A word or punctuation mark
+#[derive(Debug, Clone, Deserialize)]
+struct Item {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(rename = "type")]
+    kind: String, // `pronunciation` or `punctuation`
+    #[serde(default)]
+    start_time: Option<String>,
+    #[serde(default)]
+    end_time: Option<String>,
+    alternatives: Vec<Alternative>,
+    #[serde(default)]
+    speaker_label: Option<String>,
+    #[serde(default)]
+    channel_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Alternative {
+    content: String,
+    #[serde(default)]
+    confidence: Option<String>,
+}
+
+/// Speakers by time, in results of jobs with speaker labels
+#[derive(Debug, Deserialize)]
+struct SpeakerLabels {
+    #[serde(default)]
+    segments: Vec<SpeakerSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeakerSegment {
+    #[serde(default)]
+    items: Vec<SpeakerItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeakerItem {
+    start_time: String,
+    speaker_label: String,
+}
+
+/// Items per channel, in results of jobs with channel identification
+#[derive(Debug, Deserialize)]
+struct ChannelLabels {
+    #[serde(default)]
+    channels: Vec<Channel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Channel {
+    channel_label: String,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+/// A stretch of the transcript as Transcribe split it
+#[derive(Debug, Deserialize)]
+struct AudioSegment {
+    transcript: String,
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    items: Vec<u64>, // IDs of the items spoken in the segment
+    #[serde(default)]
+    speaker_label: Option<String>,
+    #[serde(default)]
+    channel_label: Option<String>,
+}
+
+/// A word of the result with where it was heard
+struct LabeledWord {
+    item_id: Option<u64>,
+    speaker: Option<String>,
+    channel: Option<u32>,
+    word: Word,
+}
+
+/// Turn an Amazon Transcribe result JSON document into a transcript
+///
+/// The result URI is unknown here and left empty, and the language is only
+/// known for jobs with language identification.
+pub fn parse_transcription_result(json_text: &str) -> Result<Transcript, AppError> {
+    let result: TranscribeResult = serde_json::from_str(json_text)
+        .map_err(|e| AppError::Transcribe(format!("Failed to parse transcription JSON: {}", e)))?;
+    let results = result.results;
+
+    let text = results
+        .transcripts
+        .first()
+        .map(|transcript| transcript.transcript.clone())
+        .ok_or_else(|| AppError::Transcribe("No transcript text found in results".to_string()))?;
+    if text.trim().is_empty() {
+        return Err(AppError::Transcribe(
+            "Transcription result is empty".to_string(),
+        ));
+    }
+
+    let words = labeled_words(&results);
+    let segments = if results.audio_segments.is_empty() {
+        segments_from_words(words)
+    } else {
+        segments_from_audio_segments(&results.audio_segments, words)
+    };
+
+    Ok(Transcript {
+        job_name: result.job_name,
+        language: results.language_code.unwrap_or_default(),
+        result_uri: String::new(),
+        text,
+        segments,
+        result_json: Some(json_text.to_string()),
+    })
+}
+
+/// Words in spoken order, with punctuation attached to the word before it
+fn labeled_words(results: &Results) -> Vec<LabeledWord> {
+    let speakers: HashMap<&str, &str> = results
+        .speaker_labels
+        .iter()
+        .flat_map(|labels| &labels.segments)
+        .flat_map(|segment| &segment.items)
+        .map(|item| (item.start_time.as_str(), item.speaker_label.as_str()))
+        .collect();
+
+    let mut words: Vec<LabeledWord> = Vec::new();
+    for item in items_in_order(results) {
+        let Some(alternative) = item.alternatives.first() else {
+            continue;
+        };
+
+        if item.kind == "punctuation" {
+            // Punctuation before the first word has nothing to attach to
+            if let Some(last) = words.last_mut() {
+                last.word.punctuation.push_str(&alternative.content);
+            }
+            continue;
+        }
+
+        let speaker = item.speaker_label.clone().or_else(|| {
+            let start_time = item.start_time.as_deref()?;
+            speakers.get(start_time).map(|speaker| speaker.to_string())
+        });
+        words.push(LabeledWord {
+            item_id: item.id,
+            speaker,
+            channel: item.channel_label.as_deref().and_then(parse_channel),
+            word: Word {
+                text: alternative.content.clone(),
+                start_seconds: parse_seconds(item.start_time.as_deref()),
+                end_seconds: parse_seconds(item.end_time.as_deref()),
+                confidence: alternative.confidence.as_deref().and_then(|c| c.parse().ok()),
+                punctuation: String::new(),
+            },
+        });
+    }
+    words
+}
+
+/// Items of the result in spoken order
+///
+/// Results of jobs with channel identification may only list the items per
+/// channel. Those are merged by time, keeping punctuation behind its word.
+fn items_in_order(results: &Results) -> Vec<Item> {
+    if !results.items.is_empty() {
+        return results.items.clone();
+    }
+
+    let mut items = Vec::new();
+    for channel in results.channel_labels.iter().flat_map(|labels| &labels.channels) {
+        let mut start_seconds = 0.0;
+        for item in &channel.items {
+            if item.start_time.is_some() {
+                start_seconds = parse_seconds(item.start_time.as_deref());
+            }
+            let mut item = item.clone();
+            item.channel_label.get_or_insert_with(|| channel.channel_label.clone());
+            items.push((start_seconds, item));
+        }
+    }
+    items.sort_by(|a, b| a.0.total_cmp(&b.0)); // Stable, punctuation stays in place
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Segments as Transcribe split the result, each with the words spoken in it
+///
+/// Words are matched by item ID, or by time for segments without item IDs.
+fn segments_from_audio_segments(
+    audio_segments: &[AudioSegment],
+    words: Vec<LabeledWord>,
+) -> Vec<Segment> {
+    let mut words = words.into_iter().peekable();
+
+    audio_segments
+        .iter()
+        .map(|audio_segment| {
+            let start_seconds = parse_seconds(Some(&audio_segment.start_time));
+            let end_seconds = parse_seconds(Some(&audio_segment.end_time));
+            let mut segment_words = Vec::new();
+            let mut speaker = audio_segment.speaker_label.clone();
+            let mut channel = audio_segment.channel_label.as_deref().and_then(parse_channel);
+
+            while let Some(next) = words.peek() {
+                let in_segment = match next.item_id {
+                    Some(id) if !audio_segment.items.is_empty() => audio_segment.items.contains(&id),
+                    _ => next.word.start_seconds < end_seconds,
+                };
+                if !in_segment {
+                    break;
+                }
+                let next = words.next().expect("peeked");
+                speaker = speaker.or(next.speaker);
+                channel = channel.or(next.channel);
+                segment_words.push(next.word);
+            }
+
+            Segment {
+                start_seconds,
+                end_seconds,
+                text: audio_segment.transcript.trim().to_string(),
+                speaker,
+                channel,
+                words: segment_words,
+            }
+        })
+        .collect()
+}
+
+/// Segments made of whole sentences, split where the speaker or channel changes
+fn segments_from_words(words: Vec<LabeledWord>) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut sentence_ended = true;
+
+    for labeled in words {
+        let continues = segments.last().is_some_and(|segment| {
+            !sentence_ended && segment.speaker == labeled.speaker && segment.channel == labeled.channel
+        });
+        sentence_ended = labeled.word.punctuation.contains(['.', '?', '!']);
+
+        match segments.last_mut() {
+            Some(segment) if continues => {
+                segment.end_seconds = labeled.word.end_seconds;
+                segment.text.push(' ');
+                segment.text.push_str(&labeled.word.written());
+                segment.words.push(labeled.word);
+            }
+            _ => segments.push(Segment {
+                start_seconds: labeled.word.start_seconds,
+                end_seconds: labeled.word.end_seconds,
+                text: labeled.word.written(),
+                speaker: labeled.speaker,
+                channel: labeled.channel,
+                words: vec![labeled.word],
+            }),
+        }
+    }
+    segments
+}
+
+/// Seconds from a Transcribe time, e.g. `"1.250"`
+fn parse_seconds(time: Option<&str>) -> f64 {
+    time.and_then(|time| time.parse().ok()).unwrap_or(0.0)
+}
+
+/// Channel number from a channel label, e.g. `1` for `ch_1`
+fn parse_channel(label: &str) -> Option<u32> {
+    label.strip_prefix("ch_")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn word(content: &str, start: &str, end: &str) -> serde_json::Value {
+        json!({
+            "type": "pronunciation",
+            "start_time": start,
+            "end_time": end,
+            "alternatives": [{ "content": content, "confidence": "0.9" }],
+        })
+    }
+
+    fn punctuation(content: &str) -> serde_json::Value {
+        json!({ "type": "punctuation", "alternatives": [{ "content": content }] })
+    }
+
+    #[test]
+    fn sentences_become_segments_with_punctuation_attached() {
+        let result = json!({
+            "jobName": "job",
+            "results": {
+                "transcripts": [{ "transcript": "Hello, world. Bye!" }],
+                "items": [
+                    word("Hello", "0.0", "0.5"),
+                    punctuation(","),
+                    word("world", "0.6", "1.0"),
+                    punctuation("."),
+                    word("Bye", "2.0", "2.4"),
+                    punctuation("!"),
+                ],
+            },
+        });
+
+        let transcript = parse_transcription_result(&result.to_string()).unwrap();
+
+        assert_eq!(transcript.job_name, "job");
+        assert_eq!(transcript.text, "Hello, world. Bye!");
+        let texts: Vec<_> = transcript.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Hello, world.", "Bye!"]);
+        let first = &transcript.segments[0];
+        assert_eq!((first.start_seconds, first.end_seconds), (0.0, 1.0));
+        assert_eq!(first.words[0].punctuation, ",");
+        assert_eq!(first.words[0].confidence, Some(0.9));
+    }
+
+    #[test]
+    fn speaker_changes_split_segments() {
+        let result = json!({
+            "results": {
+                "transcripts": [{ "transcript": "hi there hello" }],
+                "items": [word("hi", "0.0", "0.2"), word("there", "0.3", "0.5"), word("hello", "0.6", "0.9")],
+                "speaker_labels": { "segments": [
+                    { "items": [
+                        { "start_time": "0.0", "speaker_label": "spk_0" },
+                        { "start_time": "0.3", "speaker_label": "spk_0" },
+                    ]},
+                    { "items": [{ "start_time": "0.6", "speaker_label": "spk_1" }] },
+                ]},
+            },
+        });
+
+        let transcript = parse_transcription_result(&result.to_string()).unwrap();
+
+        let segments: Vec<_> = transcript
+            .segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.speaker.as_deref()))
+            .collect();
+        assert_eq!(segments, [("hi there", Some("spk_0")), ("hello", Some("spk_1"))]);
+    }
+
+    #[test]
+    fn audio_segments_keep_their_text_and_words() {
+        let mut first = word("One", "0.0", "0.4");
+        first["id"] = json!(0);
+        let mut dot = punctuation(".");
+        dot["id"] = json!(1);
+        let mut second = word("Two", "1.0", "1.3");
+        second["id"] = json!(2);
+        let result = json!({
+            "results": {
+                "transcripts": [{ "transcript": "One. Two" }],
+                "items": [first, dot, second],
+                "audio_segments": [
+                    { "id": 0, "transcript": "One.", "start_time": "0.0", "end_time": "0.9", "items": [0, 1] },
+                    { "id": 1, "transcript": "Two", "start_time": "0.9", "end_time": "1.5", "channel_label": "ch_1" },
+                ],
+            },
+        });
+
+        let transcript = parse_transcription_result(&result.to_string()).unwrap();
+
+        assert_eq!(transcript.segments.len(), 2);
+        assert_eq!(transcript.segments[0].words.len(), 1);
+        assert_eq!(transcript.segments[0].words[0].written(), "One.");
+        assert_eq!(transcript.segments[1].words[0].text, "Two");
+        assert_eq!(transcript.segments[1].channel, Some(1));
+    }
+
+    #[test]
+    fn channels_are_merged_by_time() {
+        let result = json!({
+            "results": {
+                "transcripts": [{ "transcript": "a b c" }],
+                "channel_labels": { "channels": [
+                    { "channel_label": "ch_0", "items": [word("a", "0.0", "0.1"), punctuation("."), word("c", "2.0", "2.1")] },
+                    { "channel_label": "ch_1", "items": [word("b", "1.0", "1.1")] },
+                ]},
+            },
+        });
+
+        let transcript = parse_transcription_result(&result.to_string()).unwrap();
+
+        let segments: Vec<_> = transcript
+            .segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.channel))
+            .collect();
+        assert_eq!(segments, [("a.", Some(0)), ("b", Some(1)), ("c", Some(0))]);
+    }
+
+    #[test]
+    fn empty_transcript_is_an_error() {
+        let result = json!({ "results": { "transcripts": [{ "transcript": "  " }], "items": [] } });
+
+        assert!(parse_transcription_result(&result.to_string()).is_err());
+    }
+
+    #[test]
+    fn transcript_json_round_trips() {
+        let result = json!({
+            "jobName": "job",
+            "results": {
+                "transcripts": [{ "transcript": "Hello, world." }],
+                "items": [word("Hello", "0.0", "0.5"), punctuation(","), word("world", "0.6", "1.0"), punctuation(".")],
+            },
+        });
+        let transcript = parse_transcription_result(&result.to_string()).unwrap();
+
+        let json_text = transcript.to_json().unwrap();
+
+        let expected = Transcript { result_json: None, ..transcript };
+        assert_eq!(Transcript::from_json(&json_text).unwrap(), expected);
+        assert!(Transcript::from_json(&result.to_string()).is_err());
+    }
+}
@@ -4,23 +4,73 @@
 //!
 //! It provides functionality for:
 //! - Starting transcription jobs
-//! - Monitoring job status with exponential backoff
-//! - Retrieving and parsing transcription results
+//! - Listing, inspecting and deleting existing jobs
+//! - Monitoring job status with jittered backoff and an overall deadline
+//! - Retrieving transcription results, retrying failed downloads
 //! - Error handling for transcription operations
 //!
 //! The module implements a robust polling mechanism to efficiently wait for
-//! transcription jobs to complete while providing user feedback.
+//! transcription jobs to complete while showing a live status line.
 
-use crate::error::AppError;
-use crate::models::TranscriptionStatus;
+use crate::aws::result::parse_transcription_result;
+use crate::aws::s3::{delete_file_from_s3, download_file_from_s3};
+use crate::error::{AppError, ErrorClass};
+use crate::events::{self, Event};
+use crate::file::{AudioTrack, MediaInfo};
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use crate::progress::PollProgress;
+use crate::utils::{format_duration, parse_s3_object_url};
+use aws_sdk_transcribe::config::http::HttpResponse;
+use aws_sdk_transcribe::config::retry::RetryConfig;
+use aws_sdk_transcribe::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_transcribe::operation::get_transcription_job::GetTranscriptionJobError;
+use aws_sdk_transcribe::types::{
+    ContentRedaction, LanguageCode, RedactionOutput, RedactionType, TranscriptionJob,
+    TranscriptionJobStatus, TranscriptionJobSummary,
+};
+use indicatif::MultiProgress;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Range of speaker labels supported by Amazon Transcribe
+const SPEAKER_LABEL_RANGE: std::ops::RangeInclusive<i32> = 2..=30;
+
+/// Sample rates accepted by Amazon Transcribe as a job hint, in Hz
+const SAMPLE_RATE_RANGE: std::ops::RangeInclusive<i32> = 8000..=48000;
 
 /// Start a transcription job with Amazon Transcribe
+///
+/// When an output location is given, Transcribe writes the result JSON to
+/// that bucket instead of its own service-managed one.
 pub async fn start_transcription_job(
     transcribe_client: &aws_sdk_transcribe::Client,
     job_name: &str,
     s3_uri: &str,
+    options: &JobOptions,
 ) -> Result<(), AppError> {
-    println!("🎙️  Starting transcription job: {}", job_name);
+    info!("🎙️  Starting transcription job: {}", job_name);
+    validate_job_options(options)?;
+    let output = options.output.as_ref();
+
+    // Speaker labels are only enabled when a speaker count is requested
+    let speaker_settings = options.max_speakers.map(|max_speakers| {
+        aws_sdk_transcribe::types::Settings::builder()
+            .show_speaker_labels(true)
+            .max_speaker_labels(max_speakers)
+            .build()
+    });
+
+    // Redacted transcripts replace names, addresses and the like with [PII]
+    let content_redaction = options
+        .redact_pii
+        .then(|| {
+            ContentRedaction::builder()
+                .redaction_type(RedactionType::Pii)
+                .redaction_output(RedactionOutput::Redacted)
+                .build()
+        })
+        .transpose()
+        .map_err(|e| AppError::Transcribe(format!("Invalid redaction settings: {}", e)))?;
 
     // Create the media object with the S3 URI
     let media = aws_sdk_transcribe::types::Media::builder()
@@ -32,35 +82,80 @@ pub async fn start_transcription_job(
         .start_transcription_job()
         .transcription_job_name(job_name)
         .media(media)
-        .language_code(aws_sdk_transcribe::types::LanguageCode::EnUs) // Default to English
+        .language_code(LanguageCode::from(options.language.as_str()))
+        .set_settings(speaker_settings)
+        .set_content_redaction(content_redaction)
+        .set_media_sample_rate_hertz(options.sample_rate)
+        .set_output_bucket_name(output.map(|o| o.bucket.clone()))
+        .set_output_key(output.and_then(|o| o.key.clone()))
         .send()
         .await
     {
         Ok(_) => {
-            println!("✅ Transcription job started successfully");
+            info!("✅ Transcription job started successfully");
             Ok(())
         }
-        Err(e) => Err(AppError::Transcribe(format!(
-            "🛑 Error: Failed to start transcription job: {}",
-            e
-        ))),
+        Err(e) => Err(AppError::transcribe("Failed to start transcription job", e)),
+    }
+}
+
+/// Check job options against what Amazon Transcribe accepts
+pub fn validate_job_options(options: &JobOptions) -> Result<(), AppError> {
+    if !LanguageCode::values().contains(&options.language.as_str()) {
+        return Err(AppError::Config(format!(
+            "Unsupported language code: {}. Supported codes: {}",
+            options.language,
+            LanguageCode::values().join(", ")
+        )));
     }
+
+    if let Some(max_speakers) = options.max_speakers
+        && !SPEAKER_LABEL_RANGE.contains(&max_speakers)
+    {
+        return Err(AppError::Config(format!(
+            "Speaker count must be between {} and {}, got {}",
+            SPEAKER_LABEL_RANGE.start(),
+            SPEAKER_LABEL_RANGE.end(),
+            max_speakers
+        )));
+    }
+
+    Ok(())
+}
+
+/// Sample rate to pass as a job hint, when the probed rate can be trusted
+///
+/// AAC headers may announce half the real rate of HE-AAC streams, and a wrong
+/// hint fails the job, so those are left for Transcribe to detect.
+pub fn sample_rate_hint(media_info: &MediaInfo) -> Option<i32> {
+    let AudioTrack::Found(stream) = &media_info.audio else {
+        return None;
+    };
+    let sample_rate = i32::try_from(stream.sample_rate?).ok()?;
+    (stream.codec != "aac" && SAMPLE_RATE_RANGE.contains(&sample_rate)).then_some(sample_rate)
 }
 
-/// Poll transcription job status with exponential backoff
+/// Poll transcription job status with jittered exponential backoff
+///
+/// Throttling and transient service or network errors are retried until the
+/// overall deadline in `options.timeout` has passed. In batch runs a shared
+/// [`MultiProgress`] gives every job its own status line.
 pub async fn poll_transcription_status(
     transcribe_client: &aws_sdk_transcribe::Client,
     job_name: &str,
+    options: &PollOptions,
+    multi: Option<&MultiProgress>,
 ) -> Result<TranscriptionStatus, AppError> {
-    println!("⏳ Polling transcription job status...");
+    let progress = match multi {
+        Some(multi) => PollProgress::with_multi(multi, job_name, options.estimate),
+        None => PollProgress::new(job_name, options.estimate),
+    };
 
-    let mut interval = 5; // Start with 5 seconds
-    let max_interval = 30; // Maximum 30 seconds
-    let max_attempts = 120; // Maximum attempts (10 minutes total)
-
-    for attempt in 1..=max_attempts {
-        println!("🔍 Checking status (attempt {}/{})", attempt, max_attempts);
+    let started = Instant::now();
+    let deadline = started + options.timeout;
+    let mut interval = options.interval;
 
+    loop {
         match transcribe_client
             .get_transcription_job()
             .transcription_job_name(job_name)
@@ -68,101 +163,428 @@ pub async fn poll_transcription_status(
             .await
         {
             Ok(response) => {
-                if let Some(job) = response.transcription_job() {
-                    match job.transcription_job_status() {
-                        Some(aws_sdk_transcribe::types::TranscriptionJobStatus::InProgress) => {
-                            println!("⏳ Job still in progress...");
-                        }
-                        Some(aws_sdk_transcribe::types::TranscriptionJobStatus::Completed) => {
-                            if let Some(transcript) = job.transcript() {
-                                if let Some(uri) = transcript.transcript_file_uri() {
-                                    println!("✅ Transcription job completed successfully");
-                                    return Ok(TranscriptionStatus::Completed(uri.to_string()));
-                                }
-                            }
+                let Some(job) = response.transcription_job() else {
+                    progress.finish("❌ Lost");
+                    return Err(AppError::NotFound(format!("Transcription job {}", job_name)));
+                };
+                if let Some(status) = job.transcription_job_status() {
+                    events::emit(Event::Status {
+                        job_name: job_name.to_string(),
+                        status: status.as_str().to_string(),
+                        elapsed_seconds: started.elapsed().as_secs(),
+                    });
+                }
+                match job.transcription_job_status() {
+                    Some(TranscriptionJobStatus::Completed) => {
+                        let Some(uri) = job
+                            .transcript()
+                            .and_then(|transcript| transcript.transcript_file_uri())
+                        else {
+                            progress.finish("❌ Failed");
                             return Err(AppError::Transcribe(
                                 "Job completed but no transcript URI found".to_string(),
                             ));
-                        }
-                        Some(aws_sdk_transcribe::types::TranscriptionJobStatus::Failed) => {
-                            let failure_reason = job
-                                .failure_reason()
-                                .unwrap_or("Unknown failure reason")
-                                .to_string();
-                            return Ok(TranscriptionStatus::Failed(failure_reason));
-                        }
-                        _ => {
-                            return Err(AppError::Transcribe("Unknown job status".to_string()));
-                        }
+                        };
+                        progress.finish("✅ Completed");
+                        events::emit(Event::Completed {
+                            job_name: job_name.to_string(),
+                            result_uri: uri.to_string(),
+                        });
+                        return Ok(TranscriptionStatus::Completed(uri.to_string()));
+                    }
+                    Some(TranscriptionJobStatus::Failed) => {
+                        progress.finish("❌ Failed");
+                        let failure_reason = job
+                            .failure_reason()
+                            .unwrap_or("Unknown failure reason")
+                            .to_string();
+                        events::emit(Event::Failed {
+                            job_name: job_name.to_string(),
+                            reason: failure_reason.clone(),
+                        });
+                        return Ok(TranscriptionStatus::Failed(failure_reason));
+                    }
+                    Some(status) => progress.set_status(status.as_str()),
+                    None => {
+                        progress.finish("❌ Lost");
+                        return Err(AppError::Transcribe("Unknown job status".to_string()));
                     }
-                } else {
-                    return Err(AppError::Transcribe("Job not found".to_string()));
                 }
             }
+            Err(e) if is_transient(&e) => {
+                progress.warn(&format!(
+                    "Failed to get status of {}, retrying: {}",
+                    job_name,
+                    DisplayErrorContext(&e)
+                ));
+            }
             Err(e) => {
-                return Err(AppError::Transcribe(format!(
-                    "Failed to get job status: {}",
-                    e
-                )));
+                progress.finish("❌ Lost");
+                return Err(AppError::transcribe("Failed to get job status", e));
             }
         }
 
-        // Wait before next attempt
-        if attempt < max_attempts {
-            println!("⏰ Waiting {} seconds before next check...", interval);
-            tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
-
-            // Increase interval with exponential backoff, but cap at max_interval
-            interval = std::cmp::min(interval * 2, max_interval);
+        let now = Instant::now();
+        if now >= deadline {
+            progress.finish("⏰ Gave up on");
+            return Err(AppError::Timeout(format!(
+                "Gave up waiting for job {} after {}. The job keeps running, fetch its result later with `prepis jobs fetch {}`",
+                job_name,
+                format_duration(options.timeout),
+                job_name
+            )));
         }
+
+        // Wait before next attempt, never sleeping past the deadline
+        tokio::time::sleep(jitter(interval).min(deadline - now)).await;
+
+        interval = next_interval(interval, options.max_interval);
     }
+}
+
+/// Rough processing time of a job for media of the given length
+///
+/// Amazon Transcribe usually needs a fraction of the media length plus some
+/// time in the queue, this is only meant to set expectations.
+pub fn estimate_processing_time(media_duration: Duration) -> Duration {
+    Duration::from_secs(30) + media_duration.mul_f64(0.4)
+}
+
+/// Double the polling interval, but never beyond `max_interval`
+fn next_interval(interval: Duration, max_interval: Duration) -> Duration {
+    interval.saturating_mul(2).min(max_interval)
+}
+
+/// Spread a delay by ±20% so that many clients do not poll in lockstep
+fn jitter(delay: Duration) -> Duration {
+    delay.mul_f64(0.8 + fastrand::f64() * 0.4)
+}
 
-    Err(AppError::Transcribe(
-        "Transcription job timed out".to_string(),
-    ))
+/// Whether a failed status request is worth retrying
+fn is_transient(error: &SdkError<GetTranscriptionJobError, HttpResponse>) -> bool {
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => true,
+        _ => {
+            let status = error.raw_response().map(|r| r.status().as_u16());
+            matches!(status, Some(429 | 500..=599))
+                || matches!(
+                    error.code(),
+                    Some("ThrottlingException" | "LimitExceededException" | "InternalFailureException")
+                )
+        }
+    }
 }
 
 /// Retrieve and parse transcription results from the result URI
-pub async fn get_transcription_result(result_uri: &str) -> Result<String, AppError> {
-    println!("📥 Retrieving transcription results...");
+pub async fn get_transcription_result(
+    s3_client: &aws_sdk_s3::Client,
+    result_uri: &str,
+) -> Result<Transcript, AppError> {
+    info!("📥 Retrieving transcription results...");
+
+    let json_text = download_transcription_json(result_uri, &retry_config(s3_client)).await?;
+    let mut transcript = parse_transcription_result(&json_text)?;
+    transcript.result_uri = result_uri.to_string();
+
+    info!("✅ Transcription results retrieved successfully");
+    Ok(transcript)
+}
+
+/// Download the raw result JSON from a presigned result URI
+///
+/// The URI is fetched outside of the SDK, so failed downloads are retried
+/// here following the same retry configuration as the AWS clients. Errors
+/// such as an expired link fail right away.
+pub async fn download_transcription_json(
+    result_uri: &str,
+    retry_config: &RetryConfig,
+) -> Result<String, AppError> {
+    let max_attempts = retry_config.max_attempts();
+    let mut backoff = retry_config.initial_backoff();
 
+    for attempt in 1.. {
+        match fetch_transcription_json(result_uri).await {
+            Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                let delay = jitter(backoff);
+                warn!(
+                    "{}, retrying in {} (attempt {} of {})",
+                    e,
+                    format_duration(delay),
+                    attempt + 1,
+                    max_attempts
+                );
+                tokio::time::sleep(delay).await;
+                backoff = (backoff * 2).min(retry_config.max_backoff());
+            }
+            result => return result,
+        }
+    }
+    unreachable!("the retry loop only ends by returning")
+}
+
+/// Fetch the result JSON once
+async fn fetch_transcription_json(result_uri: &str) -> Result<String, AppError> {
     // Make HTTP request to get the transcription JSON
-    let response = reqwest::get(result_uri).await.map_err(|e| {
-        AppError::Transcribe(format!("Failed to fetch transcription results: {}", e))
+    let response = reqwest::get(result_uri)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|source| AppError::Http {
+            context: "Failed to fetch transcription results".to_string(),
+            source: source.without_url(), // Keep the presigned URL out of messages
+        })?;
+
+    let json_text = response.text().await.map_err(|source| AppError::Http {
+        context: "Failed to read transcription response".to_string(),
+        source: source.without_url(),
     })?;
 
-    if !response.status().is_success() {
-        return Err(AppError::Transcribe(format!(
-            "Failed to fetch transcription results: HTTP {}",
-            response.status()
-        )));
+    Ok(json_text)
+}
+
+/// Download the raw result JSON for a job, wherever Transcribe stored it
+///
+/// Results in the service bucket come with a presigned URL, while results in
+/// our own output bucket are plain S3 object URLs that need to go through S3.
+pub async fn download_result_json(
+    s3_client: &aws_sdk_s3::Client,
+    s3_endpoint: Option<&str>,
+    result_uri: &str,
+) -> Result<String, AppError> {
+    if !result_uri.contains("X-Amz-Signature")
+        && let Some((bucket, key)) = parse_s3_object_url(result_uri, s3_endpoint)
+    {
+        return download_file_from_s3(s3_client, &bucket, &key).await;
     }
 
-    let json_text = response.text().await.map_err(|e| {
-        AppError::Transcribe(format!("Failed to read transcription response: {}", e))
-    })?;
+    download_transcription_json(result_uri, &retry_config(s3_client)).await
+}
+
+/// Whether a failed result download is worth another attempt
+fn is_retryable(error: &AppError) -> bool {
+    matches!(
+        error.class(),
+        ErrorClass::Throttled | ErrorClass::Service | ErrorClass::Timeout
+    )
+}
+
+/// Retry configuration the AWS clients were built with
+fn retry_config(s3_client: &aws_sdk_s3::Client) -> RetryConfig {
+    s3_client
+        .config()
+        .retry_config()
+        .cloned()
+        .unwrap_or_else(RetryConfig::standard)
+}
+
+/// Retrieve and parse transcription results that Transcribe wrote to our own bucket
+pub async fn get_transcription_result_from_s3(
+    s3_client: &aws_sdk_s3::Client,
+    bucket: &str,
+    key: &str,
+) -> Result<Transcript, AppError> {
+    info!("📥 Retrieving transcription results from s3://{}/{}", bucket, key);
+
+    let json_text = download_file_from_s3(s3_client, bucket, key).await?;
+    let mut transcript = parse_transcription_result(&json_text)?;
+    transcript.result_uri = format!("s3://{}/{}", bucket, key);
+
+    info!("✅ Transcription results retrieved successfully");
+    Ok(transcript)
+}
+
+/// Retrieve the transcript of a completed job
+///
+/// Results in our own output bucket are read straight from S3. They stay
+/// there until [`remove_result`] is called, so the transcript can be fetched
+/// again when saving it fails.
+pub async fn retrieve_transcript(
+    s3_client: &aws_sdk_s3::Client,
+    s3_endpoint: Option<&str>,
+    result_uri: &str,
+    in_output_bucket: bool,
+) -> Result<Transcript, AppError> {
+    if !in_output_bucket {
+        return get_transcription_result(s3_client, result_uri).await;
+    }
+
+    let (bucket, key) = result_location(result_uri, s3_endpoint)?;
+    get_transcription_result_from_s3(s3_client, &bucket, &key).await
+}
+
+/// Remove a result JSON from our own output bucket, unless it should be kept as an archive
+pub async fn remove_result(
+    s3_client: &aws_sdk_s3::Client,
+    s3_endpoint: Option<&str>,
+    result_uri: &str,
+    keep_output: bool,
+) -> Result<(), AppError> {
+    let (bucket, key) = result_location(result_uri, s3_endpoint)?;
+    if keep_output {
+        info!("🗄️  Result JSON archived at: s3://{}/{}", bucket, key);
+        Ok(())
+    } else {
+        delete_file_from_s3(s3_client, &bucket, &key).await
+    }
+}
+
+/// Bucket and key of a result in our own output bucket
+fn result_location(
+    result_uri: &str,
+    s3_endpoint: Option<&str>,
+) -> Result<(String, String), AppError> {
+    parse_s3_object_url(result_uri, s3_endpoint).ok_or_else(|| {
+        AppError::Transcribe(format!(
+            "Could not determine S3 location of the result: {}",
+            result_uri
+        ))
+    })
+}
+
+/// Get the full details of a transcription job
+pub async fn get_transcription_job(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    job_name: &str,
+) -> Result<TranscriptionJob, AppError> {
+    transcribe_client
+        .get_transcription_job()
+        .transcription_job_name(job_name)
+        .send()
+        .await
+        .map_err(|e| AppError::transcribe(format!("Failed to get job {}", job_name), e))?
+        .transcription_job
+        .ok_or_else(|| AppError::NotFound(format!("Transcription job {}", job_name)))
+}
+
+/// Look up a transcription job, returning `None` when it no longer exists
+pub async fn find_transcription_job(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    job_name: &str,
+) -> Result<Option<TranscriptionJob>, AppError> {
+    match transcribe_client
+        .get_transcription_job()
+        .transcription_job_name(job_name)
+        .send()
+        .await
+    {
+        Ok(output) => Ok(output.transcription_job),
+        Err(e) => {
+            // Only an unknown job name counts, other bad requests say nothing about the job
+            let error = AppError::transcribe(format!("Failed to get job {}", job_name), e);
+            match error.class() {
+                ErrorClass::NotFound => Ok(None),
+                _ => Err(error),
+            }
+        }
+    }
+}
+
+/// List transcription jobs whose name starts with the given prefix, newest first
+pub async fn list_transcription_jobs(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    name_prefix: &str,
+    status: Option<TranscriptionJobStatus>,
+    limit: usize,
+) -> Result<Vec<TranscriptionJobSummary>, AppError> {
+    let mut jobs = Vec::new();
+    let mut next_token = None;
+
+    loop {
+        let response = transcribe_client
+            .list_transcription_jobs()
+            .job_name_contains(name_prefix)
+            .set_status(status.clone())
+            .set_next_token(next_token)
+            .max_results(100)
+            .send()
+            .await
+            .map_err(|e| AppError::transcribe("Failed to list transcription jobs", e))?;
+
+        // The API only supports "contains", so make sure the name really starts with the prefix
+        jobs.extend(
+            response
+                .transcription_job_summaries()
+                .iter()
+                .filter(|job| {
+                    job.transcription_job_name()
+                        .is_some_and(|name| name.starts_with(name_prefix))
+                })
+                .cloned(),
+        );
+
+        next_token = response.next_token().map(str::to_string);
+        if next_token.is_none() || jobs.len() >= limit {
+            break;
+        }
+    }
+
+    jobs.truncate(limit);
+    Ok(jobs)
+}
+
+/// Delete a transcription job and its service-managed result
+pub async fn delete_transcription_job(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    job_name: &str,
+) -> Result<(), AppError> {
+    transcribe_client
+        .delete_transcription_job()
+        .transcription_job_name(job_name)
+        .send()
+        .await
+        .map_err(|e| AppError::transcribe(format!("Failed to delete job {}", job_name), e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::error::ErrorMetadata;
+
+    fn status_error(status: u16, code: &str) -> SdkError<GetTranscriptionJobError, HttpResponse> {
+        let metadata = ErrorMetadata::builder().code(code).build();
+        let response = HttpResponse::new(status.try_into().unwrap(), SdkBody::empty());
+        SdkError::service_error(GetTranscriptionJobError::generic(metadata), response)
+    }
+
+    #[test]
+    fn interval_doubles_up_to_the_maximum() {
+        let max_interval = Duration::from_secs(60);
+        let mut interval = Duration::from_secs(5);
+        let mut intervals = vec![interval.as_secs()];
+        for _ in 0..5 {
+            interval = next_interval(interval, max_interval);
+            intervals.push(interval.as_secs());
+        }
+
+        assert_eq!(intervals, [5, 10, 20, 40, 60, 60]);
+        assert_eq!(next_interval(Duration::MAX, max_interval), max_interval);
+    }
 
-    // Parse the JSON to extract the transcript text
-    let json_value: serde_json::Value = serde_json::from_str(&json_text)
-        .map_err(|e| AppError::Transcribe(format!("Failed to parse transcription JSON: {}", e)))?;
-
-    // Navigate the JSON structure to extract the transcript text
-    let transcript_text = json_value
-        .get("results")
-        .and_then(|results| results.get("transcripts"))
-        .and_then(|transcripts| transcripts.as_array())
-        .and_then(|arr| arr.first())
-        .and_then(|transcript| transcript.get("transcript"))
-        .and_then(|text| text.as_str())
-        .ok_or_else(|| AppError::Transcribe("No transcript text found in results".to_string()))?;
-    // TODO: This 👆 can be cleaner as a Struct
-
-    if transcript_text.trim().is_empty() {
-        return Err(AppError::Transcribe(
-            "Transcription result is empty".to_string(),
-        ));
-    }
-
-    println!("✅ Transcription results retrieved successfully");
-    Ok(transcript_text.to_string())
+    #[test]
+    fn jitter_stays_within_a_fifth_of_the_delay() {
+        let delay = Duration::from_secs(10);
+
+        for _ in 0..1000 {
+            let jittered = jitter(delay);
+            assert!((Duration::from_secs(8)..=Duration::from_secs(12)).contains(&jittered));
+        }
+    }
+
+    #[test]
+    fn throttling_and_server_errors_are_retried() {
+        assert!(is_transient(&status_error(400, "ThrottlingException")));
+        assert!(is_transient(&status_error(400, "LimitExceededException")));
+        assert!(is_transient(&status_error(429, "TooManyRequests")));
+        assert!(is_transient(&status_error(503, "ServiceUnavailable")));
+        assert!(!is_transient(&status_error(400, "BadRequestException")));
+        assert!(!is_transient(&status_error(403, "AccessDeniedException")));
+    }
+
+    #[test]
+    fn processing_time_grows_with_the_media() {
+        assert_eq!(estimate_processing_time(Duration::ZERO), Duration::from_secs(30));
+        assert_eq!(estimate_processing_time(Duration::from_secs(600)), Duration::from_secs(270));
+    }
 }
@@ -0,0 +1,146 @@
+//! # Amazon Transcribe Backend
+//!
+//! This module implements [`TranscriptionBackend`] on top of Amazon
+//! Transcribe: the media is uploaded to a temporary key in S3, transcribed
+//! by a batch job and removed again once the transcript is in.
+//!
+//! It uses the building blocks of the [`aws`](crate::aws) module, so all of
+//! its retries, logging and error handling apply.
+
+use super::{BackendKind, PreparedMedia, StagedMedia, SubmittedJob, TranscriptionBackend};
+use crate::aws::{self, AwsClients};
+use crate::error::AppError;
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use crate::utils;
+use async_trait::async_trait;
+use tracing::info;
+
+/// Transcribes media with Amazon Transcribe, staging it in an S3 bucket
+pub struct AmazonTranscribe {
+    clients: AwsClients,
+    bucket: String,
+    keep_output: bool, // Keep the result JSON in the output bucket
+}
+
+impl AmazonTranscribe {
+    /// Create the backend for the given clients and staging bucket
+    pub fn new(clients: AwsClients, bucket: impl Into<String>) -> Self {
+        Self {
+            clients,
+            bucket: bucket.into(),
+            keep_output: false,
+        }
+    }
+
+    /// Keep the result JSON in the output bucket instead of deleting it
+    pub fn keep_output(mut self, keep_output: bool) -> Self {
+        self.keep_output = keep_output;
+        self
+    }
+
+    /// AWS clients the backend uses, e.g. for checks before submitting
+    pub fn clients(&self) -> &AwsClients {
+        &self.clients
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for AmazonTranscribe {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Aws
+    }
+
+    async fn prepare_media(&self, media: &PreparedMedia) -> Result<StagedMedia, AppError> {
+        let key = utils::generate_s3_key(&media.path);
+        let s3_uri =
+            aws::upload_file_to_s3(&self.clients.s3_client, &self.bucket, &key, &media.path)
+                .await?;
+        info!("📍 S3 URI: {}", s3_uri);
+
+        Ok(StagedMedia::S3 {
+            bucket: self.bucket.clone(),
+            key,
+        })
+    }
+
+    async fn submit(
+        &self,
+        media: &PreparedMedia,
+        staged: &StagedMedia,
+        options: &JobOptions,
+    ) -> Result<SubmittedJob, AppError> {
+        let Some(s3_uri) = staged.s3_uri() else {
+            return Err(AppError::Config(
+                "Amazon Transcribe can only read media from S3".to_string(),
+            ));
+        };
+
+        let mut options = options.clone();
+        options.sample_rate = media.info.as_ref().and_then(aws::transcribe::sample_rate_hint);
+
+        let job_name = utils::generate_job_name(&media.path);
+        aws::start_transcription_job(&self.clients.transcribe_client, &job_name, &s3_uri, &options)
+            .await?;
+
+        Ok(SubmittedJob {
+            job_name,
+            staged: staged.clone(),
+            options,
+            media_duration: media.duration(),
+        })
+    }
+
+    async fn poll(
+        &self,
+        job: &SubmittedJob,
+        options: &PollOptions,
+    ) -> Result<TranscriptionStatus, AppError> {
+        aws::poll_transcription_status(&self.clients.transcribe_client, &job.job_name, options, None)
+            .await
+    }
+
+    async fn fetch_transcript(
+        &self,
+        job: &SubmittedJob,
+        result_uri: &str,
+    ) -> Result<Transcript, AppError> {
+        let in_output_bucket = job.options.output.is_some();
+        let transcript = aws::transcribe::retrieve_transcript(
+            &self.clients.s3_client,
+            self.clients.s3_endpoint(),
+            result_uri,
+            in_output_bucket,
+        )
+        .await?;
+
+        Ok(Transcript {
+            job_name: job.job_name.clone(),
+            language: job.options.language.clone(),
+            result_uri: result_uri.to_string(),
+            ..transcript
+        })
+    }
+
+    async fn remove_result(&self, job: &SubmittedJob, result_uri: &str) -> Result<(), AppError> {
+        // Results in the service bucket expire on their own
+        if job.options.output.is_none() {
+            return Ok(());
+        }
+        aws::transcribe::remove_result(
+            &self.clients.s3_client,
+            self.clients.s3_endpoint(),
+            result_uri,
+            self.keep_output,
+        )
+        .await
+    }
+
+    async fn cleanup(&self, staged: &StagedMedia) -> Result<(), AppError> {
+        match staged {
+            StagedMedia::S3 { bucket, key } => {
+                aws::delete_file_from_s3(&self.clients.s3_client, bucket, key).await
+            }
+            StagedMedia::File(_) => Ok(()),
+        }
+    }
+}
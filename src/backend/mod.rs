@@ -0,0 +1,149 @@
+//! # Transcription Backends
+//!
+//! This module defines [`TranscriptionBackend`], the interface between the
+//! transcription pipeline and the service that does the actual work.
+//!
+//! It provides:
+//! - The backend trait with the steps every service goes through: prepare
+//!   the media, submit it, wait for the result and fetch the transcript
+//! - The types passed between those steps
+//! - [`BackendKind`], which selects a backend with `--backend` or the
+//!   `backend` setting
+//! - Amazon Transcribe as the default backend (see [`amazon`])
+//! - Self-hosted OpenAI-compatible Whisper servers (see [`whisper_api`])
+//! - A local whisper.cpp binary for offline use (see [`whisper_cpp`])
+//!
+//! A new engine only needs to implement the trait; the
+//! [`Transcriber`](crate::transcriber::Transcriber) and every output work
+//! the same for all of them.
+
+pub mod amazon;
+mod whisper;
+pub mod whisper_api;
+pub mod whisper_cpp;
+
+pub use amazon::AmazonTranscribe;
+pub use whisper_api::WhisperApi;
+pub use whisper_cpp::WhisperCpp;
+
+use crate::error::AppError;
+use crate::file::MediaInfo;
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The transcription services prepis can use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    /// Amazon Transcribe, with the media staged in S3
+    #[default]
+    Aws,
+    /// An OpenAI-compatible Whisper server, with the media posted straight from disk
+    WhisperApi,
+    /// A local whisper.cpp binary, which needs no network at all
+    WhisperCpp,
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// A media file that passed validation and is ready to be submitted
+#[derive(Debug, Clone)]
+pub struct PreparedMedia {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub info: Option<MediaInfo>, // What the container headers say, when readable
+}
+
+impl PreparedMedia {
+    /// Length of the media, when known
+    pub fn duration(&self) -> Option<Duration> {
+        self.info.as_ref().and_then(|info| info.duration)
+    }
+}
+
+/// Where a backend put the media for its service to read
+#[derive(Debug, Clone, PartialEq)]
+pub enum StagedMedia {
+    S3 { bucket: String, key: String }, // Uploaded temporarily
+    File(PathBuf),                      // Read straight from disk
+}
+
+impl StagedMedia {
+    /// URI of the staged media, e.g. `s3://bucket/key`
+    pub fn uri(&self) -> String {
+        match self {
+            StagedMedia::S3 { bucket, key } => format!("s3://{}/{}", bucket, key),
+            StagedMedia::File(path) => path.display().to_string(),
+        }
+    }
+
+    /// URI of media staged in S3
+    pub fn s3_uri(&self) -> Option<String> {
+        matches!(self, StagedMedia::S3 { .. }).then(|| self.uri())
+    }
+}
+
+/// A transcription job that has been started
+#[derive(Debug, Clone)]
+pub struct SubmittedJob {
+    pub job_name: String,
+    pub staged: StagedMedia,
+    pub options: JobOptions, // As the job was started
+    pub media_duration: Option<Duration>,
+}
+
+/// A service that turns media into a transcript
+///
+/// The steps are called in order for every file. Services that answer right
+/// away can do all the work in [`submit`](Self::submit) and report the job
+/// as completed on the first [`poll`](Self::poll).
+#[async_trait]
+pub trait TranscriptionBackend: Send + Sync {
+    /// Which backend this is
+    fn kind(&self) -> BackendKind;
+
+    /// Make the media available to the service, e.g. by uploading it
+    async fn prepare_media(&self, media: &PreparedMedia) -> Result<StagedMedia, AppError>;
+
+    /// Start transcribing staged media
+    async fn submit(
+        &self,
+        media: &PreparedMedia,
+        staged: &StagedMedia,
+        options: &JobOptions,
+    ) -> Result<SubmittedJob, AppError>;
+
+    /// Wait until a job has finished, successfully or not
+    async fn poll(
+        &self,
+        job: &SubmittedJob,
+        options: &PollOptions,
+    ) -> Result<TranscriptionStatus, AppError>;
+
+    /// Fetch the transcript of a completed job from its result URI
+    async fn fetch_transcript(
+        &self,
+        job: &SubmittedJob,
+        result_uri: &str,
+    ) -> Result<Transcript, AppError>;
+
+    /// Remove the result of a job once its transcript has been saved
+    async fn remove_result(&self, _job: &SubmittedJob, _result_uri: &str) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    /// Remove whatever `prepare_media` left behind
+    async fn cleanup(&self, staged: &StagedMedia) -> Result<(), AppError>;
+}
@@ -0,0 +1,193 @@
+//! # Whisper Results
+//!
+//! This module holds what the Whisper backends share: the `verbose_json`
+//! result of a Whisper transcription and its conversion into the shape of an
+//! Amazon Transcribe result.
+//!
+//! It provides:
+//! - The segments and words of a Whisper transcription
+//! - The conversion into Transcribe `items` and `audio_segments`
+//! - A check of the job options, since Whisper cannot do everything
+//!   Amazon Transcribe can
+//! - `FinishedJobs`, which keeps results until the transcript is fetched,
+//!   since Whisper answers as soon as the media is submitted
+
+use super::{BackendKind, SubmittedJob};
+use crate::aws::result::parse_transcription_result;
+use crate::error::AppError;
+use crate::models::{JobOptions, Transcript, TranscriptionStatus};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// A Whisper transcription in the `verbose_json` format of the OpenAI API
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct WhisperTranscription {
+    pub text: String,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub words: Vec<Word>, // Only with `timestamp_granularities[]=word`
+}
+
+/// A segment of the transcript, usually a sentence
+#[derive(Debug, Deserialize)]
+pub(crate) struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    #[serde(default)]
+    pub words: Vec<Word>, // Where whisper.cpp puts word timestamps
+}
+
+/// A single recognized word
+#[derive(Debug, Deserialize)]
+pub(crate) struct Word {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+    pub probability: Option<f64>,
+}
+
+/// Language code Whisper expects, e.g. `en` for `en-US`
+pub(crate) fn whisper_language(language: &str) -> String {
+    language.split('-').next().unwrap_or(language).to_lowercase()
+}
+
+/// Turn a Whisper transcription into the shape of an Amazon Transcribe result
+///
+/// Words become `pronunciation` items with their punctuation split off into
+/// `punctuation` items, and segments become `audio_segments`.
+pub(crate) fn to_transcribe_result(job_name: &str, transcription: &WhisperTranscription) -> Value {
+    let words: Vec<&Word> = if transcription.words.is_empty() {
+        transcription.segments.iter().flat_map(|segment| &segment.words).collect()
+    } else {
+        transcription.words.iter().collect()
+    };
+
+    let mut items = Vec::new();
+    for word in words {
+        let text = word.word.trim();
+        let spoken = text.trim_end_matches(|c: char| c.is_ascii_punctuation() && c != '\'');
+        let punctuation = &text[spoken.len()..];
+
+        if !spoken.is_empty() {
+            let mut alternative = json!({ "content": spoken });
+            if let Some(probability) = word.probability {
+                alternative["confidence"] = json!(format!("{:.3}", probability));
+            }
+            items.push(json!({
+                "id": items.len(),
+                "type": "pronunciation",
+                "start_time": format_seconds(word.start),
+                "end_time": format_seconds(word.end),
+                "alternatives": [alternative],
+            }));
+        }
+        if !punctuation.is_empty() {
+            items.push(json!({
+                "id": items.len(),
+                "type": "punctuation",
+                "alternatives": [{ "content": punctuation }],
+            }));
+        }
+    }
+
+    let audio_segments: Vec<Value> = transcription
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(id, segment)| {
+            json!({
+                "id": id,
+                "transcript": segment.text.trim(),
+                "start_time": format_seconds(segment.start),
+                "end_time": format_seconds(segment.end),
+            })
+        })
+        .collect();
+
+    json!({
+        "jobName": job_name,
+        "status": "COMPLETED",
+        "results": {
+            "transcripts": [{ "transcript": transcription.text.trim() }],
+            "items": items,
+            "audio_segments": audio_segments,
+        },
+    })
+}
+
+/// Transcript of a job from its result in the Transcribe format
+pub(crate) fn transcript_from_result(
+    job: &SubmittedJob,
+    result_uri: &str,
+    result: &str,
+) -> Result<Transcript, AppError> {
+    Ok(Transcript {
+        job_name: job.job_name.clone(),
+        language: job.options.language.clone(),
+        result_uri: result_uri.to_string(),
+        ..parse_transcription_result(result)?
+    })
+}
+
+/// Check the job options against what Whisper can do
+///
+/// Whisper has no PII redaction, which must not be skipped silently. Other
+/// options that only make sense for Amazon Transcribe are ignored.
+pub(crate) fn check_options(kind: BackendKind, options: &JobOptions) -> Result<(), AppError> {
+    if options.redact_pii {
+        return Err(AppError::Config(format!(
+            "PII redaction is not supported by the {} backend",
+            kind
+        )));
+    }
+    if options.max_speakers.is_some() {
+        warn!("Speaker labels are not supported by the {} backend, ignoring --speakers", kind);
+    }
+    if options.output.is_some() {
+        warn!("The {} backend keeps no result JSON, ignoring the output bucket", kind);
+    }
+    Ok(())
+}
+
+/// Seconds as Transcribe writes them, e.g. `"1.250"`
+fn format_seconds(seconds: f64) -> String {
+    format!("{:.3}", seconds)
+}
+
+/// Result JSON of finished jobs by job name, until their transcript is fetched
+#[derive(Debug, Default)]
+pub(crate) struct FinishedJobs(Mutex<HashMap<String, String>>);
+
+impl FinishedJobs {
+    /// Keep the result of a job that just finished
+    pub fn insert(&self, job_name: &str, result: &Value) {
+        self.lock().insert(job_name.to_string(), result.to_string());
+    }
+
+    /// Status of a job, which is always completed once it is known
+    pub fn status(&self, job_name: &str, result_uri: String) -> Result<TranscriptionStatus, AppError> {
+        if !self.lock().contains_key(job_name) {
+            return Err(AppError::NotFound(format!("Transcription job {}", job_name)));
+        }
+        Ok(TranscriptionStatus::Completed(result_uri))
+    }
+
+    /// Hand out the transcript of a job, forgetting its result
+    pub fn take_transcript(&self, job: &SubmittedJob, result_uri: &str) -> Result<Transcript, AppError> {
+        let result = self
+            .lock()
+            .remove(&job.job_name)
+            .ok_or_else(|| AppError::NotFound(format!("Transcription job {}", job.job_name)))?;
+        transcript_from_result(job, result_uri, &result)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        // A panic while holding the lock cannot leave the map half-written
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,203 @@
+//! # Whisper API Backend
+//!
+//! This module implements [`TranscriptionBackend`] for servers that speak the
+//! OpenAI `/v1/audio/transcriptions` API, such as a self-hosted whisper.cpp
+//! or faster-whisper server. The media is posted straight from disk, so
+//! nothing ever reaches AWS and no S3 bucket is needed.
+//!
+//! The server answers with `verbose_json`, whose segments and words are
+//! turned into an Amazon Transcribe style result. Everything that reads a
+//! Transcribe result therefore works the same for both backends.
+
+use super::whisper::{
+    FinishedJobs, WhisperTranscription, check_options, to_transcribe_result, whisper_language,
+};
+use super::{BackendKind, PreparedMedia, StagedMedia, SubmittedJob, TranscriptionBackend};
+use crate::error::AppError;
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use crate::utils;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::Duration;
+use tracing::info;
+
+/// Model requested from the server when none is configured
+pub const DEFAULT_WHISPER_MODEL: &str = "whisper-1";
+
+/// Environment variable with the API key for the Whisper server, if it needs one
+pub const WHISPER_API_KEY_ENV: &str = "PREPIS_WHISPER_API_KEY";
+
+/// Path of the transcription endpoint below the base URL
+const TRANSCRIPTIONS_PATH: &str = "/v1/audio/transcriptions";
+
+/// Transcribes media with an OpenAI-compatible Whisper server
+pub struct WhisperApi {
+    http: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    timeout: Duration, // For the whole request, the server answers once it is done
+    finished: FinishedJobs,
+}
+
+impl WhisperApi {
+    /// Create the backend for the server at the given base URL, e.g. `http://localhost:8080`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: DEFAULT_WHISPER_MODEL.to_string(),
+            api_key: None,
+            timeout: PollOptions::default().timeout,
+            finished: FinishedJobs::default(),
+        }
+    }
+
+    /// Model to request, e.g. `large-v3` (default: `whisper-1`)
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Bearer token sent with every request
+    pub fn api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// How long to wait for the server to answer (default: one hour)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Full URL of the transcription endpoint
+    pub fn endpoint(&self) -> String {
+        format!("{}{}", self.base_url, TRANSCRIPTIONS_PATH)
+    }
+
+    /// Post a file to the server and return its `verbose_json` answer
+    async fn request_transcription(
+        &self,
+        media: &PreparedMedia,
+        options: &JobOptions,
+    ) -> Result<WhisperTranscription, AppError> {
+        let file = tokio::fs::File::open(&media.path).await?;
+        let file_name = media
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "media".to_string());
+        let part = reqwest::multipart::Part::stream_with_length(file, media.size_bytes)
+            .file_name(file_name);
+
+        let form = reqwest::multipart::Form::new()
+            .part("file", part)
+            .text("model", self.model.clone())
+            .text("language", whisper_language(&options.language))
+            .text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "segment")
+            .text("timestamp_granularities[]", "word");
+
+        let mut request = self
+            .http
+            .post(self.endpoint())
+            .timeout(self.timeout)
+            .multipart(form);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|source| AppError::WhisperApi {
+            context: "Failed to send the media".to_string(),
+            source,
+        })?;
+
+        // Keep the server's own explanation of a rejected request
+        if let Err(source) = response.error_for_status_ref() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::WhisperApi {
+                context: server_message(&body)
+                    .unwrap_or_else(|| "The transcription request failed".to_string()),
+                source,
+            });
+        }
+
+        let body = response.text().await.map_err(|source| AppError::WhisperApi {
+            context: "Failed to read the transcription response".to_string(),
+            source,
+        })?;
+        serde_json::from_str(&body).map_err(|e| {
+            AppError::Transcribe(format!("Failed to parse the Whisper server response: {}", e))
+        })
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for WhisperApi {
+    fn kind(&self) -> BackendKind {
+        BackendKind::WhisperApi
+    }
+
+    async fn prepare_media(&self, media: &PreparedMedia) -> Result<StagedMedia, AppError> {
+        Ok(StagedMedia::File(media.path.clone()))
+    }
+
+    async fn submit(
+        &self,
+        media: &PreparedMedia,
+        staged: &StagedMedia,
+        options: &JobOptions,
+    ) -> Result<SubmittedJob, AppError> {
+        check_options(self.kind(), options)?;
+
+        // The server answers once the whole file is transcribed
+        info!("📤 Sending {} to {}", media.path.display(), self.endpoint());
+        let job_name = utils::generate_job_name(&media.path);
+        let response = self.request_transcription(media, options).await?;
+        self.finished.insert(&job_name, &to_transcribe_result(&job_name, &response));
+
+        Ok(SubmittedJob {
+            job_name,
+            staged: staged.clone(),
+            options: options.clone(),
+            media_duration: media.duration(),
+        })
+    }
+
+    async fn poll(
+        &self,
+        job: &SubmittedJob,
+        _options: &PollOptions,
+    ) -> Result<TranscriptionStatus, AppError> {
+        self.finished.status(&job.job_name, self.endpoint())
+    }
+
+    async fn fetch_transcript(
+        &self,
+        job: &SubmittedJob,
+        result_uri: &str,
+    ) -> Result<Transcript, AppError> {
+        self.finished.take_transcript(job, result_uri)
+    }
+
+    async fn cleanup(&self, _staged: &StagedMedia) -> Result<(), AppError> {
+        // Nothing was copied anywhere
+        Ok(())
+    }
+}
+
+/// The error message in an OpenAI-style error body, if there is one
+fn server_message(body: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    value
+        .pointer("/error/message")
+        .or_else(|| value.get("detail"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
@@ -0,0 +1,406 @@
+//! # whisper.cpp Backend
+//!
+//! This module implements [`TranscriptionBackend`] on top of a locally
+//! installed [whisper.cpp](https://github.com/ggml-org/whisper.cpp) binary,
+//! so media can be transcribed without any network access.
+//!
+//! whisper.cpp reads 16 kHz WAV files, so any other media is first converted
+//! with `ffmpeg` into a temporary file. The JSON written by whisper.cpp is
+//! turned into an Amazon Transcribe style result, which makes saving and
+//! printing work the same as with every other backend.
+//!
+//! Converted media and results go to private temporary directories of their
+//! own, so concurrent runs never see each other's files.
+
+use super::whisper::{
+    FinishedJobs, Segment, WhisperTranscription, Word, check_options, to_transcribe_result,
+    whisper_language,
+};
+use super::{BackendKind, PreparedMedia, StagedMedia, SubmittedJob, TranscriptionBackend};
+use crate::error::AppError;
+use crate::file::{AudioTrack, MediaInfo};
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use crate::utils;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::Mutex;
+use tempfile::TempDir;
+use tokio::process::Command;
+use tracing::{debug, info};
+
+/// Binary looked up on the `PATH` when none is configured
+pub const DEFAULT_WHISPER_CPP_BINARY: &str = "whisper-cli";
+
+/// Sample rate whisper.cpp expects its input in
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Transcribes media with a local whisper.cpp binary
+pub struct WhisperCpp {
+    binary: PathBuf,
+    model: PathBuf,
+    ffmpeg: PathBuf,
+    converted: Mutex<HashMap<PathBuf, TempDir>>, // Directory of each converted file
+    finished: FinishedJobs,
+}
+
+impl WhisperCpp {
+    /// Create the backend for a model file, e.g. `ggml-base.en.bin`
+    pub fn new(model: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: PathBuf::from(DEFAULT_WHISPER_CPP_BINARY),
+            model: model.into(),
+            ffmpeg: PathBuf::from("ffmpeg"),
+            converted: Mutex::default(),
+            finished: FinishedJobs::default(),
+        }
+    }
+
+    /// whisper.cpp binary to run, e.g. `./build/bin/whisper-cli` or the older `main`
+    pub fn binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// ffmpeg binary used to convert media, `ffmpeg` on the `PATH` by default
+    pub fn ffmpeg(mut self, ffmpeg: impl Into<PathBuf>) -> Self {
+        self.ffmpeg = ffmpeg.into();
+        self
+    }
+
+    /// Convert media into a 16 kHz mono WAV file for whisper.cpp
+    async fn convert(&self, media: &PreparedMedia) -> Result<PathBuf, AppError> {
+        let work_dir = work_dir()?;
+        let wav = work_dir.path().join(format!("{}.wav", utils::generate_job_name(&media.path)));
+
+        info!("🔄 Converting {} to 16 kHz WAV with ffmpeg...", media.path.display());
+        let mut command = Command::new(&self.ffmpeg);
+        command
+            .args(["-nostdin", "-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(&media.path)
+            .args(["-vn", "-ac", "1", "-ar", &WHISPER_SAMPLE_RATE.to_string()])
+            .args(["-c:a", "pcm_s16le"])
+            .arg(&wav);
+
+        let output = run(command, &self.ffmpeg, "Install ffmpeg to convert media for whisper.cpp").await?;
+        if !output.status.success() {
+            return Err(AppError::File(format!(
+                "ffmpeg could not convert {}: {}",
+                media.path.display(),
+                last_line(&output.stderr)
+            )));
+        }
+        self.lock_converted().insert(wav.clone(), work_dir);
+        Ok(wav)
+    }
+
+    fn lock_converted(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, TempDir>> {
+        // A panic while holding the lock cannot leave the map half-written
+        self.converted.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for WhisperCpp {
+    fn kind(&self) -> BackendKind {
+        BackendKind::WhisperCpp
+    }
+
+    async fn prepare_media(&self, media: &PreparedMedia) -> Result<StagedMedia, AppError> {
+        if !self.model.is_file() {
+            return Err(AppError::Config(format!(
+                "whisper.cpp model not found: {}",
+                self.model.display()
+            )));
+        }
+
+        if is_whisper_wav(media.info.as_ref()) {
+            return Ok(StagedMedia::File(media.path.clone()));
+        }
+        Ok(StagedMedia::File(self.convert(media).await?))
+    }
+
+    async fn submit(
+        &self,
+        media: &PreparedMedia,
+        staged: &StagedMedia,
+        options: &JobOptions,
+    ) -> Result<SubmittedJob, AppError> {
+        check_options(self.kind(), options)?;
+        let StagedMedia::File(wav) = staged else {
+            return Err(AppError::Config(
+                "whisper.cpp can only read media from a local file".to_string(),
+            ));
+        };
+
+        let job_name = utils::generate_job_name(&media.path);
+        let result_dir = work_dir()?;
+        let result_path = result_dir.path().join(format!("{}.json", job_name));
+
+        // whisper.cpp adds the `.json` extension to the output name itself
+        info!("🖥️ Transcribing with whisper.cpp on this machine, this may take a while...");
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--model")
+            .arg(&self.model)
+            .arg("--file")
+            .arg(wav)
+            .args(["--language", &whisper_language(&options.language)])
+            .args(["--output-json-full", "--no-prints", "--output-file"])
+            .arg(result_path.with_extension(""));
+
+        let output = run(command, &self.binary, "Install whisper.cpp or pass --whisper-cpp-path").await?;
+        if !output.status.success() {
+            return Err(AppError::WhisperCpp(format!(
+                "Transcription failed ({}): {}",
+                output.status,
+                last_line(&output.stderr)
+            )));
+        }
+
+        let json_text = tokio::fs::read_to_string(&result_path).await?;
+        let output: WhisperCppOutput = serde_json::from_str(&json_text).map_err(|e| {
+            AppError::WhisperCpp(format!("Failed to parse the result: {}", e))
+        })?;
+        self.finished.insert(&job_name, &to_transcribe_result(&job_name, &output.into_transcription()));
+        result_dir.close()?;
+
+        Ok(SubmittedJob {
+            job_name,
+            staged: staged.clone(),
+            options: options.clone(),
+            media_duration: media.duration(),
+        })
+    }
+
+    async fn poll(
+        &self,
+        job: &SubmittedJob,
+        _options: &PollOptions,
+    ) -> Result<TranscriptionStatus, AppError> {
+        // whisper.cpp has finished by the time `submit` returns
+        self.finished.status(&job.job_name, self.binary.display().to_string())
+    }
+
+    async fn fetch_transcript(
+        &self,
+        job: &SubmittedJob,
+        result_uri: &str,
+    ) -> Result<Transcript, AppError> {
+        self.finished.take_transcript(job, result_uri)
+    }
+
+    async fn cleanup(&self, staged: &StagedMedia) -> Result<(), AppError> {
+        // Only remove what `prepare_media` converted, never the input itself
+        let work_dir = match staged {
+            StagedMedia::File(path) => self.lock_converted().remove(path),
+            _ => None,
+        };
+        if let Some(work_dir) = work_dir {
+            work_dir.close()?;
+        }
+        Ok(())
+    }
+}
+
+/// Result file written by whisper.cpp with `--output-json-full`
+#[derive(Debug, Deserialize)]
+struct WhisperCppOutput {
+    #[serde(default)]
+    transcription: Vec<WhisperCppSegment>,
+}
+
+/// A segment of the whisper.cpp result
+#[derive(Debug, Deserialize)]
+struct WhisperCppSegment {
+    offsets: Offsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperCppToken>,
+}
+
+/// A token of a segment, usually part of a word
+#[derive(Debug, Deserialize)]
+struct WhisperCppToken {
+    text: String,
+    offsets: Offsets,
+    p: Option<f64>,
+}
+
+/// Start and end of a segment or token, in milliseconds
+#[derive(Debug, Deserialize)]
+struct Offsets {
+    from: u64,
+    to: u64,
+}
+
+impl WhisperCppOutput {
+    /// The result in the format of the OpenAI API
+    ///
+    /// Tokens are joined into words, a new word starting at every token with a
+    /// leading space. A word is as confident as its least certain token.
+    fn into_transcription(self) -> WhisperTranscription {
+        let mut text = String::new();
+        let mut segments = Vec::new();
+
+        for segment in self.transcription {
+            let mut words: Vec<Word> = Vec::new();
+            for token in segment.tokens.iter().filter(|token| !is_special_token(&token.text)) {
+                let start = millis_to_seconds(token.offsets.from);
+                let end = millis_to_seconds(token.offsets.to);
+                match words.last_mut() {
+                    Some(word) if !token.text.starts_with(' ') => {
+                        word.word.push_str(&token.text);
+                        word.end = end;
+                        word.probability = match (word.probability, token.p) {
+                            (Some(a), Some(b)) => Some(a.min(b)),
+                            (a, b) => a.or(b),
+                        };
+                    }
+                    _ => words.push(Word {
+                        word: token.text.clone(),
+                        start,
+                        end,
+                        probability: token.p,
+                    }),
+                }
+            }
+
+            text.push_str(&segment.text);
+            segments.push(Segment {
+                start: millis_to_seconds(segment.offsets.from),
+                end: millis_to_seconds(segment.offsets.to),
+                text: segment.text,
+                words,
+            });
+        }
+
+        WhisperTranscription {
+            text,
+            segments,
+            words: Vec::new(),
+        }
+    }
+}
+
+/// Whether media can be passed to whisper.cpp as it is
+fn is_whisper_wav(info: Option<&MediaInfo>) -> bool {
+    matches!(
+        info,
+        Some(MediaInfo { container: "wav", audio: AudioTrack::Found(stream), .. })
+            if stream.codec == "pcm" && stream.sample_rate == Some(WHISPER_SAMPLE_RATE)
+    )
+}
+
+/// Markers such as `[_BEG_]`, `[_TT_42]` or `<|endoftext|>` that are no speech
+fn is_special_token(text: &str) -> bool {
+    let text = text.trim();
+    (text.starts_with("[_") && text.ends_with(']')) || (text.starts_with("<|") && text.ends_with("|>"))
+}
+
+fn millis_to_seconds(millis: u64) -> f64 {
+    millis as f64 / 1000.0
+}
+
+/// A new private directory for the files of one job, removed when dropped
+fn work_dir() -> Result<TempDir, AppError> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("prepis-whisper-cpp-");
+    // Other local users must not read the media or plant files next to it
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o700));
+    Ok(builder.tempdir()?)
+}
+
+/// Run a command to completion, explaining a missing binary
+async fn run(mut command: Command, binary: &Path, install_hint: &str) -> Result<Output, AppError> {
+    debug!("Running {:?}", command.as_std());
+    command.kill_on_drop(true).output().await.map_err(|e| match e.kind() {
+        ErrorKind::NotFound => AppError::Config(format!(
+            "{} was not found. {}",
+            binary.display(),
+            install_hint
+        )),
+        _ => AppError::Io(e),
+    })
+}
+
+/// Last non-empty line a command wrote, usually its error message
+fn last_line(output: &[u8]) -> String {
+    String::from_utf8_lossy(output)
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("no output")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aws::result::parse_transcription_result;
+    use crate::file::probe::AudioStream;
+
+    /// A result recorded with `whisper-cli --output-json-full`
+    const RESULT: &str = include_str!("../../tests/fixtures/whisper-cpp-full.json");
+
+    #[test]
+    fn tokens_are_joined_into_words() {
+        let output: WhisperCppOutput = serde_json::from_str(RESULT).unwrap();
+        let transcription = output.into_transcription();
+
+        let words: Vec<_> = transcription
+            .segments
+            .iter()
+            .flat_map(|segment| &segment.words)
+            .map(|word| (word.word.as_str(), word.start, word.end, word.probability))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                (" Hello,", 0.0, 0.7, Some(0.903)), // As confident as the least certain token
+                (" world.", 0.9, 1.9, Some(0.804)),
+                (" It's", 2.6, 3.0, Some(0.934)), // The second token has no probability
+                (" fine.", 3.1, 3.7, Some(0.887)),
+            ]
+        );
+        assert_eq!(transcription.text, " Hello, world. It's fine.");
+        assert_eq!((transcription.segments[1].start, transcription.segments[1].end), (2.5, 4.0));
+
+        let result = to_transcribe_result("talk", &transcription);
+        assert_eq!(parse_transcription_result(&result.to_string()).unwrap().text, "Hello, world. It's fine.");
+    }
+
+    #[test]
+    fn markers_are_special_tokens() {
+        for token in ["[_BEG_]", " [_TT_125]", "<|endoftext|>", "<|en|>"] {
+            assert!(is_special_token(token), "{}", token);
+        }
+        for token in [" Hello", "[music]", " <3", "[_"] {
+            assert!(!is_special_token(token), "{}", token);
+        }
+    }
+
+    #[test]
+    fn only_16_khz_pcm_wav_is_passed_as_it_is() {
+        let info = |container, codec: &str, sample_rate| MediaInfo {
+            container,
+            duration: None,
+            audio: AudioTrack::Found(AudioStream {
+                codec: codec.to_string(),
+                channels: Some(1),
+                sample_rate: Some(sample_rate),
+            }),
+        };
+
+        assert!(is_whisper_wav(Some(&info("wav", "pcm", 16_000))));
+        assert!(!is_whisper_wav(Some(&info("wav", "pcm", 44_100))));
+        assert!(!is_whisper_wav(Some(&info("wav", "mulaw", 16_000))));
+        assert!(!is_whisper_wav(Some(&info("mp3", "mp3", 16_000))));
+        assert!(!is_whisper_wav(None));
+    }
+}
@@ -0,0 +1,395 @@
+//! # Command-Line Interface
+//!
+//! This module defines the command-line arguments accepted by Prepis.
+//!
+//! Running `prepis <VIDEO_FILE> <S3_BUCKET>` transcribes a file, which is the
+//! default behaviour and the same as `prepis transcribe <VIDEO_FILE> <S3_BUCKET>`.
+//! Job management and maintenance tasks are exposed as subcommands.
+//!
+//! Options left out on the command line are filled in from the configuration
+//! files described in [`prepis::config`].
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use prepis::backend::BackendKind;
+use prepis::render::TranscriptFormat;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "prepis")]
+#[command(about = "A CLI tool to transcribe video files using Amazon Transcribe")]
+#[command(version = "0.1.0")]
+#[command(subcommand_negates_reqs = true)]
+#[command(override_usage = "prepis [OPTIONS] <VIDEO_FILE> [S3_BUCKET] [OUTPUT_FILE]\n       prepis [OPTIONS] <COMMAND>")]
+pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub transcribe: TranscribeArgs,
+
+    #[command(flatten)]
+    pub aws: AwsArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Named preset from the configuration files
+    #[arg(long, global = true, help = "Use a named preset from the configuration files")]
+    pub preset: Option<String>,
+}
+
+/// AWS connection settings shared by every command
+#[derive(Args)]
+pub struct AwsArgs {
+    /// AWS region to use for S3 and Transcribe
+    #[arg(long, global = true, help = "AWS region (defaults to the configured region)")]
+    pub region: Option<String>,
+
+    /// Named profile from the AWS config files
+    #[arg(long, global = true, help = "AWS profile to use for credentials and settings")]
+    pub profile: Option<String>,
+
+    /// Custom endpoint for every AWS service
+    #[arg(long, global = true, help = "Custom endpoint URL for all AWS services (e.g. LocalStack)")]
+    pub endpoint_url: Option<String>,
+
+    /// Custom endpoint for S3 only
+    #[arg(long, global = true, help = "Custom endpoint URL for S3 (e.g. MinIO)")]
+    pub s3_endpoint_url: Option<String>,
+
+    /// Custom endpoint for Amazon Transcribe only
+    #[arg(long, global = true, help = "Custom endpoint URL for Amazon Transcribe")]
+    pub transcribe_endpoint_url: Option<String>,
+
+    /// How often a failed AWS call is tried before giving up
+    #[arg(
+        long,
+        global = true,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Attempts per AWS call, including the first one [default: 5]"
+    )]
+    pub max_attempts: Option<u32>,
+}
+
+/// How much prepis prints and how it looks, shared by every command
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose", help = "Only print warnings and errors")]
+    pub quiet: bool,
+
+    /// Print debug logs, twice for trace logs
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, help = "Print debug logs with AWS request IDs and timings (-vv for more)")]
+    pub verbose: u8,
+
+    /// Leave out emojis and other decoration
+    #[arg(long, visible_alias = "plain", global = true, help = "Plain output without emojis")]
+    pub no_emoji: bool,
+}
+
+/// Subcommands available next to the default transcription workflow
+#[derive(Subcommand)]
+pub enum Command {
+    /// Transcribe a video file (the default when no subcommand is given)
+    Transcribe(Box<TranscribeArgs>),
+
+    /// Manage transcription jobs started by prepis
+    #[command(subcommand)]
+    Jobs(JobsCommand),
+
+    /// Create and configure an S3 bucket for use with prepis
+    InitBucket(InitBucketArgs),
+
+    /// Check jobs submitted with --detach and save the finished ones
+    Collect(CollectArgs),
+
+    /// Render an existing result JSON as text, subtitles or a document, without running a job
+    Render(RenderArgs),
+
+    /// Check that the AWS credentials have every permission prepis needs
+    Doctor(DoctorArgs),
+
+    /// Inspect the configuration files
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// Subcommands of `prepis config`
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective settings and where each value came from
+    Show,
+}
+
+/// Subcommands of `prepis jobs`
+#[derive(Subcommand)]
+pub enum JobsCommand {
+    /// List transcription jobs started by prepis
+    List(JobsListArgs),
+
+    /// Show the details of a transcription job
+    Status(JobNameArgs),
+
+    /// Download and render the result of a completed job
+    Fetch(JobsFetchArgs),
+
+    /// Delete a transcription job
+    Delete(JobNameArgs),
+}
+
+/// Job states that can be used to filter `prepis jobs list`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum JobStatusFilter {
+    Queued,
+    InProgress,
+    Completed,
+    Failed,
+}
+
+/// Formats a job result can be rendered in
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ResultFormat {
+    /// Plain transcript text
+    #[default]
+    Text,
+    /// Raw Amazon Transcribe result JSON
+    Json,
+}
+
+/// Formats the final result of a transcription run can be printed in
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable transcript
+    #[default]
+    Text,
+    /// One JSON document with the job details and transcript
+    Json,
+}
+
+/// Formats of the progress event stream
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum EventsFormat {
+    /// One JSON object per line on stdout
+    Ndjson,
+}
+
+/// Arguments for `prepis jobs list`
+#[derive(Args)]
+pub struct JobsListArgs {
+    /// Only show jobs in this state
+    #[arg(long, value_enum, help = "Only show jobs with this status")]
+    pub status: Option<JobStatusFilter>,
+
+    /// Maximum number of jobs to show
+    #[arg(long, default_value_t = 20, help = "Maximum number of jobs to show")]
+    pub limit: usize,
+}
+
+/// Arguments for job subcommands that only take a job name
+#[derive(Args)]
+pub struct JobNameArgs {
+    /// Name of the transcription job
+    #[arg(help = "Name of the transcription job")]
+    pub name: String,
+}
+
+/// Arguments for `prepis jobs fetch`
+#[derive(Args)]
+pub struct JobsFetchArgs {
+    /// Name of the transcription job
+    #[arg(help = "Name of the transcription job")]
+    pub name: String,
+
+    /// File to write the result to
+    #[arg(short, long, help = "Write the result to this file instead of printing it")]
+    pub output: Option<PathBuf>,
+
+    /// Format to render the result in
+    #[arg(long, value_enum, default_value_t, help = "Format of the result")]
+    pub format: ResultFormat,
+
+    /// Replace the output file when it exists
+    #[arg(long, requires = "output", help = "Replace the output file if it already exists")]
+    pub force: bool,
+}
+
+/// Arguments for `prepis render`
+#[derive(Args)]
+pub struct RenderArgs {
+    /// Result JSON to render
+    #[arg(help = "Result JSON from Amazon Transcribe or prepis: a local file, a URL or an s3:// URI")]
+    pub source: String,
+
+    /// File to write the rendered transcript to
+    #[arg(short, long, help = "Write the transcript to this file instead of printing it")]
+    pub output: Option<PathBuf>,
+
+    /// Format to render the transcript in
+    #[arg(long, value_enum, help = "Format of the transcript (default: from the --output extension, or txt)")]
+    pub format: Option<TranscriptFormat>,
+
+    /// Replace the output file when it exists
+    #[arg(long, requires = "output", help = "Replace the output file if it already exists")]
+    pub force: bool,
+}
+
+/// Arguments for the default transcription workflow
+#[derive(Args)]
+pub struct TranscribeArgs {
+    /// Path to the video file to transcribe
+    #[arg(required = true, help = "Path to the video file")]
+    pub video_file: Option<PathBuf>,
+
+    /// S3 bucket name to use for temporary file storage, or the output file with other backends
+    #[arg(help = "S3 bucket name for temporary storage (defaults to the configured bucket). With the whisper-api and whisper-cpp backends, the output file instead")]
+    pub s3_bucket: Option<String>,
+
+    /// Output filename for the transcription
+    #[arg(help = "File to write the transcript to, in the format of its extension (default: the video file name with .txt, next to it)")]
+    pub output_file: Option<PathBuf>,
+
+    /// More files to write the transcript to
+    #[arg(short = 'o', long = "output", value_name = "FILE", help = "Also write the transcript to this file, in the format of its extension (can be repeated)")]
+    pub outputs: Vec<PathBuf>,
+
+    /// Formats to write, named after the media file
+    #[arg(long, value_enum, value_delimiter = ',', help = "Write the transcript in these formats, e.g. txt,json,srt, named after the media file")]
+    pub formats: Vec<TranscriptFormat>,
+
+    /// Directory for the output files
+    #[arg(long, help = "Directory for the files named after the video and for relative --output paths (default: the directory of the video file)")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Name of the files of --formats
+    #[arg(long, help = "File name for --formats, using {stem}, {lang} and {ext} (default: {stem}.{ext})")]
+    pub output_template: Option<String>,
+
+    /// Replace output files that exist
+    #[arg(long, help = "Replace output files that already exist instead of stopping")]
+    pub force: bool,
+
+    /// Always print the transcript
+    #[arg(long, overrides_with = "no_print", help = "Print the transcript to stdout, even in a terminal where it is also saved to a file")]
+    pub print: bool,
+
+    /// Never print the transcript
+    #[arg(long, overrides_with = "print", help = "Do not print the transcript to stdout, only save it")]
+    pub no_print: bool,
+
+    /// Language spoken in the media
+    #[arg(long, help = "Language code of the media, e.g. en-US (default: en-US)")]
+    pub language: Option<String>,
+
+    /// Maximum number of speakers to tell apart
+    #[arg(long, help = "Enable speaker labels for up to this many speakers (2-30)")]
+    pub speakers: Option<i32>,
+
+    /// S3 bucket where Amazon Transcribe writes the result JSON
+    #[arg(long, help = "S3 bucket for the Transcribe result JSON (instead of the service bucket)")]
+    pub output_bucket: Option<String>,
+
+    /// Object key for the result JSON inside the output bucket
+    #[arg(long, help = "Object key for the result JSON in the output bucket")]
+    pub output_key: Option<String>,
+
+    /// Redact personally identifiable information from the transcript
+    #[arg(long, overrides_with = "no_redact_pii", help = "Replace names, addresses and other personal data with [PII] in the transcript")]
+    pub redact_pii: bool,
+
+    /// Keep personal data, even when a preset redacts it
+    #[arg(long, overrides_with = "redact_pii", help = "Do not redact personal data, even when the config or preset does")]
+    pub no_redact_pii: bool,
+
+    /// Keep the result JSON in the output bucket after it has been retrieved
+    #[arg(long, overrides_with = "no_keep_output", help = "Keep the result JSON in the output bucket as an archive")]
+    pub keep_output: bool,
+
+    /// Delete the result JSON, even when a preset keeps it
+    #[arg(long, overrides_with = "keep_output", help = "Delete the result JSON once retrieved, even when the config or preset keeps it")]
+    pub no_keep_output: bool,
+
+    /// Skip the read-only permission checks done before uploading
+    #[arg(long, help = "Skip the read-only AWS checks done before uploading (credentials, bucket access, Transcribe access)")]
+    pub skip_preflight: bool,
+
+    /// Delay before the second status check
+    #[arg(long, value_parser = humantime::parse_duration, help = "Initial delay between job status checks, e.g. 5s (default: 5s)")]
+    pub poll_interval: Option<Duration>,
+
+    /// Upper bound for the delay between status checks
+    #[arg(long, value_parser = humantime::parse_duration, help = "Maximum delay between job status checks, e.g. 1m (default: 30s)")]
+    pub max_interval: Option<Duration>,
+
+    /// Overall time to wait for the job
+    #[arg(long, value_parser = humantime::parse_duration, help = "Stop waiting for the job after this long, e.g. 2h (default: 1h, longer for long media)")]
+    pub timeout: Option<Duration>,
+
+    /// Format of the final result on stdout
+    #[arg(long, value_enum, default_value_t, help = "Format of the result printed to stdout")]
+    pub output_format: OutputFormat,
+
+    /// Emit progress events on stdout
+    #[arg(long, value_enum, help = "Emit structured progress events to stdout")]
+    pub events: Option<EventsFormat>,
+
+    /// Service that does the transcription
+    #[arg(long, value_enum, help = "Transcription backend to use (default: aws)")]
+    pub backend: Option<BackendKind>,
+
+    /// Base URL of the server for the whisper-api backend
+    #[arg(long, help = "Base URL of an OpenAI-compatible Whisper server, e.g. http://localhost:8080")]
+    pub whisper_url: Option<String>,
+
+    /// Model requested from the Whisper server
+    #[arg(long, help = "Model to request from the Whisper server (default: whisper-1)")]
+    pub whisper_model: Option<String>,
+
+    /// whisper.cpp binary for the whisper-cpp backend
+    #[arg(long, help = "Path of the whisper.cpp binary, e.g. whisper-cli or main (default: whisper-cli on the PATH)")]
+    pub whisper_cpp_path: Option<PathBuf>,
+
+    /// Model file for the whisper-cpp backend
+    #[arg(long, help = "Path of the whisper.cpp model file, e.g. ggml-base.en.bin")]
+    pub whisper_cpp_model: Option<PathBuf>,
+
+    /// Exit after starting the job and pick up the result with `prepis collect`
+    #[arg(long, help = "Start the job and exit, collect the result later with `prepis collect`")]
+    pub detach: bool,
+
+    /// Only validate and estimate, without uploading or starting anything
+    #[arg(long, conflicts_with = "detach", help = "Validate the input and estimate cost and time without uploading anything or starting a job (the input may be a directory)")]
+    pub dry_run: bool,
+}
+
+/// Arguments for `prepis collect`
+#[derive(Args)]
+pub struct CollectArgs {
+    /// Wait for running jobs instead of leaving them for the next run
+    #[arg(long, help = "Wait for running jobs to finish before collecting")]
+    pub wait: bool,
+
+    /// Overall time to wait for running jobs
+    #[arg(long, value_parser = humantime::parse_duration, requires = "wait", help = "Stop waiting after this long, e.g. 2h (default: 1h)")]
+    pub timeout: Option<Duration>,
+}
+
+/// Arguments for `prepis init-bucket`
+#[derive(Args)]
+pub struct InitBucketArgs {
+    /// Name of the bucket to create or configure
+    #[arg(help = "Name of the S3 bucket to create or configure")]
+    pub name: String,
+
+    /// Skip the Amazon Transcribe read check
+    #[arg(long, help = "Skip verifying that Amazon Transcribe can read from the bucket")]
+    pub skip_verify: bool,
+}
+
+/// Arguments for `prepis doctor`
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Bucket to check access to
+    #[arg(help = "S3 bucket to check (bucket checks are skipped when omitted)")]
+    pub bucket: Option<String>,
+}
@@ -0,0 +1,20 @@
+//! # Bucket Command
+//!
+//! This module implements `prepis init-bucket`, which prepares an S3 bucket
+//! for use as temporary storage.
+
+use prepis::aws::{self, AwsOptions};
+use crate::cli::InitBucketArgs;
+use prepis::error::AppError;
+use tracing::info;
+
+/// Create and configure the bucket given on the command line
+pub async fn run_init_bucket(
+    args: InitBucketArgs,
+    aws_options: &AwsOptions,
+) -> Result<(), AppError> {
+    info!("🪣 Preparing bucket: {}", args.name);
+
+    let aws_clients = aws::create_aws_clients(aws_options).await?;
+    aws::init_bucket(&aws_clients, &args.name, !args.skip_verify).await
+}
@@ -0,0 +1,269 @@
+//! # Collect Command
+//!
+//! This module implements `prepis collect`, which picks up the results of
+//! jobs submitted with `--detach`.
+//!
+//! For every pending job it:
+//! - Saves the transcript of finished jobs and cleans up their temporary files
+//! - Reports failed or vanished jobs and cleans up after them as well
+//! - Leaves jobs that are still running in the store for the next run, or
+//!   with `--wait` polls them all side by side until they finish
+//!
+//! The command exits with an error when any job failed, so it can be run
+//! from cron and have failures show up in its mail. Jobs submitted while it
+//! runs are left for the next run.
+
+use prepis::aws::{self, AwsClients, AwsOptions};
+use crate::cli::CollectArgs;
+use prepis::error::AppError;
+use prepis::file;
+use prepis::models::PollOptions;
+use prepis::pending::{PendingJob, PendingStore};
+use prepis::render;
+use aws_sdk_transcribe::types::TranscriptionJobStatus;
+use futures_util::future::join_all;
+use indicatif::MultiProgress;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// What happened to a pending job during this run
+enum Outcome {
+    Collected,
+    Running,
+    Failed(String), // Contains the failure reason
+}
+
+/// Check every pending job and collect the finished ones
+pub async fn run_collect(args: CollectArgs, aws_options: &AwsOptions) -> Result<(), AppError> {
+    let store = PendingStore::load()?;
+    if store.jobs.is_empty() {
+        info!("No pending jobs to collect");
+        return Ok(());
+    }
+
+    info!("📬 Checking {} pending job(s)...", store.jobs.len());
+
+    // Jobs run in the region they were submitted to, which may differ between jobs
+    let mut clients_by_region: BTreeMap<Option<String>, AwsClients> = BTreeMap::new();
+    for job in &store.jobs {
+        if !clients_by_region.contains_key(&job.region) {
+            let options = AwsOptions {
+                region: job.region.clone().or_else(|| aws_options.region.clone()),
+                ..aws_options.clone()
+            };
+            clients_by_region.insert(job.region.clone(), aws::create_aws_clients(&options).await?);
+        }
+    }
+
+    if args.wait {
+        wait_for_jobs(&store.jobs, &clients_by_region, args.timeout).await;
+    }
+
+    let mut finished = Vec::new();
+    let mut still_pending = 0;
+    let mut collected = 0;
+    let mut failures = Vec::new();
+
+    for job in store.jobs {
+        let clients = &clients_by_region[&job.region];
+
+        match collect_job(clients, &job).await {
+            Ok(Outcome::Collected) => {
+                collected += 1;
+                finished.push(job.job_name);
+            }
+            Ok(Outcome::Running) => {
+                info!("⏳ {} is still running", job.job_name);
+                still_pending += 1;
+            }
+            Ok(Outcome::Failed(reason)) => {
+                error!("❌ {} failed: {}", job.job_name, reason);
+                failures.push(job.job_name.clone());
+                finished.push(job.job_name);
+            }
+            Err(e) => {
+                // Most likely a transient problem, try again on the next run
+                warn!("Could not check {}: {}", job.job_name, e);
+                still_pending += 1;
+            }
+        }
+    }
+
+    // Only drop what this run finished, the store may have gained jobs since it was loaded
+    PendingStore::remove(&finished)?;
+
+    info!(
+        "✅ Collected {} job(s), {} still pending, {} failed",
+        collected,
+        still_pending,
+        failures.len()
+    );
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Transcribe(format!(
+            "{} job(s) failed: {}",
+            failures.len(),
+            failures.join(", ")
+        )))
+    }
+}
+
+/// Poll every pending job at once, each on its own status line
+///
+/// Problems are left for the collection pass that follows to report.
+async fn wait_for_jobs(
+    jobs: &[PendingJob],
+    clients_by_region: &BTreeMap<Option<String>, AwsClients>,
+    timeout: Option<Duration>,
+) {
+    let multi = MultiProgress::new();
+    let defaults = PollOptions::default();
+
+    join_all(jobs.iter().map(|job| {
+        let options = PollOptions {
+            timeout: timeout.unwrap_or(defaults.timeout),
+            estimate: remaining_estimate(job),
+            ..defaults.clone()
+        };
+        let multi = &multi;
+        async move {
+            let clients = &clients_by_region[&job.region];
+            let _ = aws::poll_transcription_status(
+                &clients.transcribe_client,
+                &job.job_name,
+                &options,
+                Some(multi),
+            )
+            .await;
+        }
+    }))
+    .await;
+}
+
+/// Expected time left for a job, based on its media and when it was submitted
+fn remaining_estimate(job: &PendingJob) -> Option<Duration> {
+    let estimate = aws::transcribe::estimate_processing_time(file::media_duration(&job.media_file)?);
+    let running_for = Duration::from_secs(PendingJob::now().saturating_sub(job.submitted_at));
+    Some(estimate.saturating_sub(running_for))
+}
+
+/// Check a single job, saving its transcript and cleaning up once it has finished
+async fn collect_job(clients: &AwsClients, job: &PendingJob) -> Result<Outcome, AppError> {
+    let Some(details) =
+        aws::transcribe::find_transcription_job(&clients.transcribe_client, &job.job_name).await?
+    else {
+        cleanup(clients, job).await?;
+        return Ok(Outcome::Failed("job no longer exists".to_string()));
+    };
+
+    match details.transcription_job_status() {
+        Some(TranscriptionJobStatus::Completed) => {
+            let result_uri = details
+                .transcript()
+                .and_then(|t| t.transcript_file_uri())
+                .ok_or_else(|| {
+                    AppError::Transcribe("Job completed but no transcript URI found".to_string())
+                })?;
+
+            let transcript = aws::transcribe::retrieve_transcript(
+                &clients.s3_client,
+                clients.s3_endpoint(),
+                result_uri,
+                job.output_bucket.is_some(),
+            )
+            .await?;
+            render::write_outputs(&transcript, &job.outputs, job.overwrite)?;
+            info!("💾 Saved {}", job.job_name);
+
+            // The job is collected now, leftovers must not make the next run save it again
+            if job.output_bucket.is_some()
+                && let Err(e) = aws::transcribe::remove_result(
+                    &clients.s3_client,
+                    clients.s3_endpoint(),
+                    result_uri,
+                    job.keep_output,
+                )
+                .await
+            {
+                warn!("Failed to delete the result JSON of {}: {}", job.job_name, e);
+            }
+            if let Err(e) = cleanup(clients, job).await {
+                warn!("Failed to clean up {}: {}", job.job_name, e);
+            }
+            Ok(Outcome::Collected)
+        }
+        Some(TranscriptionJobStatus::Failed) => {
+            cleanup(clients, job).await?;
+            Ok(Outcome::Failed(
+                details
+                    .failure_reason()
+                    .unwrap_or("Unknown failure reason")
+                    .to_string(),
+            ))
+        }
+        _ => Ok(Outcome::Running),
+    }
+}
+
+/// Remove the media uploaded for a job
+async fn cleanup(clients: &AwsClients, job: &PendingJob) -> Result<(), AppError> {
+    aws::delete_file_from_s3(&clients.s3_client, &job.bucket, &job.s3_key).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// A job for a 60 second mono 16 kHz WAV file, submitted the given seconds ago
+    fn job(media_file: &Path, seconds_ago: u64) -> PendingJob {
+        std::fs::write(media_file, file::silent_wav(60)).unwrap();
+
+        PendingJob {
+            job_name: "transcribe-job-talk".to_string(),
+            bucket: "bucket".to_string(),
+            s3_key: "transcribe-temp/talk.wav".to_string(),
+            media_file: media_file.to_path_buf(),
+            outputs: Vec::new(),
+            overwrite: false,
+            output_bucket: None,
+            keep_output: false,
+            region: None,
+            submitted_at: PendingJob::now() - seconds_ago,
+        }
+    }
+
+    #[test]
+    fn estimate_counts_down_from_the_submission() {
+        let dir = tempfile::tempdir().unwrap();
+        let media_file = dir.path().join("talk.wav");
+        let full = aws::transcribe::estimate_processing_time(Duration::from_secs(60));
+
+        let remaining = remaining_estimate(&job(&media_file, 0)).unwrap();
+        assert!(full - Duration::from_secs(1) <= remaining && remaining <= full);
+
+        let remaining = remaining_estimate(&job(&media_file, 20)).unwrap();
+        let expected = full - Duration::from_secs(20);
+        assert!(expected - Duration::from_secs(1) <= remaining && remaining <= expected);
+    }
+
+    #[test]
+    fn overdue_jobs_have_nothing_left() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = job(&dir.path().join("talk.wav"), 3600);
+
+        assert_eq!(remaining_estimate(&job), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn missing_media_has_no_estimate() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut job = job(&dir.path().join("talk.wav"), 0);
+        job.media_file = dir.path().join("moved.wav");
+
+        assert_eq!(remaining_estimate(&job), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! # Config Command
+//!
+//! This module implements `prepis config show`, which prints the effective
+//! settings and the configuration layer each of them came from.
+
+use prepis::config::{self, EffectiveSettings, LoadedConfig};
+use prepis::logging::plain_text;
+
+/// Print the configuration files in use and the merged settings
+pub fn show_config(loaded_config: &LoadedConfig, preset: Option<&str>, effective: &EffectiveSettings) {
+    let user_path = config::user_config_path();
+    print_line(match (&loaded_config.user, &user_path) {
+        (Some((path, _)), _) => format!("📄 User config: {}", path.display()),
+        (None, Some(path)) => format!("📄 User config: {} (not found)", path.display()),
+        (None, None) => "📄 User config: unavailable (no home directory)".to_string(),
+    });
+    print_line(match &loaded_config.project {
+        Some((path, _)) => format!("📄 Project config: {}", path.display()),
+        None => format!("📄 Project config: no {} found", config::PROJECT_CONFIG_FILE),
+    });
+    print_line(format!("🎛️  Preset: {}", preset.unwrap_or("none")));
+    let presets = loaded_config.preset_names();
+    if !presets.is_empty() {
+        print_line(format!("🎛️  Available presets: {}", presets.join(", ")));
+    }
+
+    println!();
+    let entries = effective.entries();
+    let width = entries.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, value) in entries {
+        match (value, effective.sources.get(name)) {
+            (Some(value), Some(source)) => {
+                println!("{:width$} = {}  ({})", name, value, source, width = width)
+            }
+            _ => println!("{:width$} = <not set>", name, width = width),
+        }
+    }
+}
+
+/// Print a line to stdout, without emojis with `--plain`
+fn print_line(line: String) {
+    println!("{}", plain_text(line));
+}
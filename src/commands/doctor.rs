@@ -0,0 +1,34 @@
+//! # Doctor Command
+//!
+//! This module implements `prepis doctor`, which runs every preflight check
+//! and reports the permissions that are missing.
+
+use prepis::aws::{self, AwsOptions};
+use crate::cli::DoctorArgs;
+use prepis::config::Settings;
+use prepis::error::AppError;
+use tracing::info;
+
+/// Check credentials and permissions against the given or configured bucket
+pub async fn run_doctor(
+    args: DoctorArgs,
+    settings: &Settings,
+    aws_options: &AwsOptions,
+) -> Result<(), AppError> {
+    info!("🩺 Checking AWS permissions...");
+
+    let bucket = args.bucket.as_ref().or(settings.bucket.as_ref());
+    let mut aws_clients = aws::create_aws_clients(aws_options).await?;
+    if let Some(bucket) = bucket {
+        aws_clients = aws_clients.align_with_bucket_region(bucket).await;
+    }
+    if let Some(region) = aws_clients.region() {
+        info!("🌍 Region: {}", region);
+    }
+    let report = aws::run_full_preflight(&aws_clients, bucket.map(String::as_str)).await;
+    report.print();
+    report.into_result()?;
+
+    info!("🎉 All checks passed");
+    Ok(())
+}
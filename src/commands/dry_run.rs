@@ -0,0 +1,208 @@
+//! # Dry Run
+//!
+//! This module implements `--dry-run`: it runs every check of a transcription
+//! run and shows the S3 keys and job names that would be generated, together
+//! with an estimate of the Transcribe cost and processing time.
+//!
+//! Nothing is uploaded and no job is started. The only AWS call made is a
+//! read of the bucket's region, which decides the rates that apply.
+//!
+//! The input may also be a directory, in which case every supported media
+//! file below it is estimated, e.g. before transcribing a whole archive.
+
+use super::transcribe::job_options;
+use prepis::aws::{self, AwsOptions};
+use prepis::backend::BackendKind;
+use crate::cli::{OutputFormat, TranscribeArgs};
+use prepis::config::{LoadedConfig, Settings};
+use prepis::error::AppError;
+use prepis::file;
+use prepis::models::{DryRunFile, DryRunReport};
+use prepis::utils::{self, format_duration};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Validate the input and estimate what transcribing it would cost
+pub async fn run_dry_run(
+    args: TranscribeArgs,
+    settings: &Settings,
+    loaded_config: &LoadedConfig,
+    aws_options: &AwsOptions,
+) -> Result<(), AppError> {
+    // The input is enforced by clap whenever no subcommand is given
+    let Some(input) = &args.video_file else {
+        return Err(AppError::File("A video file is required".to_string()));
+    };
+    let backend_kind = settings.backend.unwrap_or_default();
+    if backend_kind != BackendKind::Aws {
+        return Err(AppError::Config(format!(
+            "--dry-run only estimates Amazon Transcribe runs, not the {} backend",
+            backend_kind
+        )));
+    }
+    let Some(s3_bucket) = &settings.bucket else {
+        return Err(AppError::Config(
+            "No S3 bucket given. Pass it as an argument or set `bucket` in a config file".to_string(),
+        ));
+    };
+
+    info!("🧪 Dry run: nothing will be uploaded and no job will be started");
+    let job_options = job_options(settings);
+    aws::transcribe::validate_job_options(&job_options)?;
+
+    let files = if input.is_dir() {
+        let files = file::find_media_files(input)?;
+        if files.is_empty() {
+            return Err(AppError::File(format!(
+                "No supported media files found in {}",
+                input.display()
+            )));
+        }
+        files
+    } else {
+        vec![input.clone()]
+    };
+
+    let mut media_files = Vec::new();
+    let mut invalid_files = 0;
+    for path in files {
+        match file::validate_video_file(&path) {
+            Ok(media_info) => media_files.push((path, media_info)),
+            Err(e) if input.is_dir() => {
+                warn!("Skipping {}: {}", path.display(), e);
+                invalid_files += 1;
+            }
+            // A single file that fails validation fails the whole run, like a real one
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Jobs run in the bucket's region, and prices differ between regions
+    let region = match aws::create_aws_clients(aws_options).await {
+        Ok(clients) => clients
+            .align_with_bucket_region(s3_bucket)
+            .await
+            .region()
+            .map(str::to_string),
+        Err(e) => {
+            warn!("{}, using the default rates", e);
+            None
+        }
+    };
+    let rates = loaded_config.rates(region.as_deref());
+
+    let mut report = DryRunReport {
+        bucket: s3_bucket.clone(),
+        region,
+        rates,
+        redact_pii: job_options.redact_pii,
+        files: Vec::new(),
+        total_media_seconds: 0.0,
+        total_cost_usd: 0.0,
+        files_without_duration: 0,
+    };
+
+    for (path, media_info) in media_files {
+        let media_duration = media_info.as_ref().and_then(|info| info.duration);
+        let estimated_cost = media_duration.map(|d| rates.estimate_cost(d, job_options.redact_pii));
+        match (media_duration, estimated_cost) {
+            (Some(duration), Some(cost)) => {
+                report.total_media_seconds += duration.as_secs_f64();
+                report.total_cost_usd += cost;
+            }
+            _ => report.files_without_duration += 1,
+        }
+
+        report.files.push(DryRunFile {
+            size_bytes: std::fs::metadata(&path)?.len(),
+            media_duration_seconds: media_duration.map(|d| d.as_secs_f64()),
+            s3_key: utils::generate_s3_key(&path),
+            job_name: utils::generate_job_name(&path),
+            estimated_cost_usd: estimated_cost,
+            estimated_processing_seconds: media_duration
+                .map(|d| aws::transcribe::estimate_processing_time(d).as_secs_f64()),
+            file: path,
+        });
+    }
+
+    print_report(&report, args.output_format)?;
+
+    if invalid_files > 0 {
+        return Err(AppError::File(format!(
+            "{} file(s) failed validation",
+            invalid_files
+        )));
+    }
+    Ok(())
+}
+
+/// Print the plan to stdout, as a table or as JSON
+fn print_report(report: &DryRunReport, format: OutputFormat) -> Result<(), AppError> {
+    if format == OutputFormat::Json {
+        let json = serde_json::to_string(report)
+            .map_err(|e| AppError::File(format!("Failed to serialize the result: {}", e)))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    info!(
+        "🪣 Bucket: {} ({})",
+        report.bucket,
+        report.region.as_deref().unwrap_or("unknown region")
+    );
+    info!(
+        "💵 Rate: ${:.4}/min{}",
+        report.rates.per_minute_with(report.redact_pii),
+        if report.redact_pii { " including PII redaction" } else { "" }
+    );
+
+    let files: Vec<String> = report.files.iter().map(|f| f.file.display().to_string()).collect();
+    let width = files.iter().map(String::len).max().unwrap_or(0).max("TOTAL".len());
+    let job_width = report.files.iter().map(|f| f.job_name.len()).max().unwrap_or(0);
+
+    println!(
+        "{:width$}  {:>10}  {:>9}  {:>9}  {:job_width$}  S3 KEY",
+        "FILE", "DURATION", "COST", "READY IN", "JOB NAME",
+    );
+    for (file, dry_run_file) in files.iter().zip(&report.files) {
+        println!(
+            "{:width$}  {:>10}  {:>9}  {:>9}  {:job_width$}  {}",
+            file,
+            seconds(dry_run_file.media_duration_seconds),
+            dry_run_file
+                .estimated_cost_usd
+                .map_or("?".to_string(), |cost| format!("${:.4}", cost)),
+            seconds(dry_run_file.estimated_processing_seconds),
+            dry_run_file.job_name,
+            dry_run_file.s3_key,
+        );
+    }
+    // A total of nothing but unknown durations would look like a real one
+    let (total_duration, total_cost) = if report.files_without_duration == report.files.len() {
+        ("unknown".to_string(), "unknown".to_string())
+    } else {
+        (
+            seconds(Some(report.total_media_seconds)),
+            format!("${:.2}", report.total_cost_usd),
+        )
+    };
+    println!("{:width$}  {:>10}  {:>9}", "TOTAL", total_duration, total_cost);
+
+    if report.files_without_duration > 0 {
+        warn!(
+            "Could not read the duration of {} file(s), they are not included in the total",
+            report.files_without_duration
+        );
+    }
+    Ok(())
+}
+
+/// Format a number of seconds for the table, `?` when unknown
+fn seconds(seconds: Option<f64>) -> String {
+    match seconds {
+        None => "?".to_string(),
+        // `format_duration` rounds everything up to at least a second
+        Some(0.0) => "0s".to_string(),
+        Some(s) => format_duration(Duration::from_secs_f64(s)),
+    }
+}
@@ -0,0 +1,167 @@
+//! # Jobs Command
+//!
+//! This module implements `prepis jobs`, which manages transcription jobs
+//! after the run that started them has exited.
+//!
+//! It provides:
+//! - Listing jobs started by prepis, optionally filtered by status
+//! - Showing the details of a single job
+//! - Re-downloading and rendering the result of a completed job
+//! - Deleting jobs
+
+use prepis::aws::{self, AwsClients, AwsOptions};
+use crate::cli::{JobNameArgs, JobStatusFilter, JobsCommand, JobsFetchArgs, JobsListArgs, ResultFormat};
+use prepis::error::AppError;
+use prepis::file;
+use prepis::utils::JOB_NAME_PREFIX;
+use aws_sdk_transcribe::primitives::{DateTime, DateTimeFormat};
+use aws_sdk_transcribe::types::TranscriptionJobStatus;
+use tracing::info;
+
+/// Dispatch a `prepis jobs` subcommand
+pub async fn run_jobs(command: JobsCommand, aws_options: &AwsOptions) -> Result<(), AppError> {
+    let aws_clients = aws::create_aws_clients(aws_options).await?;
+    let transcribe_client = &aws_clients.transcribe_client;
+
+    match command {
+        JobsCommand::List(args) => list_jobs(transcribe_client, args).await,
+        JobsCommand::Status(args) => show_job_status(transcribe_client, args).await,
+        JobsCommand::Fetch(args) => {
+            fetch_job_result(&aws_clients, args).await
+        }
+        JobsCommand::Delete(args) => {
+            aws::transcribe::delete_transcription_job(transcribe_client, &args.name).await?;
+            info!("🗑️  Deleted transcription job: {}", args.name);
+            Ok(())
+        }
+    }
+}
+
+async fn list_jobs(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    args: JobsListArgs,
+) -> Result<(), AppError> {
+    let status = args.status.map(|status| match status {
+        JobStatusFilter::Queued => TranscriptionJobStatus::Queued,
+        JobStatusFilter::InProgress => TranscriptionJobStatus::InProgress,
+        JobStatusFilter::Completed => TranscriptionJobStatus::Completed,
+        JobStatusFilter::Failed => TranscriptionJobStatus::Failed,
+    });
+
+    let jobs =
+        aws::transcribe::list_transcription_jobs(transcribe_client, JOB_NAME_PREFIX, status, args.limit)
+            .await?;
+
+    if jobs.is_empty() {
+        println!("No transcription jobs found");
+        return Ok(());
+    }
+
+    let width = jobs
+        .iter()
+        .filter_map(|job| job.transcription_job_name())
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+
+    println!("{:width$}  {:11}  {:20}  LANGUAGE", "NAME", "STATUS", "CREATED", width = width);
+    for job in &jobs {
+        println!(
+            "{:width$}  {:11}  {:20}  {}",
+            job.transcription_job_name().unwrap_or("-"),
+            job.transcription_job_status().map(|s| s.as_str()).unwrap_or("-"),
+            format_time(job.creation_time()),
+            job.language_code().map(|l| l.as_str()).unwrap_or("-"),
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+async fn show_job_status(
+    transcribe_client: &aws_sdk_transcribe::Client,
+    args: JobNameArgs,
+) -> Result<(), AppError> {
+    let job = aws::transcribe::get_transcription_job(transcribe_client, &args.name).await?;
+
+    println!("Job:        {}", args.name);
+    println!(
+        "Status:     {}",
+        job.transcription_job_status().map(|s| s.as_str()).unwrap_or("-")
+    );
+    println!(
+        "Language:   {}",
+        job.language_code().map(|l| l.as_str()).unwrap_or("-")
+    );
+    println!(
+        "Media:      {}",
+        job.media().and_then(|m| m.media_file_uri()).unwrap_or("-")
+    );
+    println!("Created:    {}", format_time(job.creation_time()));
+    println!("Started:    {}", format_time(job.start_time()));
+    println!("Completed:  {}", format_time(job.completion_time()));
+    if let Some(reason) = job.failure_reason() {
+        println!("Failure:    {}", reason);
+    }
+    if let Some(uri) = job.transcript().and_then(|t| t.transcript_file_uri()) {
+        println!("Result:     {}", uri);
+    }
+
+    Ok(())
+}
+
+async fn fetch_job_result(aws_clients: &AwsClients, args: JobsFetchArgs) -> Result<(), AppError> {
+    let job =
+        aws::transcribe::get_transcription_job(&aws_clients.transcribe_client, &args.name).await?;
+
+    let result_uri = match job.transcription_job_status() {
+        Some(TranscriptionJobStatus::Completed) => job
+            .transcript()
+            .and_then(|t| t.transcript_file_uri())
+            .ok_or_else(|| {
+                AppError::Transcribe("Job completed but no transcript URI found".to_string())
+            })?,
+        Some(TranscriptionJobStatus::Failed) => {
+            return Err(AppError::Transcribe(format!(
+                "Job {} failed: {}",
+                args.name,
+                job.failure_reason().unwrap_or("Unknown failure reason")
+            )));
+        }
+        status => {
+            return Err(AppError::Transcribe(format!(
+                "Job {} has not completed yet (status: {})",
+                args.name,
+                status.map(|s| s.as_str()).unwrap_or("unknown")
+            )));
+        }
+    };
+
+    let json_text = aws::transcribe::download_result_json(
+        &aws_clients.s3_client,
+        aws_clients.s3_endpoint(),
+        result_uri,
+    )
+    .await?;
+    let content = match args.format {
+        ResultFormat::Text => aws::result::parse_transcription_result(&json_text)?.text,
+        ResultFormat::Json => json_text,
+    };
+
+    match &args.output {
+        Some(path) => {
+            file::save_transcription(path, &content, args.force)?;
+            info!("💾 Saved result of {} to: {}", args.name, path.display());
+        }
+        None => println!("{}", content),
+    }
+
+    Ok(())
+}
+
+/// Format an optional AWS timestamp for display
+fn format_time(time: Option<&DateTime>) -> String {
+    time.and_then(|t| t.fmt(DateTimeFormat::DateTime).ok())
+        .unwrap_or_else(|| "-".to_string())
+}
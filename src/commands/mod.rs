@@ -0,0 +1,34 @@
+//! # Commands
+//!
+//! This module contains the implementation of every Prepis command.
+//!
+//! It provides:
+//! - The default transcription workflow
+//! - Cost and time estimates without side effects (`--dry-run`)
+//! - Collecting detached jobs (`prepis collect`)
+//! - Job management (`prepis jobs`)
+//! - Rendering existing results (`prepis render`)
+//! - Bucket bootstrap (`prepis init-bucket`)
+//! - Permission checks (`prepis doctor`)
+//! - Configuration inspection (`prepis config show`)
+//!
+//! Each command receives its parsed arguments together with the settings
+//! merged from the configuration files.
+
+pub mod bucket;
+pub mod collect;
+pub mod config;
+pub mod doctor;
+pub mod dry_run;
+pub mod jobs;
+pub mod render;
+pub mod transcribe;
+
+pub use bucket::run_init_bucket;
+pub use collect::run_collect;
+pub use config::show_config;
+pub use doctor::run_doctor;
+pub use dry_run::run_dry_run;
+pub use jobs::run_jobs;
+pub use render::run_render;
+pub use transcribe::run_transcription;
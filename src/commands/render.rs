@@ -0,0 +1,87 @@
+//! # Render Command
+//!
+//! This module implements `prepis render`, which renders a result JSON that
+//! already exists, e.g. from the AWS console or an earlier run, without any
+//! upload or transcription job.
+//!
+//! It provides:
+//! - Loading the result from a local file, an HTTPS URL or an S3 URI
+//! - Rendering it in any of the transcript formats
+//! - Writing it to a file or printing it
+
+use crate::cli::RenderArgs;
+use aws_smithy_types::retry::RetryConfig;
+use prepis::aws::{self, AwsOptions};
+use prepis::config::DEFAULT_MAX_ATTEMPTS;
+use prepis::error::AppError;
+use prepis::file;
+use prepis::render::{self, TranscriptFormat};
+use prepis::utils::parse_s3_object_url;
+use std::io::Write;
+use std::path::Path;
+use tracing::info;
+
+/// Render a result JSON in the requested format
+pub async fn run_render(args: RenderArgs, aws_options: &AwsOptions) -> Result<(), AppError> {
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(TranscriptFormat::from_path))
+        .unwrap_or_default();
+    if args.output.is_none() && !format.is_text() {
+        return Err(AppError::Config(format!(
+            "{} cannot be printed to the terminal, please pass --output",
+            format
+        )));
+    }
+
+    let json_text = load_source(&args.source, aws_options).await?;
+    let mut transcript = render::read_transcript(&json_text)?;
+    if transcript.job_name.is_empty() {
+        transcript.job_name = Path::new(&args.source)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    transcript.result_uri = args.source.clone();
+
+    let rendered = render::render(&transcript, format)?;
+    match &args.output {
+        Some(path) => {
+            file::save_transcription(path, &rendered, args.force)?;
+            info!("💾 Saved {} to: {}", format, path.display());
+        }
+        None => std::io::stdout().write_all(&rendered)?,
+    }
+    Ok(())
+}
+
+/// Read the result JSON from a file, a URL or S3
+///
+/// S3 object URLs without a signature are read through S3, like the results
+/// Transcribe writes to an output bucket. Other URLs are downloaded as they
+/// are, e.g. presigned links.
+async fn load_source(source: &str, aws_options: &AwsOptions) -> Result<String, AppError> {
+    let is_url = ["s3://", "https://", "http://"]
+        .iter()
+        .any(|scheme| source.starts_with(scheme));
+    if !is_url {
+        return std::fs::read_to_string(source)
+            .map_err(|e| AppError::File(format!("Failed to read {}: {}", source, e)));
+    }
+
+    if !source.contains("X-Amz-Signature")
+        && let Some((bucket, key)) = parse_s3_object_url(source, aws_options.s3_endpoint())
+    {
+        info!("📥 Downloading s3://{}/{}", bucket, key);
+        let clients = aws::create_aws_clients(aws_options).await?;
+        return aws::s3::download_file_from_s3(&clients.s3_client, &bucket, &key).await;
+    }
+    if source.starts_with("s3://") {
+        return Err(AppError::Config(format!("Invalid S3 URI: {}", source)));
+    }
+
+    info!("📥 Downloading result JSON...");
+    let retry_config = RetryConfig::standard()
+        .with_max_attempts(aws_options.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS));
+    aws::transcribe::download_transcription_json(source, &retry_config).await
+}
@@ -0,0 +1,360 @@
+//! # Transcribe Command
+//!
+//! This module implements the default transcription workflow: validate the
+//! file, hand it to the selected backend (by default upload it to S3 and run
+//! an Amazon Transcribe job), show the result and clean up afterwards.
+//!
+//! With `--detach` the workflow stops once the job has started and records
+//! it for `prepis collect` instead.
+
+use crate::cli::{EventsFormat, OutputFormat, TranscribeArgs};
+use prepis::aws::{self, AwsOptions};
+use prepis::backend::whisper_api::WHISPER_API_KEY_ENV;
+use prepis::backend::{
+    AmazonTranscribe, BackendKind, StagedMedia, TranscriptionBackend, WhisperApi, WhisperCpp,
+};
+use prepis::config::{self, Settings};
+use prepis::error::AppError;
+use prepis::events;
+use prepis::models::{JobOptions, OutputLocation, PollOptions, TranscriptionReport};
+use prepis::pending::{PendingJob, PendingStore};
+use prepis::render::{self, OutputFile, TranscriptFormat};
+use prepis::transcriber::Transcriber;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Run the full transcription workflow for a single file
+pub async fn run_transcription(
+    args: TranscribeArgs,
+    settings: &Settings,
+    aws_options: &AwsOptions,
+) -> Result<(), AppError> {
+    // Scripts get every progress event of the run on stdout
+    match args.events {
+        Some(EventsFormat::Ndjson) => {
+            events::scope(Arc::new(events::write_ndjson), transcribe(args, settings, aws_options))
+                .await
+        }
+        None => transcribe(args, settings, aws_options).await,
+    }
+}
+
+/// Transcribe a single file with the settings of this run
+async fn transcribe(
+    mut args: TranscribeArgs,
+    settings: &Settings,
+    aws_options: &AwsOptions,
+) -> Result<(), AppError> {
+    // The video file is enforced by clap whenever no subcommand is given
+    let Some(video_file) = &args.video_file else {
+        return Err(AppError::File("A video file is required".to_string()));
+    };
+    let backend_kind = settings.backend.unwrap_or_default();
+    if args.detach && backend_kind != BackendKind::Aws {
+        return Err(AppError::Config(format!(
+            "--detach is not supported by the {} backend",
+            backend_kind
+        )));
+    }
+    // Only Amazon Transcribe stages media in a bucket, the others take the output file second
+    if backend_kind != BackendKind::Aws
+        && let Some(second) = args.s3_bucket.take()
+    {
+        if args.output_file.is_some() {
+            return Err(AppError::Config(format!(
+                "The {} backend uses no S3 bucket, pass the output file as the second argument",
+                backend_kind
+            )));
+        }
+        args.output_file = Some(second.into());
+    }
+    // Both only apply to a result JSON that lands in an output bucket
+    if settings.output_bucket.is_none() {
+        if settings.output_key.is_some() {
+            return Err(AppError::Config("--output-key needs an output bucket".to_string()));
+        }
+        if settings.keep_output == Some(true) {
+            return Err(AppError::Config("--keep-output needs an output bucket".to_string()));
+        }
+    }
+
+    info!("Video Transcription CLI");
+    info!("Video file: {:?}", video_file);
+    let job_options = job_options(settings);
+    aws::transcribe::validate_job_options(&job_options)?;
+
+    // Refuse outputs that cannot be written before anything is uploaded
+    let outputs = output_files(&args, video_file, &job_options.language)?;
+    render::check_outputs(&outputs, args.force)?;
+    for output in &outputs {
+        info!("Output file: {}", output.path.display());
+    }
+
+    // Validate the video file
+    let media = Transcriber::prepare(video_file)?;
+    let poll_options = poll_options(&args, media.duration())?;
+
+    let (backend, region) =
+        create_backend(backend_kind, &args, settings, aws_options, &poll_options).await?;
+    let transcriber = Transcriber::builder()
+        .backend(backend)
+        .options(job_options)
+        .poll_options(poll_options)
+        .build()?;
+
+    // Stage the file and start the transcription job
+    let job = transcriber.submit(&media).await?;
+
+    let mut report = TranscriptionReport {
+        job_name: job.job_name.clone(),
+        status: "submitted",
+        s3_uri: job.staged.s3_uri(),
+        language: job.options.language.clone(),
+        media_duration_seconds: media.duration().map(|d| d.as_secs_f64()),
+        result_uri: None,
+        output_files: Vec::new(),
+        transcript: None,
+    };
+
+    if args.detach {
+        let StagedMedia::S3 { bucket, key } = &job.staged else {
+            return Err(AppError::Config("--detach needs media staged in S3".to_string()));
+        };
+        PendingStore::add(PendingJob {
+            job_name: job.job_name.clone(),
+            bucket: bucket.clone(),
+            s3_key: key.clone(),
+            media_file: video_file.clone(),
+            outputs: outputs.clone(),
+            overwrite: args.force,
+            output_bucket: settings.output_bucket.clone(),
+            keep_output: settings.keep_output.unwrap_or(false),
+            region,
+            submitted_at: PendingJob::now(),
+        })?;
+        info!("📨 Job {} submitted, the transcript will be saved to: {}", job.job_name, display_paths(&outputs));
+        info!("   Run `prepis collect` to pick up the result once it is done");
+        report.output_files = outputs.into_iter().map(|output| output.path).collect();
+        return print_report(&report, args.output_format, false);
+    }
+
+    // Wait for the job and fetch the transcript
+    let transcript = transcriber.wait(&job).await?;
+
+    // Every format is rendered from the one fetched result, which is only removed once saved
+    render::write_outputs(&transcript, &outputs, args.force)?;
+    if let Err(e) = transcriber.finish(&job, &transcript).await {
+        warn!("Failed to clean up after {}: {}", job.job_name, e);
+    }
+    report.output_files = outputs.into_iter().map(|output| output.path).collect();
+    report.status = "completed";
+    report.result_uri = Some(transcript.result_uri);
+    report.transcript = Some(transcript.text);
+
+    print_report(&report, args.output_format, print_transcript(&args))
+}
+
+/// Set up the backend selected for this run
+///
+/// Returns the backend together with the AWS region its jobs run in, if any.
+async fn create_backend(
+    kind: BackendKind,
+    args: &TranscribeArgs,
+    settings: &Settings,
+    aws_options: &AwsOptions,
+    poll_options: &PollOptions,
+) -> Result<(Box<dyn TranscriptionBackend>, Option<String>), AppError> {
+    match kind {
+        BackendKind::Aws => {
+            let Some(s3_bucket) = &settings.bucket else {
+                return Err(AppError::Config(
+                    "No S3 bucket given. Pass it as an argument or set `bucket` in a config file"
+                        .to_string(),
+                ));
+            };
+            info!("S3 bucket: {}", s3_bucket);
+
+            // Initialize AWS clients
+            let aws_clients = aws::create_aws_clients(aws_options)
+                .await?
+                .align_with_bucket_region(s3_bucket)
+                .await;
+
+            // Catch bad credentials and an unreachable bucket before uploading anything
+            if !args.skip_preflight {
+                info!("🩺 Running preflight checks...");
+                let report = aws::run_preflight(&aws_clients, Some(s3_bucket)).await;
+                report.print();
+                report.into_result()?;
+            }
+
+            let region = aws_clients.region().map(str::to_string);
+            let backend = AmazonTranscribe::new(aws_clients, s3_bucket)
+                .keep_output(settings.keep_output.unwrap_or(false));
+            Ok((Box::new(backend), region))
+        }
+        BackendKind::WhisperApi => {
+            let Some(whisper_url) = &settings.whisper_url else {
+                return Err(AppError::Config(
+                    "No Whisper server given. Pass --whisper-url or set `whisper_url` in a config file"
+                        .to_string(),
+                ));
+            };
+            info!("Whisper server: {}", whisper_url);
+
+            // The server only answers once it is done, so --timeout covers the whole request
+            let mut backend = WhisperApi::new(whisper_url)
+                .api_key(std::env::var(WHISPER_API_KEY_ENV).ok().filter(|key| !key.is_empty()))
+                .timeout(poll_options.timeout);
+            if let Some(model) = &settings.whisper_model {
+                backend = backend.model(model);
+            }
+            Ok((Box::new(backend), None))
+        }
+        BackendKind::WhisperCpp => {
+            let Some(model) = &settings.whisper_cpp_model else {
+                return Err(AppError::Config(
+                    "No whisper.cpp model given. Pass --whisper-cpp-model or set `whisper_cpp_model` in a config file"
+                        .to_string(),
+                ));
+            };
+            info!("whisper.cpp model: {}", model.display());
+
+            let mut backend = WhisperCpp::new(model);
+            if let Some(binary) = &settings.whisper_cpp_path {
+                backend = backend.binary(binary);
+            }
+            Ok((Box::new(backend), None))
+        }
+    }
+}
+
+/// Build the options of the transcription job from the effective settings
+pub fn job_options(settings: &Settings) -> JobOptions {
+    JobOptions {
+        language: settings
+            .language
+            .clone()
+            .unwrap_or_else(|| config::DEFAULT_LANGUAGE.to_string()),
+        max_speakers: settings.speakers,
+        output: settings.output_bucket.as_ref().map(|bucket| OutputLocation {
+            bucket: bucket.clone(),
+            key: settings.output_key.clone(),
+        }),
+        redact_pii: settings.redact_pii.unwrap_or(false),
+        sample_rate: None,
+    }
+}
+
+/// Every file the transcript should be written to
+///
+/// The output file argument and each `--output` keep the format of their
+/// extension, and relative ones are placed in `--output-dir` when given.
+/// `--formats` are named after the media file and go next to it unless
+/// `--output-dir` is given. Without any of them the transcript is saved as
+/// plain text next to the media file.
+fn output_files(
+    args: &TranscribeArgs,
+    video_file: &Path,
+    language: &str,
+) -> Result<Vec<OutputFile>, AppError> {
+    let mut outputs: Vec<OutputFile> = args
+        .output_file
+        .iter()
+        .chain(&args.outputs)
+        .map(|path| match &args.output_dir {
+            Some(dir) => OutputFile::from_path(dir.join(path)), // Absolute paths stay as they are
+            None => OutputFile::from_path(path),
+        })
+        .collect();
+
+    let mut formats = args.formats.clone();
+    if formats.is_empty() && outputs.is_empty() {
+        formats.push(TranscriptFormat::Txt);
+    }
+    if formats.is_empty() && args.output_template.is_some() {
+        return Err(AppError::Config(
+            "--output-template names the files of --formats, please pass --formats as well or name the output files directly".to_string(),
+        ));
+    }
+    let dir = match &args.output_dir {
+        Some(dir) => dir.as_path(),
+        None => video_file.parent().unwrap_or(Path::new("")),
+    };
+    let template = args
+        .output_template
+        .as_deref()
+        .unwrap_or(render::DEFAULT_OUTPUT_TEMPLATE);
+    outputs.extend(formats.into_iter().map(|format| {
+        OutputFile::from_template(dir, template, video_file, language, format)
+    }));
+    Ok(outputs)
+}
+
+/// Paths of the outputs for a log message, e.g. `talk.txt, talk.srt`
+fn display_paths(outputs: &[OutputFile]) -> String {
+    outputs
+        .iter()
+        .map(|output| output.path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether the transcript goes to stdout next to the saved files
+///
+/// In a terminal a long transcript would only scroll away, so it is printed
+/// by default only when stdout is piped, e.g. into another command.
+fn print_transcript(args: &TranscribeArgs) -> bool {
+    if args.print || args.no_print {
+        return args.print;
+    }
+    !atty::is(atty::Stream::Stdout)
+}
+
+/// Print the final result to stdout, keeping it free of any other output
+///
+/// The JSON report always includes the transcript, the text output only
+/// when it should be printed.
+fn print_report(
+    report: &TranscriptionReport,
+    format: OutputFormat,
+    print_transcript: bool,
+) -> Result<(), AppError> {
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string(report).map_err(|e| {
+                AppError::File(format!("Failed to serialize the result: {}", e))
+            })?;
+            println!("{}", json);
+        }
+        OutputFormat::Text => {
+            if let Some(transcript_text) = report.transcript.as_ref().filter(|_| print_transcript) {
+                info!("\n📝 Transcription Results:");
+                info!("─────────────────────────");
+                println!("{}", transcript_text);
+                info!("─────────────────────────");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Build the polling options from the command line and the media length
+fn poll_options(
+    args: &TranscribeArgs,
+    media_duration: Option<Duration>,
+) -> Result<PollOptions, AppError> {
+    if args.poll_interval.is_some_and(|interval| interval.is_zero()) {
+        return Err(AppError::Config("--poll-interval must be greater than zero".to_string()));
+    }
+
+    let defaults = PollOptions::for_media(media_duration);
+    Ok(PollOptions {
+        interval: args.poll_interval.unwrap_or(defaults.interval),
+        max_interval: args.max_interval.unwrap_or(defaults.max_interval),
+        timeout: args.timeout.unwrap_or(defaults.timeout),
+        estimate: defaults.estimate,
+    })
+}
@@ -0,0 +1,456 @@
+//! # Configuration Files
+//!
+//! This module loads default settings and named presets from TOML files.
+//!
+//! Two files are read, if they exist:
+//! - The user config at `$XDG_CONFIG_HOME/prepis/config.toml`
+//!   (falling back to `~/.config/prepis/config.toml`)
+//! - A project-local `.prepis.toml` in the current directory or any parent
+//!
+//! Settings are merged in this order, later layers winning:
+//! built-in defaults, user defaults, project defaults, the selected preset,
+//! and finally flags given on the command line.
+//!
+//! ```toml
+//! [defaults]
+//! backend = "aws"
+//! bucket = "my-transcription-bucket"
+//! region = "eu-west-1"
+//!
+//! [presets.podcast]
+//! speakers = 2
+//!
+//! [presets.meeting]
+//! speakers = 8
+//! language = "en-GB"
+//!
+//! # Confidential recordings stay on the self-hosted Whisper server
+//! [presets.confidential]
+//! backend = "whisper-api"
+//! whisper_url = "http://whisper.internal:8080"
+//!
+//! # No network at all, e.g. on a flight
+//! [presets.offline]
+//! backend = "whisper-cpp"
+//! whisper_cpp_model = "/opt/models/ggml-base.en.bin"
+//!
+//! # Transcribe prices in USD used by `--dry-run`
+//! [rates]
+//! per_minute = 0.024
+//!
+//! [rates.regions.eu-central-1]
+//! per_minute = 0.0312
+//! ```
+
+use crate::backend::BackendKind;
+use crate::error::AppError;
+use crate::pricing::Rates;
+use crate::utils::xdg_base_dir;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the project-local configuration file
+pub const PROJECT_CONFIG_FILE: &str = ".prepis.toml";
+
+/// Language used when none is configured
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+/// Attempts per AWS call, including the first one, when none are configured
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Settings that can come from a config file, a preset or the command line
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub backend: Option<BackendKind>,
+    pub bucket: Option<String>,
+    pub language: Option<String>,
+    pub speakers: Option<i32>,
+    pub output_bucket: Option<String>,
+    pub output_key: Option<String>,
+    pub keep_output: Option<bool>,
+    pub region: Option<String>,
+    pub profile: Option<String>,
+    pub endpoint_url: Option<String>,
+    pub s3_endpoint_url: Option<String>,
+    pub transcribe_endpoint_url: Option<String>,
+    pub max_attempts: Option<u32>,
+    pub redact_pii: Option<bool>,
+    pub whisper_url: Option<String>,
+    pub whisper_model: Option<String>,
+    pub whisper_cpp_path: Option<PathBuf>,
+    pub whisper_cpp_model: Option<PathBuf>,
+}
+
+/// Layout of a configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub defaults: Settings,
+    pub presets: BTreeMap<String, Settings>,
+    pub rates: RatesConfig,
+}
+
+/// Transcribe prices from a configuration file, in USD per minute of media
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RatesConfig {
+    pub per_minute: Option<f64>,
+    pub pii_redaction_per_minute: Option<f64>,
+    pub regions: BTreeMap<String, RegionRates>,
+}
+
+/// Transcribe prices that only apply to one region
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegionRates {
+    pub per_minute: Option<f64>,
+    pub pii_redaction_per_minute: Option<f64>,
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    BuiltIn,
+    UserConfig(PathBuf),
+    ProjectConfig(PathBuf),
+    Preset { name: String, path: PathBuf },
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in default"),
+            Source::UserConfig(path) => write!(f, "user config {}", path.display()),
+            Source::ProjectConfig(path) => write!(f, "project config {}", path.display()),
+            Source::Preset { name, path } => write!(f, "preset \"{}\" in {}", name, path.display()),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Settings after merging every layer, with the source of each value
+#[derive(Debug, Default)]
+pub struct EffectiveSettings {
+    pub settings: Settings,
+    pub sources: BTreeMap<&'static str, Source>,
+}
+
+impl EffectiveSettings {
+    /// Apply a layer on top of the current settings
+    fn apply(&mut self, layer: &Settings, source: Source) {
+        let settings = &mut self.settings;
+        let sources = &mut self.sources;
+
+        set(&mut settings.backend, &layer.backend, "backend", &source, sources);
+        set(&mut settings.bucket, &layer.bucket, "bucket", &source, sources);
+        set(&mut settings.language, &layer.language, "language", &source, sources);
+        set(&mut settings.speakers, &layer.speakers, "speakers", &source, sources);
+        set(&mut settings.output_bucket, &layer.output_bucket, "output_bucket", &source, sources);
+        set(&mut settings.output_key, &layer.output_key, "output_key", &source, sources);
+        set(&mut settings.keep_output, &layer.keep_output, "keep_output", &source, sources);
+        set(&mut settings.region, &layer.region, "region", &source, sources);
+        set(&mut settings.profile, &layer.profile, "profile", &source, sources);
+        set(&mut settings.endpoint_url, &layer.endpoint_url, "endpoint_url", &source, sources);
+        set(&mut settings.s3_endpoint_url, &layer.s3_endpoint_url, "s3_endpoint_url", &source, sources);
+        set(
+            &mut settings.transcribe_endpoint_url,
+            &layer.transcribe_endpoint_url,
+            "transcribe_endpoint_url",
+            &source,
+            sources,
+        );
+        set(&mut settings.max_attempts, &layer.max_attempts, "max_attempts", &source, sources);
+        set(&mut settings.redact_pii, &layer.redact_pii, "redact_pii", &source, sources);
+        set(&mut settings.whisper_url, &layer.whisper_url, "whisper_url", &source, sources);
+        set(&mut settings.whisper_model, &layer.whisper_model, "whisper_model", &source, sources);
+        set(&mut settings.whisper_cpp_path, &layer.whisper_cpp_path, "whisper_cpp_path", &source, sources);
+        set(&mut settings.whisper_cpp_model, &layer.whisper_cpp_model, "whisper_cpp_model", &source, sources);
+    }
+
+    /// Every setting with its display value, in a stable order
+    pub fn entries(&self) -> Vec<(&'static str, Option<String>)> {
+        let s = &self.settings;
+        vec![
+            ("backend", s.backend.map(|v| v.to_string())),
+            ("bucket", s.bucket.clone()),
+            ("language", s.language.clone()),
+            ("speakers", s.speakers.map(|v| v.to_string())),
+            ("output_bucket", s.output_bucket.clone()),
+            ("output_key", s.output_key.clone()),
+            ("keep_output", s.keep_output.map(|v| v.to_string())),
+            ("region", s.region.clone()),
+            ("profile", s.profile.clone()),
+            ("endpoint_url", s.endpoint_url.clone()),
+            ("s3_endpoint_url", s.s3_endpoint_url.clone()),
+            ("transcribe_endpoint_url", s.transcribe_endpoint_url.clone()),
+            ("max_attempts", s.max_attempts.map(|v| v.to_string())),
+            ("redact_pii", s.redact_pii.map(|v| v.to_string())),
+            ("whisper_url", s.whisper_url.clone()),
+            ("whisper_model", s.whisper_model.clone()),
+            ("whisper_cpp_path", s.whisper_cpp_path.as_ref().map(|v| v.display().to_string())),
+            ("whisper_cpp_model", s.whisper_cpp_model.as_ref().map(|v| v.display().to_string())),
+        ]
+    }
+}
+
+/// Overwrite a setting when the layer provides a value, remembering its source
+fn set<T: Clone>(
+    target: &mut Option<T>,
+    value: &Option<T>,
+    name: &'static str,
+    source: &Source,
+    sources: &mut BTreeMap<&'static str, Source>,
+) {
+    if let Some(value) = value {
+        *target = Some(value.clone());
+        sources.insert(name, source.clone());
+    }
+}
+
+/// Configuration files found on disk
+#[derive(Debug, Default)]
+pub struct LoadedConfig {
+    pub user: Option<(PathBuf, ConfigFile)>,
+    pub project: Option<(PathBuf, ConfigFile)>,
+}
+
+impl LoadedConfig {
+    /// Load the user and project configuration files, if present
+    pub fn load() -> Result<Self, AppError> {
+        let user = match user_config_path() {
+            Some(path) if path.is_file() => Some((path.clone(), read_config_file(&path)?)),
+            _ => None,
+        };
+
+        let project = match find_project_config() {
+            Some(path) => Some((path.clone(), read_config_file(&path)?)),
+            None => None,
+        };
+
+        Ok(Self { user, project })
+    }
+
+    /// Merge all layers into the effective settings
+    pub fn resolve(
+        &self,
+        preset: Option<&str>,
+        command_line: &Settings,
+    ) -> Result<EffectiveSettings, AppError> {
+        let mut effective = EffectiveSettings::default();
+
+        let built_in = Settings {
+            backend: Some(BackendKind::default()),
+            language: Some(DEFAULT_LANGUAGE.to_string()),
+            max_attempts: Some(DEFAULT_MAX_ATTEMPTS),
+            ..Default::default()
+        };
+        effective.apply(&built_in, Source::BuiltIn);
+
+        if let Some((path, file)) = &self.user {
+            effective.apply(&file.defaults, Source::UserConfig(path.clone()));
+        }
+        if let Some((path, file)) = &self.project {
+            effective.apply(&file.defaults, Source::ProjectConfig(path.clone()));
+        }
+
+        if let Some(name) = preset {
+            let mut found = false;
+            for (path, file) in self.user.iter().chain(self.project.iter()) {
+                if let Some(preset_settings) = file.presets.get(name) {
+                    found = true;
+                    let source = Source::Preset {
+                        name: name.to_string(),
+                        path: path.clone(),
+                    };
+                    effective.apply(preset_settings, source);
+                }
+            }
+
+            if !found {
+                let available = self.preset_names();
+                return Err(AppError::Config(format!(
+                    "Unknown preset: {}. Available presets: {}",
+                    name,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )));
+            }
+        }
+
+        effective.apply(command_line, Source::CommandLine);
+        Ok(effective)
+    }
+
+    /// Transcribe prices for a region, project rates winning over user rates
+    pub fn rates(&self, region: Option<&str>) -> Rates {
+        let mut rates = Rates::default();
+        for (_, file) in self.user.iter().chain(self.project.iter()) {
+            let config = &file.rates;
+            rates.apply(config.per_minute, config.pii_redaction_per_minute);
+            if let Some(region_rates) = region.and_then(|region| config.regions.get(region)) {
+                rates.apply(region_rates.per_minute, region_rates.pii_redaction_per_minute);
+            }
+        }
+        rates
+    }
+
+    /// Names of all presets across both files
+    pub fn preset_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .user
+            .iter()
+            .chain(self.project.iter())
+            .flat_map(|(_, file)| file.presets.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Path of the user configuration file following the XDG base directory spec
+pub fn user_config_path() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("prepis").join("config.toml"))
+}
+
+/// Look for a project configuration file in the current directory and its parents
+pub fn find_project_config() -> Option<PathBuf> {
+    let current_dir = std::env::current_dir().ok()?;
+    current_dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Read and parse a configuration file
+fn read_config_file(path: &Path) -> Result<ConfigFile, AppError> {
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content)
+        .map_err(|e| AppError::Config(format!("Invalid config file {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(path: &str, toml: &str) -> Option<(PathBuf, ConfigFile)> {
+        Some((PathBuf::from(path), toml::from_str(toml).unwrap()))
+    }
+
+    fn loaded() -> LoadedConfig {
+        LoadedConfig {
+            user: config(
+                "user.toml",
+                r#"
+                [defaults]
+                bucket = "user-bucket"
+                region = "eu-west-1"
+                speakers = 2
+
+                [presets.meeting]
+                speakers = 8
+                language = "en-GB"
+                "#,
+            ),
+            project: config(
+                ".prepis.toml",
+                r#"
+                [defaults]
+                bucket = "project-bucket"
+
+                [presets.meeting]
+                language = "de-DE"
+
+                [presets.podcast]
+                redact_pii = true
+                "#,
+            ),
+        }
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let effective = loaded().resolve(None, &Settings::default()).unwrap();
+
+        assert_eq!(effective.settings.bucket.as_deref(), Some("project-bucket"));
+        assert_eq!(effective.settings.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(effective.settings.language.as_deref(), Some(DEFAULT_LANGUAGE));
+        assert_eq!(effective.sources["bucket"], Source::ProjectConfig(PathBuf::from(".prepis.toml")));
+        assert_eq!(effective.sources["region"], Source::UserConfig(PathBuf::from("user.toml")));
+        assert_eq!(effective.sources["language"], Source::BuiltIn);
+    }
+
+    #[test]
+    fn presets_of_both_files_are_merged_and_the_command_line_wins() {
+        let command_line = Settings {
+            bucket: Some("cli-bucket".to_string()),
+            ..Default::default()
+        };
+        let effective = loaded().resolve(Some("meeting"), &command_line).unwrap();
+
+        assert_eq!(effective.settings.speakers, Some(8));
+        assert_eq!(effective.settings.language.as_deref(), Some("de-DE"));
+        assert_eq!(effective.settings.bucket.as_deref(), Some("cli-bucket"));
+        assert_eq!(
+            effective.sources["language"],
+            Source::Preset {
+                name: "meeting".to_string(),
+                path: PathBuf::from(".prepis.toml"),
+            }
+        );
+        assert_eq!(effective.sources["bucket"], Source::CommandLine);
+        assert!(!effective.sources.contains_key("output_bucket"));
+    }
+
+    #[test]
+    fn the_command_line_can_turn_preset_flags_off() {
+        let command_line = Settings {
+            redact_pii: Some(false),
+            ..Default::default()
+        };
+
+        let effective = loaded().resolve(Some("podcast"), &Settings::default()).unwrap();
+        assert_eq!(effective.settings.redact_pii, Some(true));
+
+        let effective = loaded().resolve(Some("podcast"), &command_line).unwrap();
+        assert_eq!(effective.settings.redact_pii, Some(false));
+        assert_eq!(effective.sources["redact_pii"], Source::CommandLine);
+    }
+
+    #[test]
+    fn unknown_presets_list_the_available_ones() {
+        let error = loaded().resolve(Some("lecture"), &Settings::default()).unwrap_err();
+
+        assert!(matches!(&error, AppError::Config(message) if message.ends_with("meeting, podcast")));
+        assert!(LoadedConfig::default().resolve(Some("lecture"), &Settings::default()).is_err());
+    }
+
+    #[test]
+    fn region_rates_override_the_general_ones() {
+        let loaded = LoadedConfig {
+            user: config(
+                "user.toml",
+                "[rates]\nper_minute = 0.03\n\n[rates.regions.eu-central-1]\nper_minute = 0.0312",
+            ),
+            project: config(".prepis.toml", "[rates]\npii_redaction_per_minute = 0.005"),
+        };
+
+        assert_eq!(loaded.rates(None).per_minute, 0.03);
+        assert_eq!(loaded.rates(Some("eu-central-1")).per_minute, 0.0312);
+        assert_eq!(loaded.rates(Some("eu-central-1")).pii_redaction_per_minute, 0.005);
+        assert_eq!(LoadedConfig::default().rates(Some("eu-central-1")), Rates::default());
+    }
+
+    #[test]
+    fn unknown_keys_and_wrong_types_are_rejected() {
+        assert!(toml::from_str::<ConfigFile>("[defaults]\nbukcet = \"typo\"").is_err());
+        assert!(toml::from_str::<ConfigFile>("[presets.x]\nspeakers = \"two\"").is_err());
+    }
+}
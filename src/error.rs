@@ -5,19 +5,42 @@
 //!
 //! It provides:
 //! - A central `AppError` enum for all application errors
-//! - Conversions from AWS SDK errors to application errors
-//! - User-friendly error display functionality
+//! - `ServiceError`, which keeps the AWS error code, HTTP status and source
+//!   error of a failed SDK call
+//! - A classification of every error, each class with its own exit code
+//! - User-friendly error display with hints for common AWS error codes
 //!
 //! The error system is designed to provide clear, actionable feedback to users
-//! when something goes wrong during the transcription process.
+//! when something goes wrong during the transcription process, and stable
+//! exit codes for scripts:
+//!
+//! | Code | Class       | Meaning                                              |
+//! |------|-------------|------------------------------------------------------|
+//! | 0    |             | Success                                              |
+//! | 1    |             | Any other failure                                    |
+//! | 2    |             | Invalid command-line usage                           |
+//! | 3    | `User`      | Bad input, configuration or local file problem       |
+//! | 4    | `Auth`      | Missing, invalid or insufficient AWS credentials     |
+//! | 5    | `NotFound`  | A bucket, object or job does not exist               |
+//! | 6    | `Throttled` | An AWS rate or concurrency limit was hit             |
+//! | 7    | `Service`   | AWS or the network failed, or the job itself failed  |
+//! | 8    | `Timeout`   | A request or the wait for a job timed out            |
 
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
 use thiserror::Error;
+use tracing::error;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("File error: {0}")]
     File(String),
 
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     #[error("AWS error: {0}")]
     Aws(String),
 
@@ -27,43 +50,450 @@ pub enum AppError {
     #[error("Transcribe error: {0}")]
     Transcribe(String),
 
+    #[error("{service} error: {error}")]
+    Service {
+        service: &'static str,
+        #[source]
+        error: ServiceError,
+    },
+
+    #[error("{context}: {source}")]
+    Http {
+        context: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("Whisper server error: {context}: {source}")]
+    WhisperApi {
+        context: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    #[error("whisper.cpp error: {0}")]
+    WhisperCpp(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// Broad kind of an error, which decides the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    User,
+    Auth,
+    NotFound,
+    Throttled,
+    Service,
+    Timeout,
+}
+
+impl ErrorClass {
+    /// Exit code of the process for errors of this class
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorClass::User => 3,
+            ErrorClass::Auth => 4,
+            ErrorClass::NotFound => 5,
+            ErrorClass::Throttled => 6,
+            ErrorClass::Service => 7,
+            ErrorClass::Timeout => 8,
+        }
+    }
+}
+
+/// A failed AWS SDK call
+#[derive(Debug, Error)]
+#[error("{context}: {detail}")]
+pub struct ServiceError {
+    context: String,
+    code: Option<String>,
+    status: Option<u16>,
+    detail: String,
+    class: ErrorClass,
+    #[source]
+    source: BoxError,
+}
+
+impl ServiceError {
+    /// Wrap an SDK error, describing what was being done when it happened
+    pub fn new<E>(context: impl Into<String>, err: SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    {
+        let code = err.code().map(str::to_string);
+        let status = err.raw_response().map(|r| r.status().as_u16());
+        let class = match &err {
+            SdkError::TimeoutError(_) => ErrorClass::Timeout,
+            SdkError::DispatchFailure(failure) if failure.is_timeout() => ErrorClass::Timeout,
+            SdkError::DispatchFailure(_) => ErrorClass::Service,
+            // Transcribe reports unknown job names as a bad request
+            _ if err.code() == Some("BadRequestException")
+                && err.message().is_some_and(|m| m.contains("couldn't be found")) =>
+            {
+                ErrorClass::NotFound
+            }
+            _ => classify(code.as_deref(), status),
+        };
+        let detail = match (err.code(), err.message()) {
+            (Some(code), Some(message)) => format!("{} ({})", code, message),
+            (Some(code), None) => code.to_string(),
+            _ => error_chain(&err),
+        };
+
+        Self {
+            context: context.into(),
+            code,
+            status,
+            detail,
+            class,
+            source: Box::new(err),
+        }
+    }
+
+    /// Wrap an error of an SDK's aggregated `Error` type
+    fn from_metadata<E>(err: E) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    {
+        let code = err.code().map(str::to_string);
+        Self {
+            context: "Request failed".to_string(),
+            detail: err.message().unwrap_or("unknown error").to_string(),
+            class: classify(code.as_deref(), None),
+            code,
+            status: None,
+            source: Box::new(err),
+        }
+    }
+
+    /// AWS error code, e.g. `AccessDenied` or `LimitExceededException`
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// HTTP status of the response, if one was received
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        self.class
+    }
+}
+
+impl AppError {
+    /// Wrap a failed S3 call
+    pub fn s3<E>(context: impl Into<String>, err: SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    {
+        AppError::Service {
+            service: "S3",
+            error: ServiceError::new(context, err),
+        }
+    }
+
+    /// Wrap a failed STS call
+    pub fn sts<E>(context: impl Into<String>, err: SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    {
+        AppError::Service {
+            service: "STS",
+            error: ServiceError::new(context, err),
+        }
+    }
+
+    /// Wrap a failed Amazon Transcribe call
+    pub fn transcribe<E>(context: impl Into<String>, err: SdkError<E, HttpResponse>) -> Self
+    where
+        E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    {
+        AppError::Service {
+            service: "Transcribe",
+            error: ServiceError::new(context, err),
+        }
+    }
+
+    /// Broad kind of this error
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            AppError::File(_) | AppError::Config(_) | AppError::Io(_) => ErrorClass::User,
+            AppError::Aws(_) => ErrorClass::Auth,
+            AppError::S3(_) | AppError::Transcribe(_) | AppError::WhisperCpp(_) => ErrorClass::Service,
+            AppError::Service { error, .. } => error.class(),
+            AppError::Http { source, .. } | AppError::WhisperApi { source, .. }
+                if source.is_timeout() =>
+            {
+                ErrorClass::Timeout
+            }
+            AppError::Http { source, .. } | AppError::WhisperApi { source, .. } => {
+                classify(None, source.status().map(|status| status.as_u16()))
+            }
+            AppError::NotFound(_) => ErrorClass::NotFound,
+            AppError::Timeout(_) => ErrorClass::Timeout,
+        }
+    }
+
+    /// Exit code of the process when this error ends the run
+    pub fn exit_code(&self) -> i32 {
+        self.class().exit_code()
+    }
+}
+
+/// Classify an AWS error by its error code, falling back to the HTTP status
+fn classify(code: Option<&str>, status: Option<u16>) -> ErrorClass {
+    match code {
+        Some(
+            "AccessDenied"
+            | "AccessDeniedException"
+            | "AllAccessDisabled"
+            | "ExpiredToken"
+            | "ExpiredTokenException"
+            | "InvalidAccessKeyId"
+            | "InvalidClientTokenId"
+            | "SignatureDoesNotMatch"
+            | "UnrecognizedClientException",
+        ) => ErrorClass::Auth,
+        Some("NoSuchBucket" | "NoSuchKey" | "NoSuchUpload" | "NotFound" | "NotFoundException") => {
+            ErrorClass::NotFound
+        }
+        Some(
+            "LimitExceededException"
+            | "RequestLimitExceeded"
+            | "SlowDown"
+            | "Throttling"
+            | "ThrottlingException"
+            | "TooManyRequestsException",
+        ) => ErrorClass::Throttled,
+        Some(
+            "BadRequestException"
+            | "ConflictException"
+            | "InvalidBucketName"
+            | "InvalidRequest"
+            | "ValidationException",
+        ) => ErrorClass::User,
+        Some("RequestTimeout" | "RequestTimeoutException") => ErrorClass::Timeout,
+        _ => match status {
+            Some(401 | 403) => ErrorClass::Auth,
+            Some(404) => ErrorClass::NotFound,
+            Some(408) => ErrorClass::Timeout,
+            Some(429) => ErrorClass::Throttled,
+            Some(400..=499) => ErrorClass::User,
+            _ => ErrorClass::Service,
+        },
+    }
+}
+
+/// An error followed by its sources, e.g. `dispatch failure: io error: connection refused`
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut chain = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        chain.push_str(": ");
+        chain.push_str(&err.to_string());
+        source = err.source();
+    }
+    chain
+}
+
 // Error conversion implementations
 impl From<aws_sdk_s3::Error> for AppError {
     fn from(err: aws_sdk_s3::Error) -> Self {
-        AppError::S3(err.to_string())
+        AppError::Service {
+            service: "S3",
+            error: ServiceError::from_metadata(err),
+        }
     }
 }
 
 impl From<aws_sdk_transcribe::Error> for AppError {
     fn from(err: aws_sdk_transcribe::Error) -> Self {
-        AppError::Transcribe(err.to_string())
+        AppError::Service {
+            service: "Transcribe",
+            error: ServiceError::from_metadata(err),
+        }
     }
 }
 
 /// Display error messages in a user-friendly format
 pub fn display_error(error: &AppError) {
-    eprintln!("🛑 Error: {}", error);
+    error!("🛑 Error: {}", error);
+
+    // Errors from AWS get a hint for their specific error code
+    if let AppError::Service { error: service_error, .. } = error
+        && let Some(hint) = service_error.code().and_then(code_hint)
+    {
+        error!("{}", hint);
+        return;
+    }
 
     // Display additional context for specific error types
-    match error {
-        AppError::File(_) => {
-            eprintln!("Please verify the file path and permissions.");
+    match error.class() {
+        ErrorClass::User => match error {
+            AppError::File(_) => error!("Please verify the file path and permissions."),
+            AppError::Io(_) => error!("Please check file permissions and disk space."),
+            AppError::WhisperApi { .. } => {
+                error!("Please check that the server supports the media format and --whisper-model.")
+            }
+            _ => error!("Please check your configuration files and command-line options."),
+        },
+        ErrorClass::Auth if matches!(error, AppError::WhisperApi { .. }) => {
+            error!("Please check the API key in PREPIS_WHISPER_API_KEY.")
+        }
+        ErrorClass::Auth => {
+            error!("Please check your AWS credentials and configuration.");
+            error!("Run `prepis doctor` to see which permissions are missing.");
+        }
+        ErrorClass::NotFound => match error {
+            AppError::Service { service: "S3", .. } => {
+                error!("Please verify the S3 bucket exists and you have access to it.")
+            }
+            AppError::WhisperApi { .. } => error!(
+                "Please check --whisper-url, the server must provide /v1/audio/transcriptions."
+            ),
+            AppError::Http { .. } => error!(
+                "The result link has expired, fetch the transcript again with `prepis jobs fetch`."
+            ),
+            _ => error!("Check the job name with `prepis jobs list`."),
+        },
+        ErrorClass::Throttled if matches!(error, AppError::WhisperApi { .. }) => {
+            error!("The Whisper server is busy. Wait a moment and try again.")
+        }
+        ErrorClass::Throttled => {
+            error!("An AWS limit was hit. Wait a moment and try again, or run fewer jobs at once.")
         }
-        AppError::Aws(_) => {
-            eprintln!("Please check your AWS credentials and configuration.");
+        ErrorClass::Service => match error {
+            AppError::S3(_) | AppError::Service { service: "S3", .. } => {
+                error!("Please verify the S3 bucket exists and you have access to it.")
+            }
+            AppError::Service { error: service_error, .. } if service_error.status().is_none() => {
+                error!("Could not reach AWS. Please check your network connection, --region and --endpoint-url.")
+            }
+            AppError::WhisperApi { source, .. } if source.status().is_none() => {
+                error!("Could not reach the Whisper server. Please check --whisper-url and that the server is running.")
+            }
+            AppError::WhisperApi { .. } => error!("Please check the logs of the Whisper server."),
+            AppError::WhisperCpp(_) => {
+                error!("Please check that the model file works with your whisper.cpp binary.")
+            }
+            _ => error!("Please check the Amazon Transcribe service status and your permissions."),
+        },
+        ErrorClass::Timeout => match error {
+            AppError::Timeout(_) => error!("Raise the limit with --timeout if jobs need longer."),
+            _ => error!("Please check your network connection."),
+        },
+    }
+}
+
+/// Actionable advice for well-known AWS error codes
+fn code_hint(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "AccessDenied" | "AccessDeniedException" | "AllAccessDisabled" => {
+            "Your credentials are not allowed to do this. Run `prepis doctor` to see which permissions are missing."
+        }
+        "InvalidAccessKeyId" | "InvalidClientTokenId" | "SignatureDoesNotMatch"
+        | "UnrecognizedClientException" => {
+            "Your AWS credentials are not valid. Check them with `aws sts get-caller-identity` or pick another --profile."
+        }
+        "ExpiredToken" | "ExpiredTokenException" => {
+            "Your AWS session has expired. Log in again, e.g. with `aws sso login`."
         }
-        AppError::S3(_) => {
-            eprintln!("Please verify the S3 bucket exists and you have access to it.");
+        "NoSuchBucket" => "The bucket does not exist. Create one with `prepis init-bucket <name>`.",
+        "NoSuchKey" => "The object no longer exists, it may have been removed by a lifecycle rule.",
+        "BadRequestException" | "NotFoundException" => {
+            "Amazon Transcribe rejected the request. Check the job name with `prepis jobs list` and the language and speaker options."
         }
-        AppError::Transcribe(_) => {
-            eprintln!("Please check the Amazon Transcribe service status and your permissions.");
+        "ConflictException" => "A transcription job with this name already exists.",
+        "LimitExceededException" | "ThrottlingException" | "TooManyRequestsException" | "SlowDown"
+        | "Throttling" | "RequestLimitExceeded" => {
+            "An AWS rate or concurrency limit was hit. Wait a moment and try again, or run fewer jobs at once."
         }
-        AppError::Io(_) => {
-            eprintln!("Please check file permissions and disk space.");
+        "InternalFailureException" | "InternalError" | "ServiceUnavailable" => {
+            "AWS had an internal failure. Trying again later usually helps."
         }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_transcribe::operation::get_transcription_job::GetTranscriptionJobError;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::error::ErrorMetadata;
+
+    /// A failed Transcribe call with the given status, error code and message
+    fn service_error(status: u16, code: &str, message: &str) -> AppError {
+        let metadata = ErrorMetadata::builder().code(code).message(message).build();
+        let response = HttpResponse::new(status.try_into().unwrap(), SdkBody::empty());
+        let err = SdkError::service_error(GetTranscriptionJobError::generic(metadata), response);
+        AppError::transcribe("Failed to get job status", err)
+    }
+
+    #[test]
+    fn every_class_has_its_own_exit_code() {
+        let classes = [
+            ErrorClass::User,
+            ErrorClass::Auth,
+            ErrorClass::NotFound,
+            ErrorClass::Throttled,
+            ErrorClass::Service,
+            ErrorClass::Timeout,
+        ];
+
+        let codes: Vec<i32> = classes.iter().map(|class| class.exit_code()).collect();
+        assert_eq!(codes, [3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn aws_errors_are_classified_by_code_before_status() {
+        assert_eq!(service_error(403, "AccessDeniedException", "denied").exit_code(), 4);
+        assert_eq!(service_error(400, "ThrottlingException", "slow down").exit_code(), 6);
+        assert_eq!(service_error(400, "LimitExceededException", "too many jobs").exit_code(), 6);
+        assert_eq!(service_error(404, "NoSuchBucket", "gone").exit_code(), 5);
+        assert_eq!(service_error(400, "ValidationException", "bad language").exit_code(), 3);
+        assert_eq!(service_error(500, "InternalFailureException", "oops").exit_code(), 7);
+    }
+
+    #[test]
+    fn unknown_codes_fall_back_to_the_status() {
+        assert_eq!(classify(Some("Whatever"), Some(401)), ErrorClass::Auth);
+        assert_eq!(classify(None, Some(404)), ErrorClass::NotFound);
+        assert_eq!(classify(None, Some(408)), ErrorClass::Timeout);
+        assert_eq!(classify(None, Some(429)), ErrorClass::Throttled);
+        assert_eq!(classify(None, Some(422)), ErrorClass::User);
+        assert_eq!(classify(None, Some(503)), ErrorClass::Service);
+        assert_eq!(classify(None, None), ErrorClass::Service);
+    }
+
+    #[test]
+    fn unknown_transcription_jobs_are_not_found() {
+        let error = service_error(
+            400,
+            "BadRequestException",
+            "The requested job couldn't be found. Check the job name and try your request again.",
+        );
+
+        assert_eq!(error.class(), ErrorClass::NotFound);
+        assert_eq!(service_error(400, "BadRequestException", "bad request").class(), ErrorClass::User);
+    }
+
+    #[test]
+    fn local_errors_are_classified_by_variant() {
+        assert_eq!(AppError::Config("bad".to_string()).exit_code(), 3);
+        assert_eq!(AppError::File("missing".to_string()).exit_code(), 3);
+        assert_eq!(AppError::Aws("no credentials".to_string()).exit_code(), 4);
+        assert_eq!(AppError::NotFound("job".to_string()).exit_code(), 5);
+        assert_eq!(AppError::Transcribe("job failed".to_string()).exit_code(), 7);
+        assert_eq!(AppError::Timeout("gave up".to_string()).exit_code(), 8);
     }
 }
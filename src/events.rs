@@ -0,0 +1,86 @@
+//! # Event Stream
+//!
+//! This module reports structured progress events of the transcription
+//! workflow to a handler, e.g. the callback given to a
+//! [`Transcriber`](crate::transcriber::Transcriber).
+//!
+//! With `--events ndjson` the CLI writes every event to stdout as a single
+//! JSON object on its own line, tagged with an `event` field:
+//!
+//! ```json
+//! {"event":"job_started","job_name":"transcribe-job-1752500000-talk","s3_uri":"s3://bucket/key","language":"en-US"}
+//! ```
+//!
+//! Human-readable messages go to stderr, so stdout only ever carries events
+//! and the final result.
+
+use serde::Serialize;
+use std::future::Future;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Receives the events of the workflow it is installed for
+pub type EventHandler = Arc<dyn Fn(&Event) + Send + Sync>;
+
+tokio::task_local! {
+    static HANDLER: EventHandler;
+}
+
+/// A progress event of the transcription workflow
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Validated {
+        file: String,
+        size_bytes: u64,
+        duration_seconds: Option<f64>,
+    },
+    UploadProgress {
+        bytes: u64,
+        total_bytes: u64,
+    },
+    JobStarted {
+        job_name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        s3_uri: Option<String>, // Only for media staged in S3
+        language: String,
+    },
+    Status {
+        job_name: String,
+        status: String,
+        elapsed_seconds: u64,
+    },
+    Completed {
+        job_name: String,
+        result_uri: String,
+    },
+    Failed {
+        job_name: String,
+        reason: String,
+    },
+    Cleanup {
+        bucket: String,
+        key: String,
+    },
+}
+
+/// Run a future with a handler that receives every event it emits
+///
+/// Scopes can be nested, the innermost handler wins.
+pub async fn scope<F: Future>(handler: EventHandler, future: F) -> F::Output {
+    HANDLER.scope(handler, future).await
+}
+
+/// Pass an event to the handler of the current scope, if there is one
+pub fn emit(event: Event) {
+    let _ = HANDLER.try_with(|handler| handler(&event));
+}
+
+/// Handler that writes every event to stdout as a line of JSON
+pub fn write_ndjson(event: &Event) {
+    if let Ok(line) = serde_json::to_string(event) {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", line);
+        let _ = stdout.flush();
+    }
+}
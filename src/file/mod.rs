@@ -6,13 +6,19 @@
 //! - File validation and verification
 //! - File format checking
 //! - File size validation
+//! - Reading the duration and audio format of media files
 //! - Reading and writing transcription files
+//! - Building silent WAV files
 //!
 //! The module ensures that files meet the requirements for Amazon Transcribe
 //! before they are processed.
 
+pub mod probe;
 pub mod validation;
+pub mod wav;
 pub mod writing;
 
-pub use validation::validate_video_file;
-pub use writing::save_transcription;
+pub use probe::{AudioTrack, MediaInfo, media_duration};
+pub use validation::{find_media_files, validate_video_file};
+pub use wav::silent_wav;
+pub use writing::{check_directory_writable, check_writable, save_transcription};
@@ -0,0 +1,1085 @@
+//! # Media Probing
+//!
+//! This module reads the duration and audio stream details of a media file
+//! from its container headers, without decoding any audio.
+//!
+//! It understands:
+//! - MP4, MOV and M4A files (the `mvhd` box and the `stsd` box of audio tracks)
+//! - WAV files (the `fmt ` chunk and the size of `data`)
+//! - FLAC files (the `STREAMINFO` block)
+//! - MP3 files (the first frame header and its Xing, Info or VBRI header)
+//! - Matroska and WebM files (the `Info` and `Tracks` elements)
+//!
+//! The container is recognized by its leading bytes rather than the file
+//! extension. Probing is best effort: other formats, or files that cannot be
+//! parsed, simply have no known details.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// What the container headers say about a media file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub container: &'static str,
+    pub duration: Option<Duration>,
+    pub audio: AudioTrack,
+}
+
+/// The first audio track of a media file
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioTrack {
+    Found(AudioStream),
+    Missing, // The container lists its tracks and none of them is audio
+    Unknown, // The tracks could not be read
+}
+
+/// Details of an audio stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioStream {
+    pub codec: String,
+    pub channels: Option<u16>,
+    pub sample_rate: Option<u32>, // In Hz
+}
+
+impl fmt::Display for AudioStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.codec)?;
+        if let Some(channels) = self.channels {
+            write!(f, ", {} ch", channels)?;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            write!(f, ", {} Hz", sample_rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the container headers of a media file, if the container is supported
+pub fn probe_media(path: &Path) -> Option<MediaInfo> {
+    let mut file = File::open(path).ok()?;
+    let magic = read_at(&mut file, 0, 12)?;
+
+    if &magic[0..4] == b"RIFF" && &magic[8..12] == b"WAVE" {
+        probe_wav(&mut file)
+    } else if &magic[0..4] == b"fLaC" {
+        probe_flac(&mut file)
+    } else if magic[0..4] == [0x1A, 0x45, 0xDF, 0xA3] {
+        probe_matroska(&mut file)
+    } else if matches!(&magic[4..8], b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip") {
+        probe_mp4(&mut file)
+    } else if &magic[0..3] == b"ID3" || parse_mp3_frame(&magic[0..4]).is_some() {
+        probe_mp3(&mut file)
+    } else {
+        None
+    }
+}
+
+/// Read the duration of a media file, if the container is supported
+pub fn media_duration(path: &Path) -> Option<Duration> {
+    probe_media(path)?.duration
+}
+
+/// Read `len` bytes at an offset
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+/// Read up to `len` bytes at an offset, fewer at the end of the file
+fn read_up_to(file: &mut File, offset: u64, len: u64) -> Option<Vec<u8>> {
+    let mut buffer = Vec::new();
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.take(len).read_to_end(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Duration of a number of ticks at a rate, `None` for an unknown rate
+///
+/// Durations too long to represent are unknown as well, headers can claim
+/// anything.
+fn ticks_to_duration(ticks: u64, rate: u64) -> Option<Duration> {
+    if rate == 0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(ticks as f64 / rate as f64).ok()
+}
+
+// MP4, MOV and M4A
+
+fn probe_mp4(file: &mut File) -> Option<MediaInfo> {
+    let file_len = file.metadata().ok()?.len();
+    let (moov_start, moov_end) = find_box(file, 0, file_len, b"moov")?;
+    let duration = mvhd_duration(file, moov_start, moov_end);
+
+    // Without any track there is nothing to say about the audio
+    let mut audio = AudioTrack::Unknown;
+    for (box_type, trak_start, trak_end) in child_boxes(file, moov_start, moov_end) {
+        if &box_type != b"trak" {
+            continue;
+        }
+        if audio == AudioTrack::Unknown {
+            audio = AudioTrack::Missing;
+        }
+        match mp4_track_handler(file, trak_start, trak_end) {
+            Some(handler) if &handler == b"soun" => {
+                // A sound track whose sample entry cannot be read still has audio
+                let stream = mp4_audio_stream(file, trak_start, trak_end).unwrap_or(AudioStream {
+                    codec: "unknown".to_string(),
+                    channels: None,
+                    sample_rate: None,
+                });
+                audio = AudioTrack::Found(stream);
+                break;
+            }
+            Some(_) => {}
+            None => audio = AudioTrack::Unknown,
+        }
+    }
+
+    Some(MediaInfo {
+        container: "mp4",
+        duration,
+        audio,
+    })
+}
+
+/// Compute duration / timescale from the `mvhd` box inside `moov`
+fn mvhd_duration(file: &mut File, moov_start: u64, moov_end: u64) -> Option<Duration> {
+    let (mvhd_start, _) = find_box(file, moov_start, moov_end, b"mvhd")?;
+    let version = read_at(file, mvhd_start, 1)?[0];
+
+    // Version 1 uses 64-bit creation, modification and duration fields
+    if version == 1 {
+        let header = read_at(file, mvhd_start + 4, 28)?;
+        let timescale = be_u32(&header[16..20]) as u64;
+        let duration = u64::from_be_bytes(header[20..28].try_into().ok()?);
+        ticks_to_duration(duration, timescale)
+    } else {
+        let header = read_at(file, mvhd_start + 4, 16)?;
+        ticks_to_duration(be_u32(&header[12..16]) as u64, be_u32(&header[8..12]) as u64)
+    }
+}
+
+/// Handler type of a track, e.g. `soun` for audio and `vide` for video
+fn mp4_track_handler(file: &mut File, trak_start: u64, trak_end: u64) -> Option<[u8; 4]> {
+    let (mdia_start, mdia_end) = find_box(file, trak_start, trak_end, b"mdia")?;
+    let (hdlr_start, _) = find_box(file, mdia_start, mdia_end, b"hdlr")?;
+    // Version and flags, then a predefined field before the handler type
+    read_at(file, hdlr_start + 8, 4)?.try_into().ok()
+}
+
+/// Read codec, channels and sample rate from the first sample entry of a track
+fn mp4_audio_stream(file: &mut File, trak_start: u64, trak_end: u64) -> Option<AudioStream> {
+    let (mdia_start, mdia_end) = find_box(file, trak_start, trak_end, b"mdia")?;
+    let (minf_start, minf_end) = find_box(file, mdia_start, mdia_end, b"minf")?;
+    let (stbl_start, stbl_end) = find_box(file, minf_start, minf_end, b"stbl")?;
+    let (stsd_start, _) = find_box(file, stbl_start, stbl_end, b"stsd")?;
+
+    // Skip version, flags and the entry count to get to the first sample entry
+    let entry = read_at(file, stsd_start + 8, 36)?;
+    let codec = mp4_codec_name(&entry[4..8]);
+
+    // QuickTime version 2 sound descriptions moved the fields
+    let (channels, sample_rate) = if be_u16(&entry[16..18]) == 2 {
+        let entry = read_at(file, stsd_start + 8, 56)?;
+        let sample_rate = f64::from_bits(u64::from_be_bytes(entry[40..48].try_into().ok()?));
+        (be_u32(&entry[48..52]) as u16, sample_rate as u32)
+    } else {
+        // The sample rate is a 16.16 fixed point number
+        (be_u16(&entry[24..26]), be_u32(&entry[32..36]) >> 16)
+    };
+
+    Some(AudioStream {
+        codec,
+        channels: (channels > 0).then_some(channels),
+        sample_rate: (sample_rate > 0).then_some(sample_rate),
+    })
+}
+
+/// Common name of an MP4 sample entry type
+fn mp4_codec_name(fourcc: &[u8]) -> String {
+    match fourcc {
+        b"mp4a" => "aac".to_string(),
+        b"alac" => "alac".to_string(),
+        b"Opus" => "opus".to_string(),
+        b"fLaC" => "flac".to_string(),
+        b"ac-3" => "ac3".to_string(),
+        b"ec-3" => "eac3".to_string(),
+        b".mp3" => "mp3".to_string(),
+        b"samr" => "amr".to_string(),
+        b"lpcm" | b"sowt" | b"twos" | b"in24" | b"in32" | b"fl32" | b"fl64" | b"raw " => {
+            "pcm".to_string()
+        }
+        other => String::from_utf8_lossy(other).trim().to_string(),
+    }
+}
+
+/// Look for a box of the given type between two offsets, returning its payload range
+fn find_box(file: &mut File, start: u64, end: u64, box_type: &[u8; 4]) -> Option<(u64, u64)> {
+    child_boxes(file, start, end)
+        .into_iter()
+        .find(|(found, _, _)| found == box_type)
+        .map(|(_, start, end)| (start, end))
+}
+
+/// List the boxes between two offsets with their payload ranges
+///
+/// Stops at the first box that cannot be read, returning the ones before it.
+/// A box that runs past its parent is cut off there and ends the list, as in
+/// a truncated file.
+fn child_boxes(file: &mut File, start: u64, end: u64) -> Vec<([u8; 4], u64, u64)> {
+    let mut boxes = Vec::new();
+    let mut offset = start;
+
+    while offset + 8 <= end {
+        let Some(header) = read_at(file, offset, 8) else {
+            break;
+        };
+
+        let mut size = be_u32(&header[0..4]) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            // 64-bit size follows the type
+            let Some(large_size) = read_at(file, offset + 8, 8) else {
+                break;
+            };
+            size = u64::from_be_bytes(large_size.try_into().unwrap_or_default());
+            header_len = 16;
+        } else if size == 0 {
+            // Box extends to the end of its parent
+            size = end - offset;
+        }
+        if size < header_len {
+            break;
+        }
+        let Some(box_end) = offset.checked_add(size) else {
+            break;
+        };
+
+        let box_type = [header[4], header[5], header[6], header[7]];
+        boxes.push((box_type, offset + header_len, box_end.min(end)));
+        if box_end >= end {
+            break;
+        }
+        offset = box_end;
+    }
+
+    boxes
+}
+
+// WAV
+
+/// Read the format of a WAV file and compute its duration from the byte rate and data size
+fn probe_wav(file: &mut File) -> Option<MediaInfo> {
+    let file_len = file.metadata().ok()?.len();
+    let mut offset = 12; // After RIFF, the size and WAVE
+    let mut format = None;
+
+    loop {
+        let chunk = read_at(file, offset, 8)?;
+        let size = le_u32(&chunk[4..8]) as u64;
+
+        match &chunk[0..4] {
+            b"fmt " => {
+                let fmt = read_at(file, offset + 8, 16)?;
+                format = Some((
+                    le_u16(&fmt[0..2]),   // Format tag
+                    le_u16(&fmt[2..4]),   // Channels
+                    le_u32(&fmt[4..8]),   // Sample rate
+                    le_u32(&fmt[8..12]),  // Byte rate
+                ));
+            }
+            b"data" => {
+                let (format_tag, channels, sample_rate, byte_rate) = format?;
+                // Streamed files may not know the size of their data yet
+                let size = if size == u32::MAX as u64 {
+                    file_len.saturating_sub(offset + 8)
+                } else {
+                    size
+                };
+
+                return Some(MediaInfo {
+                    container: "wav",
+                    duration: ticks_to_duration(size, byte_rate as u64),
+                    audio: AudioTrack::Found(AudioStream {
+                        codec: wav_codec_name(format_tag).to_string(),
+                        channels: Some(channels),
+                        sample_rate: Some(sample_rate),
+                    }),
+                });
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even size
+        offset += 8 + size + size % 2;
+    }
+}
+
+/// Common name of a WAV format tag
+fn wav_codec_name(format_tag: u16) -> &'static str {
+    match format_tag {
+        0x0001 | 0xFFFE => "pcm", // Plain and extensible PCM
+        0x0003 => "pcm_float",
+        0x0006 => "alaw",
+        0x0007 => "mulaw",
+        0x0055 => "mp3",
+        _ => "unknown",
+    }
+}
+
+// FLAC
+
+/// Read the `STREAMINFO` metadata block, which every FLAC file starts with
+fn probe_flac(file: &mut File) -> Option<MediaInfo> {
+    let mut offset = 4; // After fLaC
+
+    loop {
+        let header = read_at(file, offset, 4)?;
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7F;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+
+        if block_type == 0 {
+            let info = read_at(file, offset + 4, 18)?;
+            // 20 bits sample rate, 3 bits channels - 1, 5 bits bits per sample - 1, 36 bits samples
+            let sample_rate = (info[10] as u32) << 12 | (info[11] as u32) << 4 | (info[12] as u32) >> 4;
+            let channels = ((info[12] >> 1) & 0x07) as u16 + 1;
+            let total_samples = ((info[13] & 0x0F) as u64) << 32 | be_u32(&info[14..18]) as u64;
+
+            return Some(MediaInfo {
+                container: "flac",
+                // Encoders that do not know the length write zero samples
+                duration: ticks_to_duration(total_samples, sample_rate as u64)
+                    .filter(|d| !d.is_zero()),
+                audio: AudioTrack::Found(AudioStream {
+                    codec: "flac".to_string(),
+                    channels: Some(channels),
+                    sample_rate: (sample_rate > 0).then_some(sample_rate),
+                }),
+            });
+        }
+        if is_last {
+            return None;
+        }
+        offset += 4 + len;
+    }
+}
+
+// MP3
+
+/// Fields of an MPEG audio frame header
+#[derive(Debug)]
+struct Mp3Frame {
+    mpeg1: bool,
+    layer: u8,
+    bitrate_kbps: u32,
+    sample_rate: u32,
+    channels: u16,
+    samples: u32, // Samples per frame
+    length: usize, // Bytes including the header
+}
+
+/// Read the first frame and compute the duration from a VBR header or the bitrate
+fn probe_mp3(file: &mut File) -> Option<MediaInfo> {
+    let file_len = file.metadata().ok()?.len();
+
+    // Skip an ID3v2 tag, whose size is stored in 7-bit bytes
+    let mut start = 0;
+    let tag = read_at(file, 0, 10)?;
+    if &tag[0..3] == b"ID3" {
+        let size = tag[6..10].iter().fold(0u64, |size, byte| size << 7 | (byte & 0x7F) as u64);
+        let footer = if tag[5] & 0x10 != 0 { 10 } else { 0 };
+        start = 10 + size + footer;
+    }
+
+    // Find a frame header that is followed by another one, to skip false syncs
+    let buffer = read_up_to(file, start, 64 * 1024)?;
+    let (position, frame) = (0..buffer.len().saturating_sub(4)).find_map(|i| {
+        let frame = parse_mp3_frame(&buffer[i..i + 4])?;
+        let next = i + frame.length;
+        let confirmed = next + 4 > buffer.len() || parse_mp3_frame(&buffer[next..next + 4]).is_some();
+        confirmed.then_some((i, frame))
+    })?;
+
+    // VBR files announce their frame count in the first frame
+    let side_info = match (frame.mpeg1, frame.channels) {
+        (true, 1) => 17,
+        (true, _) => 32,
+        (false, 1) => 9,
+        (false, _) => 17,
+    };
+    let xing = position + 4 + side_info;
+    let vbri = position + 4 + 32;
+    let frame_count = if matches!(buffer.get(xing..xing + 4), Some(b"Xing" | b"Info"))
+        && buffer.get(xing + 4..xing + 8).is_some_and(|flags| flags[3] & 0x01 != 0)
+    {
+        buffer.get(xing + 8..xing + 12).map(be_u32)
+    } else if buffer.get(vbri..vbri + 4) == Some(b"VBRI") {
+        buffer.get(vbri + 14..vbri + 18).map(be_u32)
+    } else {
+        None
+    };
+
+    let duration = match frame_count {
+        Some(frames) => ticks_to_duration(frames as u64 * frame.samples as u64, frame.sample_rate as u64),
+        // Constant bitrate: the audio size divided by the bitrate
+        None => ticks_to_duration(
+            file_len.saturating_sub(start + position as u64) * 8,
+            frame.bitrate_kbps as u64 * 1000,
+        ),
+    };
+
+    Some(MediaInfo {
+        container: "mp3",
+        duration,
+        audio: AudioTrack::Found(AudioStream {
+            codec: format!("mp{}", frame.layer),
+            channels: Some(frame.channels),
+            sample_rate: Some(frame.sample_rate),
+        }),
+    })
+}
+
+/// Parse a 4-byte MPEG audio frame header
+fn parse_mp3_frame(header: &[u8]) -> Option<Mp3Frame> {
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+
+    // 0 is MPEG 2.5, 2 is MPEG 2 and 3 is MPEG 1
+    let version = (header[1] >> 3) & 0x03;
+    let layer = match (header[1] >> 1) & 0x03 {
+        3 => 1,
+        2 => 2,
+        1 => 3,
+        _ => return None,
+    };
+    let bitrate_index = (header[2] >> 4) as usize;
+    let sample_rate_index = ((header[2] >> 2) & 0x03) as usize;
+    let padding = ((header[2] >> 1) & 0x01) as u32;
+    let channels = if header[3] >> 6 == 3 { 1 } else { 2 };
+    if version == 1 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let mpeg1 = version == 3;
+    let bitrate_kbps = match (mpeg1, layer) {
+        (true, 1) => [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+        (true, 2) => [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+        (true, _) => [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+        (false, 1) => [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+        (false, _) => [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    }[bitrate_index];
+    let sample_rate = match version {
+        3 => [44100, 48000, 32000],
+        2 => [22050, 24000, 16000],
+        _ => [11025, 12000, 8000],
+    }[sample_rate_index];
+
+    let samples = match (mpeg1, layer) {
+        (_, 1) => 384,
+        (false, 3) => 576,
+        _ => 1152,
+    };
+    let length = if layer == 1 {
+        (12 * bitrate_kbps * 1000 / sample_rate + padding) * 4
+    } else {
+        samples / 8 * bitrate_kbps * 1000 / sample_rate + padding
+    };
+
+    Some(Mp3Frame {
+        mpeg1,
+        layer,
+        bitrate_kbps,
+        sample_rate,
+        channels,
+        samples,
+        length: length as usize,
+    })
+}
+
+// Matroska and WebM
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const EBML_DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const SEGMENT_INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const SEGMENT_DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+
+/// Read the segment duration and the first audio track from the EBML elements
+fn probe_matroska(file: &mut File) -> Option<MediaInfo> {
+    let file_len = file.metadata().ok()?.len();
+    let (id, header_start, header_end) = ebml_element(file, 0, file_len)?;
+    if id != EBML_HEADER {
+        return None;
+    }
+    let container = match ebml_children(file, header_start, header_end)
+        .into_iter()
+        .find(|(id, _, _)| *id == EBML_DOC_TYPE)
+        .and_then(|(_, start, end)| ebml_bytes(file, start, end))
+    {
+        Some(doc_type) if doc_type.starts_with(b"webm") => "webm",
+        _ => "matroska",
+    };
+
+    let (id, segment_start, segment_end) = ebml_element(file, header_end, file_len)?;
+    if id != SEGMENT {
+        return None;
+    }
+
+    let mut timestamp_scale = 1_000_000; // Nanoseconds per tick unless stated otherwise
+    let mut duration_ticks = None;
+    let mut audio = AudioTrack::Unknown;
+    for (id, start, end) in ebml_children(file, segment_start, segment_end) {
+        match id {
+            SEGMENT_INFO => {
+                for (id, start, end) in ebml_children(file, start, end) {
+                    match id {
+                        TIMESTAMP_SCALE => timestamp_scale = ebml_uint(file, start, end)?,
+                        SEGMENT_DURATION => duration_ticks = ebml_float(file, start, end),
+                        _ => {}
+                    }
+                }
+            }
+            TRACKS => audio = matroska_audio_track(file, start, end),
+            // Info and Tracks come before the media data
+            CLUSTER => break,
+            _ => {}
+        }
+    }
+
+    Some(MediaInfo {
+        container,
+        duration: duration_ticks
+            .filter(|ticks| ticks.is_finite() && *ticks > 0.0)
+            .and_then(|ticks| Duration::try_from_secs_f64(ticks * timestamp_scale as f64 / 1e9).ok()),
+        audio,
+    })
+}
+
+/// Find the first audio track among the track entries
+fn matroska_audio_track(file: &mut File, tracks_start: u64, tracks_end: u64) -> AudioTrack {
+    for (id, entry_start, entry_end) in ebml_children(file, tracks_start, tracks_end) {
+        if id != TRACK_ENTRY {
+            continue;
+        }
+
+        let mut track_type = None;
+        let mut codec_id = None;
+        let mut stream = AudioStream {
+            codec: "unknown".to_string(),
+            channels: None,
+            sample_rate: None,
+        };
+        for (id, start, end) in ebml_children(file, entry_start, entry_end) {
+            match id {
+                TRACK_TYPE => track_type = ebml_uint(file, start, end),
+                CODEC_ID => codec_id = ebml_bytes(file, start, end),
+                AUDIO => {
+                    for (id, start, end) in ebml_children(file, start, end) {
+                        match id {
+                            SAMPLING_FREQUENCY => {
+                                stream.sample_rate = ebml_float(file, start, end).map(|f| f as u32)
+                            }
+                            CHANNELS => stream.channels = ebml_uint(file, start, end).map(|c| c as u16),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Track type 2 is audio
+        if track_type == Some(2) {
+            if let Some(codec_id) = codec_id {
+                stream.codec = matroska_codec_name(&String::from_utf8_lossy(&codec_id));
+            }
+            // The default sampling frequency is 8000 Hz
+            stream.sample_rate = stream.sample_rate.or(Some(8000));
+            return AudioTrack::Found(stream);
+        }
+    }
+
+    AudioTrack::Missing
+}
+
+/// Common name of a Matroska codec ID such as `A_OPUS` or `A_MPEG/L3`
+fn matroska_codec_name(codec_id: &str) -> String {
+    let codec = codec_id.trim_end_matches('\0').trim_start_matches("A_").to_lowercase();
+    match codec.as_str() {
+        "mpeg/l3" => "mp3".to_string(),
+        "mpeg/l2" => "mp2".to_string(),
+        "ac3" | "eac3" | "opus" | "vorbis" | "flac" | "alac" => codec,
+        _ if codec.starts_with("aac") => "aac".to_string(),
+        _ if codec.starts_with("pcm") => "pcm".to_string(),
+        _ => codec,
+    }
+}
+
+/// Read the element at an offset, returning its ID and payload range
+fn ebml_element(file: &mut File, offset: u64, parent_end: u64) -> Option<(u32, u64, u64)> {
+    let header = read_up_to(file, offset, 12)?;
+
+    // The number of leading zeros of the first byte gives the length of each number
+    let id_len = header.first()?.leading_zeros() as usize + 1;
+    if id_len > 4 {
+        return None;
+    }
+    let id = header.get(..id_len)?.iter().fold(0u32, |id, byte| id << 8 | *byte as u32);
+
+    let size_bytes = header.get(id_len..)?;
+    let size_len = size_bytes.first()?.leading_zeros() as usize + 1;
+    if size_len > 8 {
+        return None;
+    }
+    let first = size_bytes[0] & 0xFFu8.checked_shr(size_len as u32).unwrap_or(0);
+    let size = size_bytes
+        .get(1..size_len)?
+        .iter()
+        .fold(first as u64, |size, byte| size << 8 | *byte as u64);
+    // All bits set means the size is unknown and the element runs to the end of its parent
+    let unknown_size = size == (1u64 << (7 * size_len)) - 1;
+
+    // The header itself must fit into the parent
+    let start = offset + (id_len + size_len) as u64;
+    if start > parent_end {
+        return None;
+    }
+    let end = if unknown_size { parent_end } else { start.saturating_add(size).min(parent_end) };
+    Some((id, start, end))
+}
+
+/// List the elements between two offsets with their payload ranges
+fn ebml_children(file: &mut File, start: u64, end: u64) -> Vec<(u32, u64, u64)> {
+    let mut children = Vec::new();
+    let mut offset = start;
+
+    while offset < end {
+        let Some((id, child_start, child_end)) = ebml_element(file, offset, end) else {
+            break;
+        };
+        children.push((id, child_start, child_end));
+        offset = child_end;
+    }
+
+    children
+}
+
+/// Read an unsigned integer element
+fn ebml_uint(file: &mut File, start: u64, end: u64) -> Option<u64> {
+    let bytes = read_at(file, start, (end - start).min(8) as usize)?;
+    Some(bytes.iter().fold(0u64, |value, byte| value << 8 | *byte as u64))
+}
+
+/// Read a 4 or 8 byte float element
+fn ebml_float(file: &mut File, start: u64, end: u64) -> Option<f64> {
+    match end - start {
+        4 => Some(f32::from_be_bytes(read_at(file, start, 4)?.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(read_at(file, start, 8)?.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// Read a short string or binary element, such as a codec ID
+///
+/// Only the first bytes are read, an oversized element must not make us
+/// read the whole file.
+fn ebml_bytes(file: &mut File, start: u64, end: u64) -> Option<Vec<u8>> {
+    read_up_to(file, start, (end - start).min(64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Probe a media file made of the given bytes
+    fn probe(bytes: &[u8]) -> Option<MediaInfo> {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        probe_media(file.path())
+    }
+
+    fn stream(codec: &str, channels: u16, sample_rate: u32) -> AudioTrack {
+        AudioTrack::Found(AudioStream {
+            codec: codec.to_string(),
+            channels: Some(channels),
+            sample_rate: Some(sample_rate),
+        })
+    }
+
+    // MP4
+
+    fn mp4_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (8 + payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(box_type);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn mvhd(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut payload = vec![0u8; 12]; // Version, flags, creation and modification time
+        payload.extend_from_slice(&timescale.to_be_bytes());
+        payload.extend_from_slice(&duration.to_be_bytes());
+        mp4_box(b"mvhd", &payload)
+    }
+
+    fn trak(handler: &[u8; 4]) -> Vec<u8> {
+        let hdlr = mp4_box(b"hdlr", &[&[0u8; 8][..], handler].concat());
+
+        let mut entry = vec![0u8; 36];
+        entry[0..4].copy_from_slice(&36u32.to_be_bytes());
+        entry[4..8].copy_from_slice(b"mp4a");
+        entry[24..26].copy_from_slice(&2u16.to_be_bytes());
+        entry[32..36].copy_from_slice(&(44_100u32 << 16).to_be_bytes());
+        let stsd = mp4_box(b"stsd", &[&[0, 0, 0, 0, 0, 0, 0, 1][..], &entry].concat());
+
+        let minf = mp4_box(b"minf", &mp4_box(b"stbl", &stsd));
+        mp4_box(b"trak", &mp4_box(b"mdia", &[hdlr, minf].concat()))
+    }
+
+    fn mp4(moov: &[u8]) -> Vec<u8> {
+        [mp4_box(b"ftyp", b"isom\0\0\0\0"), mp4_box(b"moov", moov)].concat()
+    }
+
+    #[test]
+    fn mp4_duration_and_audio_track() {
+        let info = probe(&mp4(&[mvhd(1000, 90_500), trak(b"vide"), trak(b"soun")].concat())).unwrap();
+
+        assert_eq!(info.container, "mp4");
+        assert_eq!(info.duration, Some(Duration::from_millis(90_500)));
+        assert_eq!(info.audio, stream("aac", 2, 44_100));
+    }
+
+    #[test]
+    fn mp4_without_sound_track_has_no_audio() {
+        let info = probe(&mp4(&[mvhd(600, 600), trak(b"vide")].concat())).unwrap();
+
+        assert_eq!(info.duration, Some(Duration::from_secs(1)));
+        assert_eq!(info.audio, AudioTrack::Missing);
+    }
+
+    #[test]
+    fn mp4_duration_too_long_to_represent_is_unknown() {
+        let mut payload = vec![1, 0, 0, 0]; // Version 1
+        payload.extend_from_slice(&[0u8; 16]);
+        payload.extend_from_slice(&1u32.to_be_bytes());
+        payload.extend_from_slice(&u64::MAX.to_be_bytes());
+
+        let info = probe(&mp4(&mp4_box(b"mvhd", &payload))).unwrap();
+
+        assert_eq!(info.duration, None);
+        assert_eq!(info.audio, AudioTrack::Unknown);
+    }
+
+    #[test]
+    fn mp4_oversized_and_undersized_boxes_end_the_box_list() {
+        // A 64-bit size that overflows the offset
+        let mut huge = 1u32.to_be_bytes().to_vec();
+        huge.extend_from_slice(b"free");
+        huge.extend_from_slice(&(u64::MAX - 4).to_be_bytes());
+        let info = probe(&mp4(&[huge, mvhd(1000, 1000)].concat())).unwrap();
+        assert_eq!(info.duration, None);
+
+        // A size smaller than the box header
+        let mut tiny = 4u32.to_be_bytes().to_vec();
+        tiny.extend_from_slice(b"free");
+        let info = probe(&mp4(&[tiny, mvhd(1000, 1000)].concat())).unwrap();
+        assert_eq!(info.duration, None);
+
+        // A box running past its parent is cut off at the parent's end
+        let mut long = mvhd(1000, 2000);
+        long[0..4].copy_from_slice(&1000u32.to_be_bytes());
+        let info = probe(&mp4(&long)).unwrap();
+        assert_eq!(info.duration, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn truncated_mp4_is_probed_as_far_as_possible() {
+        let file = mp4(&[mvhd(1000, 3000), trak(b"soun")].concat());
+
+        // Cut inside the sample entry: the duration is known, the codec is not
+        let info = probe(&file[..file.len() - 10]).unwrap();
+        assert_eq!(info.duration, Some(Duration::from_secs(3)));
+        assert!(matches!(info.audio, AudioTrack::Found(AudioStream { ref codec, .. }) if codec == "unknown"));
+
+        // Cut inside mvhd
+        let info = probe(&file[..30]).unwrap();
+        assert_eq!(info.duration, None);
+    }
+
+    // WAV
+
+    fn wav(format: Option<(u16, u16, u32, u32)>, data_size: u32, data: usize) -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        if let Some((format_tag, channels, sample_rate, byte_rate)) = format {
+            bytes.extend_from_slice(b"fmt ");
+            bytes.extend_from_slice(&16u32.to_le_bytes());
+            bytes.extend_from_slice(&format_tag.to_le_bytes());
+            bytes.extend_from_slice(&channels.to_le_bytes());
+            bytes.extend_from_slice(&sample_rate.to_le_bytes());
+            bytes.extend_from_slice(&byte_rate.to_le_bytes());
+            bytes.extend_from_slice(&[2, 0, 16, 0]); // Block align and bits per sample
+        }
+        bytes.extend_from_slice(b"LIST");
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(b"abc\0"); // Odd size plus padding
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        bytes.resize(bytes.len() + data, 0);
+        bytes
+    }
+
+    #[test]
+    fn wav_duration_from_data_size() {
+        let info = probe(&wav(Some((1, 1, 16_000, 32_000)), 64_000, 0)).unwrap();
+
+        assert_eq!(info.container, "wav");
+        assert_eq!(info.duration, Some(Duration::from_secs(2)));
+        assert_eq!(info.audio, stream("pcm", 1, 16_000));
+    }
+
+    #[test]
+    fn streamed_wav_uses_the_file_size() {
+        let info = probe(&wav(Some((1, 1, 16_000, 32_000)), u32::MAX, 16_000)).unwrap();
+
+        assert_eq!(info.duration, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn malformed_wav_has_no_details() {
+        // No fmt chunk before the data
+        assert_eq!(probe(&wav(None, 64_000, 0)), None);
+        // Nothing after the RIFF header
+        assert_eq!(probe(b"RIFF\0\0\0\0WAVE"), None);
+
+        // A byte rate of zero
+        let info = probe(&wav(Some((1, 1, 16_000, 0)), 64_000, 0)).unwrap();
+        assert_eq!(info.duration, None);
+    }
+
+    // FLAC
+
+    fn flac(sample_rate: u64, channels: u64, samples: u64, len: usize) -> Vec<u8> {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend_from_slice(&[0x04, 0, 0, 4]); // A padding block first
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&[0x80, 0, 0, 34]); // The last block, STREAMINFO
+        bytes.extend_from_slice(&[0; 10]);
+        let packed = sample_rate << 44 | (channels - 1) << 41 | 15 << 36 | samples;
+        bytes.extend_from_slice(&packed.to_be_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.truncate(len);
+        bytes
+    }
+
+    #[test]
+    fn flac_duration_from_streaminfo() {
+        let info = probe(&flac(44_100, 2, 441_000, usize::MAX)).unwrap();
+
+        assert_eq!(info.container, "flac");
+        assert_eq!(info.duration, Some(Duration::from_secs(10)));
+        assert_eq!(info.audio, stream("flac", 2, 44_100));
+    }
+
+    #[test]
+    fn flac_without_length_or_rate_has_no_duration() {
+        assert_eq!(probe(&flac(44_100, 2, 0, usize::MAX)).unwrap().duration, None);
+        assert_eq!(probe(&flac(0, 1, 441_000, usize::MAX)).unwrap().duration, None);
+    }
+
+    #[test]
+    fn truncated_flac_has_no_details() {
+        assert_eq!(probe(&flac(44_100, 2, 441_000, 30)), None);
+
+        let mut no_streaminfo = b"fLaC".to_vec();
+        no_streaminfo.extend_from_slice(&[0x84, 0, 0, 4, 0, 0, 0, 0]);
+        assert_eq!(probe(&no_streaminfo), None);
+    }
+
+    // MP3
+
+    /// MPEG 1 layer 3 at 128 kbit/s and 44.1 kHz, 417 bytes per frame
+    fn mp3_frame(xing_frames: Option<u32>) -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[0..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+        if let Some(frames) = xing_frames {
+            frame[36..40].copy_from_slice(b"Xing");
+            frame[40..44].copy_from_slice(&1u32.to_be_bytes());
+            frame[44..48].copy_from_slice(&frames.to_be_bytes());
+        }
+        frame
+    }
+
+    #[test]
+    fn constant_bitrate_mp3_duration_from_file_size() {
+        let file: Vec<u8> = (0..100).flat_map(|_| mp3_frame(None)).collect();
+
+        let info = probe(&file).unwrap();
+
+        assert_eq!(info.container, "mp3");
+        assert_eq!(info.duration, Some(Duration::from_secs_f64(41_700.0 * 8.0 / 128_000.0)));
+        assert_eq!(info.audio, stream("mp3", 2, 44_100));
+    }
+
+    #[test]
+    fn variable_bitrate_mp3_duration_from_xing_header() {
+        let mut file = b"ID3\x04\0\0\0\0\0\x14".to_vec(); // A 20 byte tag
+        file.resize(30, 0);
+        file.extend(mp3_frame(Some(1000)));
+        file.extend(mp3_frame(None));
+
+        let info = probe(&file).unwrap();
+
+        assert_eq!(info.duration, Some(Duration::from_secs_f64(1000.0 * 1152.0 / 44_100.0)));
+    }
+
+    #[test]
+    fn mp3_with_oversized_tag_or_no_frames_has_no_details() {
+        // The tag claims to be larger than the file
+        let mut file = b"ID3\x04\0\0\x7F\x7F\x7F\x7F".to_vec();
+        file.extend(mp3_frame(None));
+        assert_eq!(probe(&file), None);
+
+        // A frame header followed by garbage
+        let mut file = mp3_frame(None);
+        file.truncate(8);
+        file.resize(1000, 0xAB);
+        assert_eq!(probe(&file), None);
+    }
+
+    // Matroska and WebM
+
+    /// An element with an 8 byte size
+    fn ebml(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.push(0x01);
+        bytes.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn webm_header() -> Vec<u8> {
+        ebml(&[0x1A, 0x45, 0xDF, 0xA3], &ebml(&[0x42, 0x82], b"webm"))
+    }
+
+    fn segment_info(duration: f64) -> Vec<u8> {
+        let scale = ebml(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]); // 1 ms
+        let duration = ebml(&[0x44, 0x89], &duration.to_be_bytes());
+        ebml(&[0x15, 0x49, 0xA9, 0x66], &[scale, duration].concat())
+    }
+
+    fn tracks() -> Vec<u8> {
+        let audio = [
+            ebml(&[0xB5], &48_000f32.to_be_bytes()),
+            ebml(&[0x9F], &[2]),
+        ]
+        .concat();
+        let entry = [
+            ebml(&[0x83], &[2]),
+            ebml(&[0x86], b"A_OPUS"),
+            ebml(&[0xE1], &audio),
+        ]
+        .concat();
+        ebml(&[0x16, 0x54, 0xAE, 0x6B], &ebml(&[0xAE], &entry))
+    }
+
+    fn webm(segment: &[u8]) -> Vec<u8> {
+        [webm_header(), ebml(&[0x18, 0x53, 0x80, 0x67], segment)].concat()
+    }
+
+    #[test]
+    fn webm_duration_and_audio_track() {
+        let info = probe(&webm(&[segment_info(12_345.0), tracks()].concat())).unwrap();
+
+        assert_eq!(info.container, "webm");
+        assert_eq!(info.duration, Some(Duration::from_millis(12_345)));
+        assert_eq!(info.audio, stream("opus", 2, 48_000));
+    }
+
+    #[test]
+    fn webm_with_unknown_segment_size() {
+        let mut file = webm_header();
+        file.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        file.extend(segment_info(1000.0));
+        file.extend(tracks());
+
+        let info = probe(&file).unwrap();
+
+        assert_eq!(info.duration, Some(Duration::from_secs(1)));
+        assert_eq!(info.audio, stream("opus", 2, 48_000));
+    }
+
+    #[test]
+    fn non_finite_webm_duration_is_unknown() {
+        for duration in [f64::INFINITY, f64::NAN, -1.0, f64::MAX] {
+            let info = probe(&webm(&segment_info(duration))).unwrap();
+            assert_eq!(info.duration, None, "{}", duration);
+        }
+    }
+
+    #[test]
+    fn malformed_webm_elements_do_not_panic() {
+        // An element claiming to be larger than the file
+        let huge = [0xEC, 0x01, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let info = probe(&webm(&[&segment_info(5000.0)[..], &huge, &tracks()].concat())).unwrap();
+        assert_eq!(info.duration, Some(Duration::from_secs(5)));
+
+        // A codec ID whose header does not fit into its track entry
+        let entry = [0x86, 0x81];
+        let mut tracks = ebml(&[0x16, 0x54, 0xAE, 0x6B], &ebml(&[0xAE], &entry));
+        let len = tracks.len();
+        tracks[len - 3] = 1; // The track entry is one byte long
+        let info = probe(&webm(&tracks)).unwrap();
+        assert_eq!(info.audio, AudioTrack::Missing);
+
+        // A float of the wrong size
+        let duration = ebml(&[0x44, 0x89], &[0; 3]);
+        let info = probe(&webm(&ebml(&[0x15, 0x49, 0xA9, 0x66], &duration))).unwrap();
+        assert_eq!(info.duration, None);
+    }
+
+    #[test]
+    fn truncated_webm_is_probed_as_far_as_possible() {
+        let file = webm(&[segment_info(2000.0), tracks()].concat());
+        let info_len = webm(&segment_info(2000.0)).len();
+
+        let info = probe(&file[..info_len + 5]).unwrap();
+        assert_eq!(info.duration, Some(Duration::from_secs(2)));
+        assert_eq!(info.audio, AudioTrack::Unknown);
+
+        assert_eq!(probe(&file[..20]), None);
+    }
+}
@@ -8,10 +8,17 @@
 //! - File formats are supported by Amazon Transcribe
 //! - File sizes are within acceptable limits
 //! - Files are not empty or corrupted
+//! - Files have an audio track and are not longer than Amazon Transcribe accepts
+//!
+//! It can also find all supported media files in a directory.
 
+use super::probe::{self, AudioTrack, MediaInfo};
 use crate::error::AppError;
+use crate::utils::format_duration;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
 
 /// Supported video file extensions based on Amazon Transcribe documentation
 const SUPPORTED_EXTENSIONS: &[&str] = &[
@@ -21,8 +28,13 @@ const SUPPORTED_EXTENSIONS: &[&str] = &[
 /// Maximum file size supported by Amazon Transcribe (2GB)
 const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024 * 1024; // 2GB in bytes - TODO: Make this customizable
 
+/// Maximum media duration supported by Amazon Transcribe (4 hours)
+const MAX_MEDIA_DURATION: Duration = Duration::from_secs(4 * 60 * 60);
+
 /// Validate that the video file exists, is readable, has a supported format, and is within size limits
-pub fn validate_video_file(path: &Path) -> Result<(), AppError> {
+///
+/// Returns what the container headers say about the file, when they can be read.
+pub fn validate_video_file(path: &Path) -> Result<Option<MediaInfo>, AppError> {
     // Check if file exists
     if !path.exists() {
         return Err(AppError::File(format!(
@@ -67,13 +79,75 @@ pub fn validate_video_file(path: &Path) -> Result<(), AppError> {
         return Err(AppError::File("File is empty".to_string()));
     }
 
-    println!(
-        "✅ File validation passed: {} ({:.2} MB)",
+    // Catch files Transcribe would reject only after the upload
+    let media_info = probe::probe_media(path);
+    if let Some(info) = &media_info {
+        if info.audio == AudioTrack::Missing {
+            return Err(AppError::File(format!(
+                "File has no audio track: {}",
+                path.display()
+            )));
+        }
+        if let Some(duration) = info.duration
+            && duration > MAX_MEDIA_DURATION
+        {
+            return Err(AppError::File(format!(
+                "Media duration ({}) exceeds maximum limit of {}",
+                format_duration(duration),
+                format_duration(MAX_MEDIA_DURATION)
+            )));
+        }
+    }
+
+    let details = media_info
+        .as_ref()
+        .map(|info| {
+            let duration = info.duration.map_or("unknown duration".to_string(), format_duration);
+            match &info.audio {
+                AudioTrack::Found(stream) => format!(", {}, {}", duration, stream),
+                _ => format!(", {}", duration),
+            }
+        })
+        .unwrap_or_default();
+    info!(
+        "✅ File validation passed: {} ({:.2} MB{})",
         path.display(),
-        file_size as f64 / (1024.0 * 1024.0)
+        file_size as f64 / (1024.0 * 1024.0),
+        details
     );
 
-    Ok(())
+    Ok(media_info)
+}
+
+/// Find every media file with a supported extension below a directory, sorted by path
+pub fn find_media_files(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            // Skip hidden files and directories such as .git
+            if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'))
+            {
+                continue;
+            }
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if get_file_extension(&path)
+                .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
 }
 
 /// Extract file extension from path
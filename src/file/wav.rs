@@ -0,0 +1,30 @@
+//! # WAV Files
+//!
+//! This module builds small WAV files, such as the probe media that
+//! `prepis init-bucket` hands to Amazon Transcribe.
+
+/// Build a silent 16 kHz mono 16-bit PCM WAV file of the given length
+pub fn silent_wav(seconds: u32) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 16_000;
+    const BYTES_PER_SAMPLE: u16 = 2;
+
+    let data_len = SAMPLE_RATE * seconds * BYTES_PER_SAMPLE as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * BYTES_PER_SAMPLE as u32).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&BYTES_PER_SAMPLE.to_le_bytes()); // block align
+    wav.extend_from_slice(&(BYTES_PER_SAMPLE * 8).to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+
+    wav
+}
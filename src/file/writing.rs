@@ -3,14 +3,159 @@
 //! This module provides functionality for writing output files to disk
 //!
 //! It handles:
-//! - Saving transcription results to disk.
+//! - Saving transcription results to disk, atomically through a temporary
+//!   file so that an interrupted run or a crash never leaves a half-written
+//!   file
+//! - Refusing to replace existing files unless asked to
+//! - Checking that a directory can be written to before any work is done
 
 use crate::error::AppError;
-use std::fs;
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 /// Save transcription to disk
-pub fn save_transcription(path: impl AsRef<Path>, content: &str) -> Result<(), AppError> {
-    fs::write(path, content)?;
+///
+/// Missing parent directories are created, and existing files are only
+/// replaced with `overwrite`, including ones that appear while writing.
+pub fn save_transcription(
+    path: impl AsRef<Path>,
+    content: impl AsRef<[u8]>,
+    overwrite: bool,
+) -> Result<(), AppError> {
+    let path = path.as_ref();
+    check_writable(path, overwrite)?;
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = temp_path(path);
+    let result = write_synced(&temp_path, content.as_ref()).and_then(|_| publish(&temp_path, path, overwrite));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        if e.kind() == io::ErrorKind::AlreadyExists {
+            return check_writable(path, false);
+        }
+        return Err(AppError::File(format!("Failed to write {}: {}", path.display(), e)));
+    }
+    Ok(())
+}
+
+/// Write a file and flush it to the disk
+fn write_synced(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Move a written temporary file into place
+///
+/// Unlike a rename, a hard link fails when the target exists, so a file that
+/// appeared since it was checked is not replaced without `overwrite`. File
+/// systems without hard links fall back to a rename.
+fn publish(temp_path: &Path, path: &Path, overwrite: bool) -> io::Result<()> {
+    if overwrite {
+        return fs::rename(temp_path, path);
+    }
+    match fs::hard_link(temp_path, path) {
+        Ok(()) => fs::remove_file(temp_path),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        Err(_) if path.exists() => Err(io::ErrorKind::AlreadyExists.into()),
+        Err(_) => fs::rename(temp_path, path),
+    }
+}
+
+/// Fail when a file exists that must not be replaced
+pub fn check_writable(path: &Path, overwrite: bool) -> Result<(), AppError> {
+    if !overwrite && path.exists() {
+        return Err(AppError::File(format!(
+            "{} already exists, pass --force to replace it",
+            path.display()
+        )));
+    }
     Ok(())
 }
+
+/// Fail when the file could not be created in its directory
+///
+/// Missing directories are created when saving, so the nearest existing one
+/// is checked by creating and removing a temporary file in it.
+pub fn check_directory_writable(path: &Path) -> Result<(), AppError> {
+    let mut dir = path.parent().unwrap_or(Path::new(""));
+    while !dir.as_os_str().is_empty() && !dir.exists() {
+        dir = dir.parent().unwrap_or(Path::new(""));
+    }
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let probe_path = dir.join(format!(".prepis-write-check.{}.tmp", std::process::id()));
+    match File::options().write(true).create_new(true).open(&probe_path) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe_path);
+            Ok(())
+        }
+        Err(e) => Err(AppError::File(format!(
+            "Cannot write {} into {}: {}",
+            path.display(),
+            dir.display(),
+            e
+        ))),
+    }
+}
+
+/// Hidden temporary file next to the target, so the rename stays on one file system
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_files_are_only_replaced_with_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/talk.txt");
+
+        save_transcription(&path, "first", false).unwrap();
+        let error = save_transcription(&path, "second", false).unwrap_err();
+        assert!(error.to_string().contains("--force"), "{}", error);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        save_transcription(&path, "third", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1); // No temporary files left
+    }
+
+    #[test]
+    fn file_that_appeared_after_the_check_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("talk.txt");
+        let temp = temp_path(&path);
+        write_synced(&temp, b"new").unwrap();
+        fs::write(&path, "appeared").unwrap();
+
+        let error = publish(&temp, &path, false).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "appeared");
+        publish(&temp, &path, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[test]
+    fn directories_that_cannot_hold_files_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes");
+        fs::write(&file, "").unwrap();
+
+        check_directory_writable(&dir.path().join("missing/deeper/talk.txt")).unwrap();
+        assert!(check_directory_writable(&file.join("talk.txt")).is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1); // The check leaves nothing behind
+    }
+}
@@ -0,0 +1,51 @@
+//! # Prepis
+//!
+//! Prepis transcribes video and audio files with Amazon Transcribe. Next to
+//! the `prepis` command line tool it can be used as a library, e.g. by
+//! services that want the same upload and transcribe pipeline.
+//!
+//! The library provides:
+//! - [`Transcriber`], a builder based pipeline that validates, uploads and
+//!   transcribes a file and returns its [`Transcript`]
+//! - The [`TranscriptionBackend`] trait, implemented for Amazon Transcribe
+//!   and Whisper (see [`backend`])
+//! - The [`MediaStaging`](staging::MediaStaging) trait for the storage media
+//!   is uploaded to, implemented for S3, memory and a local directory (see
+//!   [`staging`])
+//! - Progress reporting through a callback or a channel (see [`events`])
+//! - The AWS building blocks the pipeline is made of (see [`aws`])
+//! - Media validation and probing (see [`file`])
+//! - [`AppError`] with an [`ErrorClass`] for every failure
+//!
+//! ## Transcription Workflow
+//!
+//! 1. Validate input file
+//! 2. Stage the file for the backend, e.g. upload it to S3
+//! 3. Start the transcription job
+//! 4. Poll for job completion
+//! 5. Retrieve the results
+//! 6. Clean up the staged file
+
+pub mod aws;
+pub mod backend;
+pub mod config;
+pub mod error;
+pub mod events;
+pub mod file;
+pub mod logging;
+pub mod models;
+pub mod pending;
+pub mod pricing;
+pub mod progress;
+pub mod render;
+pub mod staging;
+pub mod transcriber;
+pub mod utils;
+
+pub use backend::{
+    BackendKind, PreparedMedia, StagedMedia, SubmittedJob, TranscriptionBackend,
+};
+pub use error::{AppError, ErrorClass};
+pub use events::Event;
+pub use models::{JobOptions, PollOptions, Segment, Transcript, TranscriptionStatus, Word};
+pub use transcriber::{Transcriber, TranscriberBuilder};
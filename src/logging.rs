@@ -0,0 +1,160 @@
+//! # Logging
+//!
+//! This module sets up the `tracing` subscriber that prints every human
+//! message of prepis to stderr.
+//!
+//! It provides:
+//! - Verbosity levels: `-q` for warnings and errors only, `-v` for debug
+//!   logs (including AWS request IDs and timings), `-vv` for trace logs and
+//!   the AWS SDK's own debug output
+//! - A plain style (`--no-emoji`/`--plain`) without emojis or other
+//!   non-ASCII decoration, for terminals that cannot render them
+//! - Colours that follow the terminal and the `NO_COLOR` convention
+//!
+//! Messages are logged with the regular `tracing` macros; warnings get their
+//! `Warning:` prefix from the formatter.
+
+use console::style;
+use std::fmt;
+use std::sync::OnceLock;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// How human output should look for the rest of the run
+#[derive(Debug, Clone, Copy)]
+pub struct OutputStyle {
+    pub verbosity: i8, // -1 with `-q`, 0 by default, 1 with `-v`, 2 with `-vv`
+    pub plain: bool,
+}
+
+static STYLE: OnceLock<OutputStyle> = OnceLock::new();
+
+/// Install the logger for the given verbosity and style
+pub fn init(verbosity: i8, plain: bool) {
+    let color = std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && console::Term::stderr().features().colors_supported();
+    if !color {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+
+    let output_style = OutputStyle { verbosity, plain };
+    let _ = STYLE.set(output_style);
+
+    let (own_level, sdk_level) = match verbosity {
+        i8::MIN..=-1 => (LevelFilter::WARN, LevelFilter::OFF),
+        0 => (LevelFilter::INFO, LevelFilter::OFF),
+        1 => (LevelFilter::DEBUG, LevelFilter::OFF),
+        _ => (LevelFilter::TRACE, LevelFilter::DEBUG),
+    };
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), own_level)
+        .with_target("aws", sdk_level);
+
+    let _ = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(color)
+                .event_format(HumanFormat(output_style)),
+        )
+        .with(filter)
+        .try_init();
+}
+
+/// Style of the current run, the default one before [`init`] is called
+pub fn output_style() -> OutputStyle {
+    STYLE.get().copied().unwrap_or(OutputStyle {
+        verbosity: 0,
+        plain: false,
+    })
+}
+
+/// Whether progress bars and spinners should be drawn
+///
+/// Only the CLI installs the logger, so library users never get them.
+pub fn terminal_ui() -> bool {
+    STYLE.get().is_some_and(|style| style.verbosity >= 0)
+}
+
+/// Event formatter that prints messages the way prepis always has
+struct HumanFormat(OutputStyle);
+
+impl<S, N> FormatEvent<S, N> for HumanFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut line = String::new();
+        match *metadata.level() {
+            Level::WARN => line.push_str("⚠️  Warning: "),
+            Level::DEBUG | Level::TRACE => {
+                line.push_str(&format!("{} {}: ", metadata.level(), metadata.target()))
+            }
+            _ => {}
+        }
+        ctx.format_fields(Writer::new(&mut line), event)?;
+
+        if self.0.plain {
+            line = to_plain(&line);
+        }
+
+        match *metadata.level() {
+            Level::ERROR => writeln!(writer, "{}", style(line).for_stderr().red()),
+            Level::WARN => writeln!(writer, "{}", style(line).for_stderr().yellow()),
+            Level::DEBUG | Level::TRACE => writeln!(writer, "{}", style(line).for_stderr().dim()),
+            _ => writeln!(writer, "{}", line),
+        }
+    }
+}
+
+/// Text printed outside of the logger, without emojis with `--plain`
+pub fn plain_text(text: String) -> String {
+    if output_style().plain {
+        to_plain(&text)
+    } else {
+        text
+    }
+}
+
+/// Strip emojis and replace box-drawing and other decorative characters with ASCII
+pub fn to_plain(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let stripped: String = line
+            .chars()
+            .filter(|c| !is_emoji(*c))
+            .map(|c| match c {
+                '─' | '·' => '-',
+                c => c,
+            })
+            .collect();
+        plain.push_str(stripped.trim_start_matches(' '));
+    }
+    plain
+}
+
+/// Whether a character is an emoji or part of an emoji sequence
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF    // Pictographs, emoticons, transport and symbols
+            | 0x2300..=0x23FF // Technical symbols such as ⏳ and ⏰
+            | 0x2600..=0x27BF // Miscellaneous symbols and dingbats such as ✅ and ❌
+            | 0x2B00..=0x2BFF // Arrows and shapes
+            | 0xFE0F          // Emoji presentation selector
+            | 0x200D          // Zero width joiner
+    )
+}
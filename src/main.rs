@@ -2,119 +2,159 @@
 //!
 //! This is the main entry point for the Prepis application, which provides
 //! command-line functionality for transcribing video and audio files using
-//! Amazon Transcribe.
+//! Amazon Transcribe. The pipeline itself lives in the `prepis` library.
 //!
 //! The application handles:
 //! - Command-line argument parsing
-//! - Orchestration of the transcription workflow
-//! - User feedback and progress reporting
-//!
-//! ## Workflow
-//!
-//! 1. Parse command-line arguments
-//! 2. Validate input file
-//! 3. Upload file to S3
-//! 4. Start Amazon Transcribe job
-//! 5. Poll for job completion
-//! 6. Retrieve and display results
-//! 7. Clean up temporary S3 files
-
-mod aws;
-mod error;
-mod file;
-mod models;
-mod progress;
-mod utils;
-
-use clap::Parser;
-use error::AppError;
-use models::TranscriptionStatus;
-use std::path::PathBuf;
+//! - Merging configuration files with command-line options
+//! - Setting up logging for the chosen verbosity
+//! - Dispatching to the selected command (see [`commands`])
 
-#[derive(Parser)]
-#[command(name = "prepis")]
-#[command(about = "A CLI tool to transcribe video files using Amazon Transcribe")]
-#[command(version = "0.1.0")]
-struct CliArgs {
-    /// Path to the video file to transcribe
-    #[arg(help = "Path to the video file")]
-    video_file: PathBuf,
+mod cli;
+mod commands;
 
-    /// S3 bucket name to use for temporary file storage
-    #[arg(help = "S3 bucket name for temporary storage")]
-    s3_bucket: String,
-
-    /// Output filename for the transcription
-    #[arg(help = "S3 bucket name for temporary storage")]
-    output_file: Option<PathBuf>,
-}
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use cli::{AwsArgs, CliArgs, Command, ConfigCommand, TranscribeArgs};
+use prepis::aws::AwsOptions;
+use prepis::config::{LoadedConfig, Settings};
+use prepis::{error, logging};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = CliArgs::parse();
+    reject_positionals_with_subcommand(&args);
+    let verbosity = if args.output.quiet { -1 } else { args.output.verbose.min(2) as i8 };
+    logging::init(verbosity, args.output.no_emoji);
 
-    if let Err(e) = run_transcription(args).await {
+    if let Err(e) = run(args).await {
         error::display_error(&e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 
     Ok(())
 }
 
-async fn run_transcription(args: CliArgs) -> Result<(), error::AppError> {
-    println!("Video Transcription CLI");
-    println!("Video file: {:?}", args.video_file);
-    println!("S3 bucket: {}", args.s3_bucket);
-    if let Some(filename) = &args.output_file {
-        println!("Output file: {}", filename.to_string_lossy());
+/// Exit with a usage error when the file arguments are given next to a subcommand
+///
+/// Clap cannot refuse them itself without also refusing the global options
+/// before the subcommand, e.g. `prepis --region eu-west-1 jobs list`.
+fn reject_positionals_with_subcommand(args: &CliArgs) {
+    let positional = [
+        args.transcribe.video_file.as_ref().map(|path| path.display().to_string()),
+        args.transcribe.s3_bucket.clone(),
+        args.transcribe.output_file.as_ref().map(|path| path.display().to_string()),
+    ];
+    if args.command.is_some()
+        && let Some(value) = positional.into_iter().flatten().next()
+    {
+        CliArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!("the argument '{}' cannot be used with a subcommand", value),
+            )
+            .exit();
     }
+}
 
-    // Validate the video file
-    file::validate_video_file(&args.video_file)?;
-
-    // Initialize AWS clients
-    let aws_clients = aws::create_aws_clients().await?;
-
-    // Upload file to S3
-    let s3_uri =
-        aws::upload_file_to_s3(&aws_clients.s3_client, &args.s3_bucket, &args.video_file).await?;
-    println!("📍 S3 URI: {}", s3_uri);
-
-    // Start transcription job
-    let job_name = utils::generate_job_name(&args.video_file);
-    aws::start_transcription_job(&aws_clients.transcribe_client, &job_name, &s3_uri).await?;
-
-    // Poll for completion
-    let transcription_status =
-        aws::poll_transcription_status(&aws_clients.transcribe_client, &job_name).await?;
-
-    match transcription_status {
-        TranscriptionStatus::Completed(result_uri) => {
-            println!("🎉 Transcription completed! Result URI: {}", result_uri);
-
-            // Retrieve and display results
-            let transcript_text = aws::get_transcription_result(&result_uri).await?;
-            println!("\n📝 Transcription Results:");
-            println!("─────────────────────────");
-            println!("{}", transcript_text);
-            println!("─────────────────────────");
-
-            if let Some(filename) = &args.output_file {
-                println!("💾 Saving transcription to: {}", filename.to_string_lossy());
-                file::save_transcription(filename, &transcript_text)?;
-            }
+/// Merge the configuration files with the command line and dispatch the command
+async fn run(args: CliArgs) -> Result<(), error::AppError> {
+    // `prepis transcribe ...` and plain `prepis ...` run the same workflow
+    let transcribe_args = match &args.command {
+        Some(Command::Transcribe(transcribe_args)) => transcribe_args.as_ref(),
+        _ => &args.transcribe,
+    };
+
+    let loaded_config = LoadedConfig::load()?;
+    let effective = loaded_config.resolve(
+        args.preset.as_deref(),
+        &command_line_settings(transcribe_args, &args.aws),
+    )?;
+    let settings = &effective.settings;
+    let aws_options = aws_options(settings);
+
+    match args.command {
+        Some(Command::Jobs(jobs_command)) => {
+            commands::run_jobs(jobs_command, &aws_options).await
+        }
+        Some(Command::Collect(collect_args)) => {
+            commands::run_collect(collect_args, &aws_options).await
         }
-        TranscriptionStatus::Failed(reason) => {
-            return Err(AppError::Transcribe(format!(
-                "Transcription failed: {}",
-                reason
-            )));
+        Some(Command::Render(render_args)) => {
+            commands::run_render(render_args, &aws_options).await
         }
+        Some(Command::InitBucket(init_args)) => {
+            commands::run_init_bucket(init_args, &aws_options).await
+        }
+        Some(Command::Doctor(doctor_args)) => {
+            commands::run_doctor(doctor_args, settings, &aws_options).await
+        }
+        Some(Command::Config(ConfigCommand::Show)) => {
+            commands::show_config(&loaded_config, args.preset.as_deref(), &effective);
+            Ok(())
+        }
+        Some(Command::Transcribe(transcribe_args)) => {
+            transcribe(*transcribe_args, settings, &loaded_config, &aws_options).await
+        }
+        None => transcribe(args.transcribe, settings, &loaded_config, &aws_options).await,
     }
+}
 
-    // Clean up resources
-    let s3_key = utils::generate_s3_key(&args.video_file);
-    aws::delete_file_from_s3(&aws_clients.s3_client, &args.s3_bucket, &s3_key).await?;
+/// Transcribe a file, or only show the plan with `--dry-run`
+async fn transcribe(
+    transcribe_args: TranscribeArgs,
+    settings: &Settings,
+    loaded_config: &LoadedConfig,
+    aws_options: &AwsOptions,
+) -> Result<(), error::AppError> {
+    if transcribe_args.dry_run {
+        commands::run_dry_run(transcribe_args, settings, loaded_config, aws_options).await
+    } else {
+        commands::run_transcription(transcribe_args, settings, aws_options).await
+    }
+}
 
-    Ok(())
+/// Collect the settings given explicitly on the command line
+fn command_line_settings(transcribe: &TranscribeArgs, aws: &AwsArgs) -> Settings {
+    Settings {
+        backend: transcribe.backend,
+        bucket: transcribe.s3_bucket.clone(),
+        language: transcribe.language.clone(),
+        speakers: transcribe.speakers,
+        output_bucket: transcribe.output_bucket.clone(),
+        output_key: transcribe.output_key.clone(),
+        keep_output: flag(transcribe.keep_output, transcribe.no_keep_output),
+        region: aws.region.clone(),
+        profile: aws.profile.clone(),
+        endpoint_url: aws.endpoint_url.clone(),
+        s3_endpoint_url: aws.s3_endpoint_url.clone(),
+        transcribe_endpoint_url: aws.transcribe_endpoint_url.clone(),
+        max_attempts: aws.max_attempts,
+        redact_pii: flag(transcribe.redact_pii, transcribe.no_redact_pii),
+        whisper_url: transcribe.whisper_url.clone(),
+        whisper_model: transcribe.whisper_model.clone(),
+        whisper_cpp_path: transcribe.whisper_cpp_path.clone(),
+        whisper_cpp_model: transcribe.whisper_cpp_model.clone(),
+    }
+}
+
+/// A `--flag`/`--no-flag` pair, `None` when neither was given
+fn flag(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Collect the AWS connection overrides from the effective settings
+fn aws_options(settings: &Settings) -> AwsOptions {
+    AwsOptions {
+        region: settings.region.clone(),
+        profile: settings.profile.clone(),
+        endpoint_url: settings.endpoint_url.clone(),
+        s3_endpoint_url: settings.s3_endpoint_url.clone(),
+        transcribe_endpoint_url: settings.transcribe_endpoint_url.clone(),
+        max_attempts: settings.max_attempts,
+    }
 }
@@ -6,9 +6,217 @@
 //! These models represent the domain objects of the transcription process
 //! and help maintain a clear separation between data and behavior.
 
+use crate::pricing::Rates;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
 /// Transcription job status enum
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TranscriptionStatus {
     Completed(String), // Contains result URI
     Failed(String),    // Contains failure reason
 }
+
+/// Location where Amazon Transcribe should write the job result
+#[derive(Debug, Clone)]
+pub struct OutputLocation {
+    pub bucket: String,
+    pub key: Option<String>, // Defaults to `<job-name>.json` when not set
+}
+
+/// Options for starting a transcription job
+#[derive(Debug, Clone)]
+pub struct JobOptions {
+    pub language: String,              // Language code, e.g. `en-US`
+    pub max_speakers: Option<i32>,     // Enables speaker labels when set
+    pub output: Option<OutputLocation>, // Uses the service bucket when not set
+    pub redact_pii: bool,              // Replaces personal data with [PII] tags
+    pub sample_rate: Option<i32>,      // Sample rate of the media in Hz, when known for sure
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            language: crate::config::DEFAULT_LANGUAGE.to_string(),
+            max_speakers: None,
+            output: None,
+            redact_pii: false,
+            sample_rate: None,
+        }
+    }
+}
+
+/// How to wait for a transcription job to finish
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    pub interval: Duration,         // Delay before the second status check
+    pub max_interval: Duration,     // Upper bound for the backoff
+    pub timeout: Duration,          // Give up waiting after this long
+    pub estimate: Option<Duration>, // Expected processing time, when known
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(60 * 60),
+            estimate: None,
+        }
+    }
+}
+
+impl PollOptions {
+    /// Default options for media of the given length
+    ///
+    /// The deadline grows for long media, so that waiting never gives up on a
+    /// job that is simply still busy.
+    pub fn for_media(media_duration: Option<Duration>) -> Self {
+        let defaults = Self::default();
+        let estimate = media_duration.map(crate::aws::transcribe::estimate_processing_time);
+
+        Self {
+            timeout: estimate.map_or(defaults.timeout, |estimate| defaults.timeout.max(estimate * 3)),
+            estimate,
+            ..defaults
+        }
+    }
+}
+
+/// Name of the JSON format written by [`Transcript::to_json`]
+pub const TRANSCRIPT_FORMAT: &str = "prepis-transcript";
+
+/// Version of the transcript JSON format, raised on incompatible changes
+pub const TRANSCRIPT_FORMAT_VERSION: u32 = 1;
+
+/// Transcript of a finished transcription job, independent of the backend
+///
+/// Segments are in the order they were spoken. Results without word timings
+/// have no segments, but always have the full text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub job_name: String,
+    pub language: String,
+    pub result_uri: String,
+    pub text: String,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    #[serde(skip)]
+    pub result_json: Option<String>, // Result JSON as the backend returned it, when parsed from one
+}
+
+/// A stretch of speech by one speaker, usually a sentence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>, // Speaker label, e.g. `spk_0`, with --speakers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u32>, // Audio channel, with channel identification
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+/// A single spoken word with the punctuation that follows it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>, // Between 0 and 1, when the backend reports it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub punctuation: String, // Attached to the word, e.g. `,` or `?`
+}
+
+impl Word {
+    /// The word as written in the transcript, with its punctuation
+    pub fn written(&self) -> String {
+        format!("{}{}", self.text, self.punctuation)
+    }
+}
+
+/// A transcript document with its format and version
+#[derive(Serialize, Deserialize)]
+struct TranscriptDocument<T> {
+    format: String,
+    version: u32,
+    #[serde(flatten)]
+    transcript: T,
+}
+
+impl Transcript {
+    /// Write the transcript in the stable prepis transcript JSON format
+    pub fn to_json(&self) -> Result<String, AppError> {
+        let document = TranscriptDocument {
+            format: TRANSCRIPT_FORMAT.to_string(),
+            version: TRANSCRIPT_FORMAT_VERSION,
+            transcript: self,
+        };
+        serde_json::to_string_pretty(&document)
+            .map_err(|e| AppError::File(format!("Failed to serialize transcript: {}", e)))
+    }
+
+    /// Read a transcript written by [`Transcript::to_json`]
+    pub fn from_json(json_text: &str) -> Result<Self, AppError> {
+        let document: TranscriptDocument<Transcript> = serde_json::from_str(json_text)
+            .map_err(|e| AppError::File(format!("Invalid transcript JSON: {}", e)))?;
+
+        if document.format != TRANSCRIPT_FORMAT {
+            return Err(AppError::File(format!(
+                "Not a prepis transcript, the format is {}",
+                document.format
+            )));
+        }
+        if document.version > TRANSCRIPT_FORMAT_VERSION {
+            return Err(AppError::File(format!(
+                "Transcript format version {} is newer than the supported version {}, please update prepis",
+                document.version, TRANSCRIPT_FORMAT_VERSION
+            )));
+        }
+        Ok(document.transcript)
+    }
+}
+
+/// Final result of a transcription run, printed with `--output-format json`
+#[derive(Debug, Serialize)]
+pub struct TranscriptionReport {
+    pub job_name: String,
+    pub status: &'static str, // `completed`, or `submitted` with `--detach`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_uri: Option<String>, // Only for media staged in S3
+    pub language: String,
+    pub media_duration_seconds: Option<f64>,
+    pub result_uri: Option<String>,
+    pub output_files: Vec<PathBuf>,
+    pub transcript: Option<String>,
+}
+
+/// What a `--dry-run` would do, printed with `--output-format json`
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub bucket: String,
+    pub region: Option<String>,
+    pub rates: Rates,
+    pub redact_pii: bool,
+    pub files: Vec<DryRunFile>,
+    pub total_media_seconds: f64,
+    pub total_cost_usd: f64,
+    pub files_without_duration: usize, // Not included in the totals
+}
+
+/// A single file of a dry run
+#[derive(Debug, Serialize)]
+pub struct DryRunFile {
+    pub file: PathBuf,
+    pub size_bytes: u64,
+    pub media_duration_seconds: Option<f64>,
+    pub s3_key: String,
+    pub job_name: String,
+    pub estimated_cost_usd: Option<f64>,
+    pub estimated_processing_seconds: Option<f64>,
+}
@@ -0,0 +1,127 @@
+//! # Pending Jobs
+//!
+//! This module keeps track of transcription jobs submitted with `--detach`
+//! so that `prepis collect` can pick up their results later.
+//!
+//! Jobs are stored as JSON in `$XDG_STATE_HOME/prepis/pending-jobs.json`
+//! (falling back to `~/.local/state/prepis/pending-jobs.json`). The file is
+//! replaced atomically on every save so an interrupted run never leaves it
+//! half written, and changed under a lock so that a `collect` running next
+//! to a `--detach` never loses the other's jobs.
+
+use crate::error::AppError;
+use crate::render::OutputFile;
+use crate::utils::xdg_base_dir;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A detached job waiting to be collected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub job_name: String,
+    pub bucket: String, // Bucket holding the uploaded media
+    pub s3_key: String,
+    pub media_file: PathBuf,
+    pub outputs: Vec<OutputFile>,
+    #[serde(default)]
+    pub overwrite: bool, // Replace existing output files, with --force
+    pub output_bucket: Option<String>, // Set when the result JSON goes to our own bucket
+    pub keep_output: bool,
+    pub region: Option<String>,
+    pub submitted_at: u64, // Seconds since the Unix epoch
+}
+
+impl PendingJob {
+    /// Seconds since the Unix epoch, used as the submission time of new jobs
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// On-disk collection of pending jobs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingStore {
+    pub jobs: Vec<PendingJob>,
+}
+
+impl PendingStore {
+    /// Load the store, treating a missing file as empty
+    pub fn load() -> Result<Self, AppError> {
+        let path = store_path()?;
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            AppError::Config(format!("Invalid pending jobs file {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the store back to disk
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = store_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::Config(format!("Failed to serialize pending jobs: {}", e)))?;
+        write_atomically(&path, &content)
+    }
+
+    /// Record a newly submitted job
+    pub fn add(job: PendingJob) -> Result<(), AppError> {
+        Self::update(|store| {
+            store.jobs.retain(|existing| existing.job_name != job.job_name);
+            store.jobs.push(job);
+        })
+    }
+
+    /// Forget jobs that were collected or failed, keeping any added in the meantime
+    pub fn remove(job_names: &[String]) -> Result<(), AppError> {
+        Self::update(|store| store.jobs.retain(|job| !job_names.contains(&job.job_name)))
+    }
+
+    /// Load, change and save the store while holding its lock
+    fn update(change: impl FnOnce(&mut Self)) -> Result<(), AppError> {
+        let path = store_path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // Released when the file is closed
+        let lock = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("json.lock"))?;
+        lock.lock()?;
+
+        let mut store = Self::load()?;
+        change(&mut store);
+        store.save()
+    }
+}
+
+/// Path of the pending jobs file following the XDG base directory spec
+pub fn store_path() -> Result<PathBuf, AppError> {
+    xdg_base_dir("XDG_STATE_HOME", ".local/state")
+        .map(|dir| dir.join("prepis").join("pending-jobs.json"))
+        .ok_or_else(|| {
+            AppError::Config("Cannot locate the state directory, set XDG_STATE_HOME or HOME".to_string())
+        })
+}
+
+/// Write to a temporary file next to the target and rename it into place
+fn write_atomically(path: &Path, content: &str) -> Result<(), AppError> {
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! # Pricing
+//!
+//! This module estimates what Amazon Transcribe charges for a job, for
+//! `--dry-run`.
+//!
+//! Batch transcription is billed per second of media with a minimum of 15
+//! seconds per job. Features such as PII redaction are billed on top of the
+//! standard rate. The built-in rates are the US East list prices and can be
+//! overridden per region in the `[rates]` table of a configuration file.
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// Standard batch transcription rate in USD per minute
+pub const DEFAULT_PER_MINUTE: f64 = 0.024;
+
+/// PII redaction surcharge in USD per minute
+pub const DEFAULT_PII_REDACTION_PER_MINUTE: f64 = 0.0024;
+
+/// Shortest duration a job is billed for
+const MINIMUM_BILLED_DURATION: Duration = Duration::from_secs(15);
+
+/// Transcribe prices in USD per minute of media
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rates {
+    pub per_minute: f64,
+    pub pii_redaction_per_minute: f64,
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Self {
+            per_minute: DEFAULT_PER_MINUTE,
+            pii_redaction_per_minute: DEFAULT_PII_REDACTION_PER_MINUTE,
+        }
+    }
+}
+
+impl Rates {
+    /// Override the rates that are given
+    pub fn apply(&mut self, per_minute: Option<f64>, pii_redaction_per_minute: Option<f64>) {
+        if let Some(per_minute) = per_minute {
+            self.per_minute = per_minute;
+        }
+        if let Some(pii_redaction_per_minute) = pii_redaction_per_minute {
+            self.pii_redaction_per_minute = pii_redaction_per_minute;
+        }
+    }
+
+    /// Price of one minute of media with the given features
+    pub fn per_minute_with(&self, redact_pii: bool) -> f64 {
+        if redact_pii {
+            self.per_minute + self.pii_redaction_per_minute
+        } else {
+            self.per_minute
+        }
+    }
+
+    /// Estimated cost in USD of transcribing media of the given length
+    pub fn estimate_cost(&self, media_duration: Duration, redact_pii: bool) -> f64 {
+        // Billing is per started second
+        let billed = media_duration.max(MINIMUM_BILLED_DURATION).as_secs_f64().ceil();
+        billed / 60.0 * self.per_minute_with(redact_pii)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn media_is_billed_per_started_second() {
+        let rates = Rates::default();
+
+        assert_close(rates.estimate_cost(Duration::from_secs(600), false), 0.24);
+        assert_close(rates.estimate_cost(Duration::from_millis(60_001), false), 61.0 / 60.0 * 0.024);
+    }
+
+    #[test]
+    fn short_media_is_billed_for_the_minimum() {
+        let rates = Rates::default();
+
+        assert_close(rates.estimate_cost(Duration::ZERO, false), 0.006);
+        assert_close(rates.estimate_cost(Duration::from_secs(3), false), 0.006);
+    }
+
+    #[test]
+    fn pii_redaction_is_billed_on_top() {
+        let rates = Rates::default();
+
+        assert_close(rates.per_minute_with(true), 0.0264);
+        assert_close(rates.estimate_cost(Duration::from_secs(600), true), 0.264);
+    }
+
+    #[test]
+    fn only_given_rates_are_overridden() {
+        let mut rates = Rates::default();
+        rates.apply(Some(0.0312), None);
+
+        assert_eq!(
+            rates,
+            Rates {
+                per_minute: 0.0312,
+                pii_redaction_per_minute: DEFAULT_PII_REDACTION_PER_MINUTE,
+            }
+        );
+    }
+}
@@ -6,12 +6,19 @@
 //!
 //! The module supports both simple uploads for smaller files and multipart
 //! uploads for larger files, with appropriate progress tracking for each.
+//!
+//! It also provides a live status line for transcription jobs that are being
+//! polled, which can share the terminal with other jobs in batch runs.
 
 
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::events::{self, Event};
+use crate::logging;
+use crate::utils::format_duration;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 /// Configuration for progress bar appearance and behavior
 pub struct ProgressConfig {
@@ -62,13 +69,13 @@ impl UploadProgressBar {
     pub fn new(file_size: u64, file_name: &str) -> Self {
         let config = ProgressConfig::default();
         
-        let progress_bar = ProgressBar::new(file_size);
-        progress_bar.set_style(
+        let progress_bar = ProgressBar::with_draw_target(Some(file_size), draw_target());
+        progress_bar.set_style(plain_style(
             ProgressStyle::default_bar()
                 .template(config.template)
                 .expect("Invalid progress bar template")
                 .progress_chars(config.progress_chars),
-        );
+        ));
         
         progress_bar.enable_steady_tick(config.steady_tick);
         
@@ -84,12 +91,12 @@ impl UploadProgressBar {
 
     /// Create an indeterminate progress bar when file size is unknown
     pub fn new_indeterminate(file_name: &str) -> Self {
-        let progress_bar = ProgressBar::new_spinner();
-        progress_bar.set_style(
+        let progress_bar = ProgressBar::with_draw_target(None, draw_target());
+        progress_bar.set_style(plain_style(
             ProgressStyle::default_spinner()
                 .template("{spinner:.green} [{elapsed_precise}] Uploading {msg}")
                 .expect("Invalid spinner template"),
-        );
+        ));
         
         progress_bar.enable_steady_tick(Duration::from_millis(100));
         progress_bar.set_message(file_name.to_string());
@@ -119,15 +126,15 @@ impl UploadProgressBar {
             self.file_name,
             elapsed.as_secs_f64()
         );
-        self.progress_bar.finish_with_message(message);
+        self.progress_bar.finish_with_message(logging::plain_text(message));
     }
 
     /// Abandon the progress bar (for error cases)
     pub fn abandon(&self) {
-        self.progress_bar.abandon_with_message(format!(
+        self.progress_bar.abandon_with_message(logging::plain_text(format!(
             "❌ Upload of {} was interrupted",
             self.file_name
-        ));
+        )));
     }
 
     /// Check if the terminal supports progress bars
@@ -142,6 +149,7 @@ pub struct ProgressTracker {
     progress_bar: UploadProgressBar,
     bytes_uploaded: Arc<AtomicU64>,
     total_bytes: u64,
+    last_reported_percent: AtomicU64, // Throttles `upload_progress` events
 }
 
 impl ProgressTracker {
@@ -157,6 +165,7 @@ impl ProgressTracker {
             progress_bar,
             bytes_uploaded: Arc::new(AtomicU64::new(0)),
             total_bytes: file_size,
+            last_reported_percent: AtomicU64::new(0),
         }
     }
 
@@ -168,6 +177,7 @@ impl ProgressTracker {
             progress_bar,
             bytes_uploaded: Arc::new(AtomicU64::new(0)),
             total_bytes: 0,
+            last_reported_percent: AtomicU64::new(0),
         }
     }
 
@@ -175,8 +185,16 @@ impl ProgressTracker {
     pub fn update_progress(&self, additional_bytes: u64) {
         let new_total = self.bytes_uploaded.fetch_add(additional_bytes, Ordering::Relaxed) + additional_bytes;
         
-        if self.total_bytes > 0 {
+        if let Some(percent) = (new_total * 100).checked_div(self.total_bytes) {
             self.progress_bar.update(new_total);
+
+            // Emit at most one event per percent
+            if self.last_reported_percent.fetch_max(percent, Ordering::Relaxed) < percent {
+                events::emit(Event::UploadProgress {
+                    bytes: new_total,
+                    total_bytes: self.total_bytes,
+                });
+            }
         } else {
             // For indeterminate progress, just increment
             self.progress_bar.increment(additional_bytes);
@@ -195,3 +213,116 @@ impl ProgressTracker {
 
 }
 
+/// Live status display for a transcription job that is being polled
+///
+/// Shows a spinner with the job name, its status, the elapsed time and an
+/// ETA. Without a terminal it falls back to one line per status check.
+pub struct PollProgress {
+    progress_bar: Option<ProgressBar>,
+    job_name: String,
+    estimate: Option<Duration>,
+    start_time: Instant,
+}
+
+impl PollProgress {
+    /// Create a display for a single job
+    pub fn new(job_name: &str, estimate: Option<Duration>) -> Self {
+        Self::create(None, job_name, estimate)
+    }
+
+    /// Create a display that gets its own line in a multi-job view
+    pub fn with_multi(multi: &MultiProgress, job_name: &str, estimate: Option<Duration>) -> Self {
+        Self::create(Some(multi), job_name, estimate)
+    }
+
+    fn create(multi: Option<&MultiProgress>, job_name: &str, estimate: Option<Duration>) -> Self {
+        let progress_bar = (UploadProgressBar::is_terminal_supported() && logging::terminal_ui()).then(|| {
+            let progress_bar = ProgressBar::new_spinner();
+            let progress_bar = match multi {
+                Some(multi) => multi.add(progress_bar),
+                None => progress_bar,
+            };
+            progress_bar.set_style(plain_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} {prefix} [{elapsed}] {msg}")
+                    .expect("Invalid spinner template"),
+            ));
+            progress_bar.set_prefix(job_name.to_string());
+            progress_bar.set_message("submitted");
+            progress_bar.enable_steady_tick(Duration::from_millis(100));
+            progress_bar
+        });
+
+        Self {
+            progress_bar,
+            job_name: job_name.to_string(),
+            estimate,
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Show the latest job status, e.g. `QUEUED` or `IN_PROGRESS`
+    pub fn set_status(&self, status: &str) {
+        let message = match self.eta() {
+            Some(eta) => format!("{} · {}", status, eta),
+            None => status.to_string(),
+        };
+
+        match &self.progress_bar {
+            Some(progress_bar) => progress_bar.set_message(logging::plain_text(message)),
+            None => info!(
+                "⏳ {}: {} ({} elapsed)",
+                self.job_name,
+                message,
+                format_duration(self.start_time.elapsed())
+            ),
+        }
+    }
+
+    /// Print a warning without garbling the spinner
+    pub fn warn(&self, message: &str) {
+        match &self.progress_bar {
+            Some(progress_bar) => progress_bar.suspend(|| warn!("{}", message)),
+            None => warn!("{}", message),
+        }
+    }
+
+    /// Replace the spinner with a final message, e.g. `✅ Completed`
+    pub fn finish(&self, message: &str) {
+        let elapsed = format_duration(self.start_time.elapsed());
+        match &self.progress_bar {
+            // The job name is already shown as the prefix
+            Some(progress_bar) => {
+                progress_bar.finish_with_message(logging::plain_text(format!("{} after {}", message, elapsed)))
+            }
+            None => info!("{} {} after {}", message, self.job_name, elapsed),
+        }
+    }
+
+    /// Remaining time according to the estimate
+    fn eta(&self) -> Option<String> {
+        let estimate = self.estimate?;
+        Some(match estimate.checked_sub(self.start_time.elapsed()) {
+            Some(remaining) if !remaining.is_zero() => format!("ETA ~{}", format_duration(remaining)),
+            _ => "taking longer than expected".to_string(),
+        })
+    }
+}
+
+/// Where progress bars are drawn, nowhere with `--quiet` or outside the CLI
+fn draw_target() -> ProgressDrawTarget {
+    if !logging::terminal_ui() {
+        ProgressDrawTarget::hidden()
+    } else {
+        ProgressDrawTarget::stderr()
+    }
+}
+
+/// Swap the Unicode spinner and bar characters for ASCII with `--plain`
+fn plain_style(style: ProgressStyle) -> ProgressStyle {
+    if logging::output_style().plain {
+        style.tick_chars("|/-\\ ").progress_chars("#>-")
+    } else {
+        style
+    }
+}
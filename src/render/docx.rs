@@ -0,0 +1,108 @@
+//! Word documents (DOCX)
+//!
+//! A DOCX file is a ZIP archive of XML parts. The transcript only needs the
+//! three required ones, with the formatting set directly on the text.
+
+use super::{format_clock, paragraphs};
+use crate::error::AppError;
+use crate::models::Transcript;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+const RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+/// The transcript as a Word document, laid out like the Markdown format
+pub(super) fn render_docx(transcript: &Transcript) -> Result<Vec<u8>, AppError> {
+    let title = match transcript.job_name.as_str() {
+        "" => "Transcript",
+        job_name => job_name,
+    };
+    let mut body = paragraph(&[run(title, "<w:b/><w:sz w:val=\"32\"/>")]);
+
+    let timed = !transcript.segments.is_empty();
+    for paragraph_text in paragraphs(transcript) {
+        let label = match (timed, paragraph_text.speaker) {
+            (true, Some(speaker)) => {
+                Some(format!("[{}] {}: ", format_clock(paragraph_text.start_seconds), speaker))
+            }
+            (true, None) => Some(format!("[{}] ", format_clock(paragraph_text.start_seconds))),
+            (false, _) => None,
+        };
+        let mut runs = Vec::new();
+        if let Some(label) = label {
+            runs.push(run(&label, "<w:b/>"));
+        }
+        runs.push(run(&paragraph_text.text, ""));
+        body.push_str(&paragraph(&runs));
+    }
+
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+
+    zip_parts(&[
+        ("[Content_Types].xml", CONTENT_TYPES),
+        ("_rels/.rels", RELATIONSHIPS),
+        ("word/document.xml", &document),
+    ])
+    .map_err(|e| AppError::File(format!("Failed to create Word document: {}", e)))
+}
+
+/// A paragraph made of runs
+fn paragraph(runs: &[String]) -> String {
+    format!("<w:p>{}</w:p>", runs.concat())
+}
+
+/// A run of text with the given run properties
+fn run(text: &str, properties: &str) -> String {
+    let properties = match properties {
+        "" => String::new(),
+        properties => format!("<w:rPr>{}</w:rPr>", properties),
+    };
+    format!("<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>", properties, escape(text))
+}
+
+/// Text safe to use in XML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A ZIP archive of the named parts
+fn zip_parts(parts: &[(&str, &str)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in parts {
+        zip.start_file(*name, SimpleFileOptions::default())?;
+        zip.write_all(content.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::transcript;
+    use std::io::Read;
+
+    #[test]
+    fn docx_contains_the_document_parts() {
+        let docx = render_docx(&transcript()).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+        assert!(archive.by_name("_rels/.rels").is_ok());
+        let mut document = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut document).unwrap();
+        assert!(document.contains("[00:00:00] spk_0: "));
+        assert!(document.contains("Hello, world. How are you?"));
+    }
+}
@@ -0,0 +1,350 @@
+//! # Rendering
+//!
+//! This module turns a [`Transcript`] into the files prepis writes. Every
+//! format renders from the same transcript, so a result looks the same no
+//! matter which backend produced it or whether it was fetched from a job or
+//! loaded from an old result file.
+//!
+//! It provides:
+//! - Plain text and Markdown with timestamps and speakers
+//! - SubRip (SRT) and WebVTT subtitles
+//! - Word documents (DOCX)
+//! - The result JSON as the backend returned it
+//! - Reading result JSON in the Amazon Transcribe or prepis transcript format
+//! - Output file names from a template, and writing several formats at once
+
+mod docx;
+mod subtitles;
+mod text;
+
+use crate::aws::result::parse_transcription_result;
+use crate::error::AppError;
+use crate::file;
+use crate::models::{TRANSCRIPT_FORMAT, Transcript};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Default template for the names of output files
+pub const DEFAULT_OUTPUT_TEMPLATE: &str = "{stem}.{ext}";
+
+/// The formats a transcript can be rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TranscriptFormat {
+    /// Plain text, with a line per speaker turn when speakers are known
+    #[default]
+    Txt,
+    /// Result JSON as the backend returned it
+    Json,
+    /// SubRip subtitles
+    Srt,
+    /// WebVTT subtitles
+    Vtt,
+    /// Markdown with a timestamp for every paragraph
+    Md,
+    /// Word document with a timestamp for every paragraph
+    Docx,
+}
+
+impl TranscriptFormat {
+    /// File extension of the format, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Txt => "txt",
+            Self::Json => "json",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Md => "md",
+            Self::Docx => "docx",
+        }
+    }
+
+    /// Format of a file, guessed from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|format| format.extension() == extension)
+    }
+
+    /// Whether the format is text that can be printed to a terminal
+    pub fn is_text(self) -> bool {
+        self != Self::Docx
+    }
+}
+
+impl std::fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Render a transcript in the given format
+pub fn render(transcript: &Transcript, format: TranscriptFormat) -> Result<Vec<u8>, AppError> {
+    let rendered = match format {
+        TranscriptFormat::Txt => text::render_txt(transcript).into_bytes(),
+        TranscriptFormat::Json => match &transcript.result_json {
+            Some(result_json) => result_json.clone().into_bytes(),
+            None => transcript.to_json()?.into_bytes(), // E.g. loaded from a prepis transcript
+        },
+        TranscriptFormat::Md => text::render_md(transcript).into_bytes(),
+        TranscriptFormat::Srt => subtitles::render_srt(transcript)?.into_bytes(),
+        TranscriptFormat::Vtt => subtitles::render_vtt(transcript)?.into_bytes(),
+        TranscriptFormat::Docx => docx::render_docx(transcript)?,
+    };
+    Ok(rendered)
+}
+
+/// Read a result JSON document, from Amazon Transcribe or written by prepis
+///
+/// Transcribe results from the console and other tools work as well as the
+/// ones prepis fetched itself.
+pub fn read_transcript(json_text: &str) -> Result<Transcript, AppError> {
+    #[derive(Deserialize)]
+    struct Format {
+        format: Option<String>,
+    }
+
+    match serde_json::from_str::<Format>(json_text) {
+        Ok(Format { format: Some(format) }) if format == TRANSCRIPT_FORMAT => {
+            Transcript::from_json(json_text)
+        }
+        _ => parse_transcription_result(json_text),
+    }
+}
+
+/// A file to write a transcript to, in a format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub format: TranscriptFormat,
+}
+
+impl OutputFile {
+    /// An output in the format of its extension, or plain text for unknown ones
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let format = TranscriptFormat::from_path(&path).unwrap_or_default();
+        Self { path, format }
+    }
+
+    /// An output named after the media file following a template
+    ///
+    /// The template may use `{stem}` for the media file name without its
+    /// extension, `{lang}` for the language code and `{ext}` for the
+    /// extension of the format, e.g. `{stem}.{lang}.{ext}`.
+    pub fn from_template(
+        dir: &Path,
+        template: &str,
+        media_file: &Path,
+        language: &str,
+        format: TranscriptFormat,
+    ) -> Self {
+        let stem = media_file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let name = template
+            .replace("{stem}", &stem)
+            .replace("{lang}", language)
+            .replace("{ext}", format.extension());
+        Self {
+            path: dir.join(name),
+            format,
+        }
+    }
+}
+
+/// Check that a set of outputs can be written before any work is done
+///
+/// Two outputs must not share a path, their directories must be writable,
+/// and existing files are only replaced with `overwrite`.
+pub fn check_outputs(outputs: &[OutputFile], overwrite: bool) -> Result<(), AppError> {
+    for (index, output) in outputs.iter().enumerate() {
+        if outputs[..index].iter().any(|earlier| earlier.path == output.path) {
+            return Err(AppError::Config(format!(
+                "{} would be written more than once, please check the output template",
+                output.path.display()
+            )));
+        }
+        file::check_writable(&output.path, overwrite)?;
+        file::check_directory_writable(&output.path)?;
+    }
+    Ok(())
+}
+
+/// Render a transcript in every format and write the files
+///
+/// Everything is rendered before the first file is written, so a format that
+/// cannot be rendered leaves no partial set of files behind.
+pub fn write_outputs(
+    transcript: &Transcript,
+    outputs: &[OutputFile],
+    overwrite: bool,
+) -> Result<(), AppError> {
+    let rendered = outputs
+        .iter()
+        .map(|output| render(transcript, output.format))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (output, content) in outputs.iter().zip(rendered) {
+        info!("💾 Saving {} to: {}", output.format, output.path.display());
+        file::save_transcription(&output.path, content, overwrite)?;
+    }
+    Ok(())
+}
+
+/// Consecutive segments of one speaker, or single segments without speakers
+struct Paragraph {
+    start_seconds: f64,
+    speaker: Option<String>,
+    text: String,
+}
+
+/// The paragraphs of a transcript, or its full text without timings
+fn paragraphs(transcript: &Transcript) -> Vec<Paragraph> {
+    if transcript.segments.is_empty() {
+        return vec![Paragraph {
+            start_seconds: 0.0,
+            speaker: None,
+            text: transcript.text.trim().to_string(),
+        }];
+    }
+
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    for segment in &transcript.segments {
+        match paragraphs.last_mut() {
+            Some(last) if segment.speaker.is_some() && last.speaker == segment.speaker => {
+                last.text.push(' ');
+                last.text.push_str(&segment.text);
+            }
+            _ => paragraphs.push(Paragraph {
+                start_seconds: segment.start_seconds,
+                speaker: segment.speaker.clone(),
+                text: segment.text.clone(),
+            }),
+        }
+    }
+    paragraphs
+}
+
+/// A timestamp with hours, minutes and seconds, e.g. `01:02:03`
+fn format_clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Segment, Word};
+
+    /// A transcript of two speakers, with timings
+    pub(super) fn transcript() -> Transcript {
+        let word = |text: &str, start: f64, end: f64, punctuation: &str| Word {
+            text: text.to_string(),
+            start_seconds: start,
+            end_seconds: end,
+            confidence: Some(0.9),
+            punctuation: punctuation.to_string(),
+        };
+        let segment = |speaker: &str, words: Vec<Word>| Segment {
+            start_seconds: words[0].start_seconds,
+            end_seconds: words[words.len() - 1].end_seconds,
+            text: words.iter().map(Word::written).collect::<Vec<_>>().join(" "),
+            speaker: Some(speaker.to_string()),
+            channel: None,
+            words,
+        };
+
+        Transcript {
+            job_name: "talk".to_string(),
+            language: "en-US".to_string(),
+            result_uri: String::new(),
+            text: "Hello, world. How are you? Fine.".to_string(),
+            segments: vec![
+                segment("spk_0", vec![word("Hello", 0.5, 0.9, ","), word("world", 1.0, 1.4, ".")]),
+                segment("spk_0", vec![word("How", 2.0, 2.2, ""), word("are", 2.3, 2.4, ""), word("you", 2.5, 2.8, "?")]),
+                segment("spk_1", vec![word("Fine", 3661.0, 3661.5, ".")]),
+            ],
+            result_json: None,
+        }
+    }
+
+    #[test]
+    fn paragraphs_join_segments_of_one_speaker() {
+        let paragraphs = paragraphs(&transcript());
+
+        let texts: Vec<_> = paragraphs
+            .iter()
+            .map(|p| (p.speaker.as_deref(), p.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            [(Some("spk_0"), "Hello, world. How are you?"), (Some("spk_1"), "Fine.")]
+        );
+        assert_eq!(format_clock(paragraphs[1].start_seconds), "01:01:01");
+    }
+
+    #[test]
+    fn both_result_formats_can_be_read() {
+        let transcript = transcript();
+        let transcribe_result = serde_json::json!({
+            "jobName": "talk",
+            "results": { "transcripts": [{ "transcript": "Hello." }], "items": [] },
+        });
+
+        assert_eq!(read_transcript(&transcript.to_json().unwrap()).unwrap(), transcript);
+        assert_eq!(read_transcript(&transcribe_result.to_string()).unwrap().text, "Hello.");
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(TranscriptFormat::from_path(Path::new("talk.SRT")), Some(TranscriptFormat::Srt));
+        assert_eq!(TranscriptFormat::from_path(Path::new("talk.pdf")), None);
+        assert_eq!(TranscriptFormat::from_path(Path::new("talk")), None);
+        assert_eq!(OutputFile::from_path("talk.text").format, TranscriptFormat::Txt);
+    }
+
+    #[test]
+    fn template_names_outputs_after_the_media() {
+        let output = OutputFile::from_template(
+            Path::new("out"),
+            "{stem}.{lang}.{ext}",
+            Path::new("/videos/talk.final.mp4"),
+            "de-DE",
+            TranscriptFormat::Srt,
+        );
+
+        assert_eq!(output.path, Path::new("out/talk.final.de-DE.srt"));
+    }
+
+    #[test]
+    fn outputs_are_written_once_and_never_replaced_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let txt = OutputFile::from_path(dir.path().join("talk.txt"));
+        let md = OutputFile::from_path(dir.path().join("talk.md"));
+
+        assert!(check_outputs(&[txt.clone(), txt.clone()], true).is_err());
+        write_outputs(&transcript(), &[txt.clone(), md.clone()], false).unwrap();
+        assert!(std::fs::read_to_string(&md.path).unwrap().starts_with("# talk"));
+
+        assert!(check_outputs(std::slice::from_ref(&txt), false).is_err());
+        assert!(write_outputs(&transcript(), std::slice::from_ref(&txt), false).is_err());
+        check_outputs(std::slice::from_ref(&txt), true).unwrap();
+        write_outputs(&transcript(), &[txt], true).unwrap();
+    }
+
+    #[test]
+    fn json_is_the_result_as_returned() {
+        let mut transcript = transcript();
+        let prepis_json = render(&transcript, TranscriptFormat::Json).unwrap();
+        assert_eq!(Transcript::from_json(std::str::from_utf8(&prepis_json).unwrap()).unwrap(), transcript);
+
+        transcript.result_json = Some("{\"results\":{}}".to_string());
+        assert_eq!(render(&transcript, TranscriptFormat::Json).unwrap(), b"{\"results\":{}}");
+    }
+}
@@ -0,0 +1,197 @@
+//! SubRip (SRT) and WebVTT subtitles
+
+use crate::error::AppError;
+use crate::models::{Transcript, Word};
+
+/// Longest line of a subtitle, in characters
+const MAX_LINE_CHARS: usize = 42;
+
+/// Longest subtitle, which is two full lines
+const MAX_CUE_CHARS: usize = 2 * MAX_LINE_CHARS;
+
+/// Longest time a subtitle stays on screen
+const MAX_CUE_SECONDS: f64 = 7.0;
+
+/// A single subtitle
+#[derive(Debug, PartialEq)]
+struct Cue {
+    start_seconds: f64,
+    end_seconds: f64,
+    speaker: Option<String>,
+    text: String,
+}
+
+/// The transcript as SubRip subtitles
+pub(super) fn render_srt(transcript: &Transcript) -> Result<String, AppError> {
+    let srt = cues(transcript, "SRT")?
+        .into_iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                format_timestamp(cue.start_seconds, ','),
+                format_timestamp(cue.end_seconds, ','),
+                wrap(&cue.text)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(srt)
+}
+
+/// The transcript as WebVTT subtitles, with speakers as voice tags
+pub(super) fn render_vtt(transcript: &Transcript) -> Result<String, AppError> {
+    let mut vtt = String::from("WEBVTT\n");
+    for cue in cues(transcript, "WebVTT")? {
+        let text = wrap(&escape(&cue.text));
+        vtt.push_str(&format!(
+            "\n{} --> {}\n",
+            format_timestamp(cue.start_seconds, '.'),
+            format_timestamp(cue.end_seconds, '.')
+        ));
+        match cue.speaker {
+            Some(speaker) => vtt.push_str(&format!("<v {}>{}\n", escape(&speaker), text)),
+            None => vtt.push_str(&format!("{}\n", text)),
+        }
+    }
+    Ok(vtt)
+}
+
+/// Subtitles short enough to read, never spanning two segments
+fn cues(transcript: &Transcript, format: &str) -> Result<Vec<Cue>, AppError> {
+    if transcript.segments.is_empty() {
+        return Err(AppError::File(format!(
+            "The transcript has no timings, which {} subtitles need",
+            format
+        )));
+    }
+
+    let mut cues = Vec::new();
+    for segment in &transcript.segments {
+        if segment.words.is_empty() {
+            cues.push(Cue {
+                start_seconds: segment.start_seconds,
+                end_seconds: segment.end_seconds,
+                speaker: segment.speaker.clone(),
+                text: segment.text.clone(),
+            });
+            continue;
+        }
+
+        let mut words: Vec<&Word> = Vec::new();
+        for word in &segment.words {
+            if let Some(first) = words.first() {
+                let chars = words.iter().map(|w| w.written().chars().count() + 1).sum::<usize>()
+                    + word.written().chars().count();
+                if chars > MAX_CUE_CHARS || word.end_seconds - first.start_seconds > MAX_CUE_SECONDS {
+                    cues.push(cue(&words, segment.speaker.clone()));
+                    words.clear();
+                }
+            }
+            words.push(word);
+        }
+        cues.push(cue(&words, segment.speaker.clone()));
+    }
+    Ok(cues)
+}
+
+/// A subtitle showing the words
+fn cue(words: &[&Word], speaker: Option<String>) -> Cue {
+    Cue {
+        start_seconds: words[0].start_seconds,
+        end_seconds: words[words.len() - 1].end_seconds,
+        speaker,
+        text: words.iter().map(|word| word.written()).collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// Break long subtitles into two lines at the space closest to the middle
+fn wrap(text: &str) -> String {
+    if text.chars().count() <= MAX_LINE_CHARS {
+        return text.to_string();
+    }
+    let middle = text.len() / 2;
+    let split = text
+        .match_indices(' ')
+        .map(|(index, _)| index)
+        .min_by_key(|index| index.abs_diff(middle));
+    match split {
+        Some(index) => format!("{}\n{}", &text[..index], &text[index + 1..]),
+        None => text.to_string(),
+    }
+}
+
+/// Text safe to use in a WebVTT cue
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// A subtitle timestamp, e.g. `00:01:02,500` with a comma as separator
+fn format_timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::transcript;
+
+    #[test]
+    fn srt_has_numbered_cues() {
+        assert_eq!(
+            render_srt(&transcript()).unwrap(),
+            "1\n00:00:00,500 --> 00:00:01,400\nHello, world.\n\n\
+             2\n00:00:02,000 --> 00:00:02,800\nHow are you?\n\n\
+             3\n01:01:01,000 --> 01:01:01,500\nFine.\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_voice_tags() {
+        let vtt = render_vtt(&transcript()).unwrap();
+
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.500 --> 00:00:01.400\n<v spk_0>Hello, world.\n"));
+        assert!(vtt.ends_with("01:01:01.000 --> 01:01:01.500\n<v spk_1>Fine.\n"));
+    }
+
+    #[test]
+    fn long_segments_are_split_into_short_cues() {
+        let mut transcript = transcript();
+        let segment = &mut transcript.segments[0];
+        segment.words = (0..30)
+            .map(|i| Word {
+                text: format!("word{}", i),
+                start_seconds: i as f64 * 0.5,
+                end_seconds: i as f64 * 0.5 + 0.4,
+                confidence: None,
+                punctuation: String::new(),
+            })
+            .collect();
+
+        let cues = cues(&transcript, "SRT").unwrap();
+
+        assert!(cues.len() > 3);
+        for cue in &cues {
+            assert!(cue.text.len() <= MAX_CUE_CHARS, "{}", cue.text);
+            assert!(cue.end_seconds - cue.start_seconds <= MAX_CUE_SECONDS);
+            assert!(wrap(&cue.text).lines().all(|line| line.len() <= MAX_LINE_CHARS));
+        }
+    }
+
+    #[test]
+    fn subtitles_need_timings() {
+        let mut transcript = transcript();
+        transcript.segments.clear();
+
+        assert!(render_srt(&transcript).is_err());
+    }
+}
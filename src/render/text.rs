@@ -0,0 +1,80 @@
+//! Plain text and Markdown
+
+use super::{format_clock, paragraphs};
+use crate::models::Transcript;
+
+/// The transcript as plain text
+///
+/// Transcripts with speakers get a line per speaker turn, all others are
+/// written as the single block of text the backend returned.
+pub(super) fn render_txt(transcript: &Transcript) -> String {
+    if transcript.segments.iter().all(|segment| segment.speaker.is_none()) {
+        return format!("{}\n", transcript.text.trim());
+    }
+
+    paragraphs(transcript)
+        .into_iter()
+        .map(|paragraph| match paragraph.speaker {
+            Some(speaker) => format!("{}: {}\n", speaker, paragraph.text),
+            None => format!("{}\n", paragraph.text),
+        })
+        .collect()
+}
+
+/// The transcript as Markdown, with a timestamp in front of every paragraph
+pub(super) fn render_md(transcript: &Transcript) -> String {
+    let title = match transcript.job_name.as_str() {
+        "" => "Transcript",
+        job_name => job_name,
+    };
+    let mut markdown = format!("# {}\n", title);
+
+    let timed = !transcript.segments.is_empty();
+    for paragraph in paragraphs(transcript) {
+        markdown.push('\n');
+        match (timed, paragraph.speaker) {
+            (true, Some(speaker)) => markdown.push_str(&format!(
+                "**[{}] {}:** ",
+                format_clock(paragraph.start_seconds),
+                speaker
+            )),
+            (true, None) => {
+                markdown.push_str(&format!("**[{}]** ", format_clock(paragraph.start_seconds)))
+            }
+            (false, _) => {}
+        }
+        markdown.push_str(&paragraph.text);
+        markdown.push('\n');
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::transcript;
+
+    #[test]
+    fn txt_has_a_line_per_speaker_turn() {
+        assert_eq!(
+            render_txt(&transcript()),
+            "spk_0: Hello, world. How are you?\nspk_1: Fine.\n"
+        );
+    }
+
+    #[test]
+    fn txt_without_speakers_is_the_full_text() {
+        let mut transcript = transcript();
+        transcript.segments.iter_mut().for_each(|segment| segment.speaker = None);
+
+        assert_eq!(render_txt(&transcript), "Hello, world. How are you? Fine.\n");
+    }
+
+    #[test]
+    fn md_has_timestamps_and_speakers() {
+        assert_eq!(
+            render_md(&transcript()),
+            "# talk\n\n**[00:00:00] spk_0:** Hello, world. How are you?\n\n**[01:01:01] spk_1:** Fine.\n"
+        );
+    }
+}
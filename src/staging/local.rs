@@ -0,0 +1,304 @@
+//! # Local Staging
+//!
+//! This module implements [`MediaStaging`] for a directory on disk, e.g. a
+//! share that a transcription service reads from. Keys become paths below
+//! the directory, and multipart uploads keep their parts in a hidden
+//! `.multipart` directory until they are completed.
+
+use super::{MediaStaging, StagedObject, UploadedPart};
+use crate::error::AppError;
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+
+/// Directory below the root that holds the parts of open multipart uploads
+const MULTIPART_DIR: &str = ".multipart";
+
+/// Media staged in a local directory
+#[derive(Debug)]
+pub struct LocalStaging {
+    root: PathBuf,
+    uploads_started: AtomicU64, // Keeps upload IDs unique within a process
+}
+
+impl LocalStaging {
+    /// Stage media below the given directory, which is created when needed
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            uploads_started: AtomicU64::new(0),
+        }
+    }
+
+    /// Path of a key, refusing keys that would leave the directory
+    fn path(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        let inside = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if key.is_empty() || !inside || key.starts_with(MULTIPART_DIR) {
+            return Err(AppError::File(format!("Invalid staging key: {}", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Directory with the parts of a multipart upload
+    fn upload_dir(&self, upload_id: &str) -> Result<PathBuf, AppError> {
+        if upload_id.is_empty() || upload_id.contains(['/', '\\', '.']) {
+            return Err(AppError::File(format!("Invalid upload ID: {}", upload_id)));
+        }
+        Ok(self.root.join(MULTIPART_DIR).join(upload_id))
+    }
+
+    /// Directory of an upload that must have been started
+    async fn open_upload(&self, key: &str, upload_id: &str) -> Result<PathBuf, AppError> {
+        let dir = self.upload_dir(upload_id)?;
+        if !tokio::fs::try_exists(&dir).await? {
+            return Err(AppError::NotFound(format!("Multipart upload {} of {}", upload_id, key)));
+        }
+        Ok(dir)
+    }
+}
+
+#[async_trait]
+impl MediaStaging for LocalStaging {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn uri(&self, key: &str) -> String {
+        self.root.join(key).display().to_string()
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), AppError> {
+        write_atomically(&self.path(key)?, &[body]).await
+    }
+
+    async fn create_multipart(&self, key: &str) -> Result<String, AppError> {
+        self.path(key)?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        let upload_id = format!(
+            "{}-{}-{}",
+            std::process::id(),
+            nanos,
+            self.uploads_started.fetch_add(1, Ordering::Relaxed)
+        );
+        tokio::fs::create_dir_all(self.upload_dir(&upload_id)?).await?;
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, AppError> {
+        let dir = self.open_upload(key, upload_id).await?;
+        tokio::fs::write(dir.join(part_number.to_string()), &body).await?;
+        Ok(UploadedPart {
+            part_number,
+            etag: part_number.to_string(),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), AppError> {
+        if parts.is_empty() {
+            return Err(AppError::File("A multipart upload needs at least one part".to_string()));
+        }
+        let dir = self.open_upload(key, upload_id).await?;
+
+        let mut bodies = Vec::with_capacity(parts.len());
+        for part in &parts {
+            bodies.push(tokio::fs::read(dir.join(part.part_number.to_string())).await?);
+        }
+        write_atomically(&self.path(key)?, &bodies).await?;
+        tokio::fs::remove_dir_all(dir).await?;
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), AppError> {
+        let dir = self.open_upload(key, upload_id).await?;
+        tokio::fs::remove_dir_all(dir).await?;
+        Ok(())
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<StagedObject>, AppError> {
+        match tokio::fs::metadata(self.path(key)?).await {
+            Ok(metadata) if metadata.is_file() => Ok(Some(StagedObject {
+                key: key.to_string(),
+                size_bytes: metadata.len(),
+            })),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StagedObject>, AppError> {
+        let root = self.root.clone();
+        let prefix = prefix.to_string();
+        let objects = tokio::task::spawn_blocking(move || list_files(&root, &prefix))
+            .await
+            .map_err(|e| AppError::File(format!("Failed to list {}: {}", self.root.display(), e)))??;
+        Ok(objects)
+    }
+}
+
+/// Write the bodies one after another into a file, which appears only once complete
+async fn write_atomically(path: &Path, bodies: &[Vec<u8>]) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let mut file = tokio::fs::File::create(&partial).await?;
+    for body in bodies {
+        file.write_all(body).await?;
+    }
+    file.flush().await?;
+    drop(file);
+
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+/// Every file below the root whose key starts with the prefix, sorted by key
+fn list_files(root: &Path, prefix: &str) -> Result<Vec<StagedObject>, AppError> {
+    let mut objects = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() && path != root.join(MULTIPART_DIR) {
+                dirs.push(path);
+            } else if file_type.is_file() {
+                let key = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if key.starts_with(prefix) {
+                    objects.push(StagedObject {
+                        key,
+                        size_bytes: entry.metadata()?.len(),
+                    });
+                }
+            }
+        }
+    }
+
+    objects.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn multipart_upload_is_joined_into_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = LocalStaging::new(dir.path());
+
+        let upload_id = staging.create_multipart("media/talk.wav").await.unwrap();
+        let second = staging.upload_part("media/talk.wav", &upload_id, 2, b"world".to_vec()).await.unwrap();
+        let first = staging.upload_part("media/talk.wav", &upload_id, 1, b"hello ".to_vec()).await.unwrap();
+        staging.complete_multipart("media/talk.wav", &upload_id, vec![first, second]).await.unwrap();
+
+        assert_eq!(std::fs::read(dir.path().join("media/talk.wav")).unwrap(), b"hello world");
+        assert!(!dir.path().join(MULTIPART_DIR).join(&upload_id).exists());
+    }
+
+    #[tokio::test]
+    async fn aborted_upload_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = LocalStaging::new(dir.path());
+
+        let upload_id = staging.create_multipart("talk.wav").await.unwrap();
+        staging.upload_part("talk.wav", &upload_id, 1, b"hello".to_vec()).await.unwrap();
+        staging.abort_multipart("talk.wav", &upload_id).await.unwrap();
+
+        assert_eq!(staging.head("talk.wav").await.unwrap(), None);
+        assert_eq!(staging.list("").await.unwrap(), Vec::new());
+        assert!(staging.upload_part("talk.wav", &upload_id, 2, Vec::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn list_finds_keys_by_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = LocalStaging::new(dir.path());
+        staging.put("transcribe-temp/a.wav", b"a".to_vec()).await.unwrap();
+        staging.put("transcribe-temp/b.wav", b"bb".to_vec()).await.unwrap();
+        staging.put("other/c.wav", b"c".to_vec()).await.unwrap();
+
+        let keys: Vec<_> = staging
+            .list("transcribe-temp/")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| (object.key, object.size_bytes))
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("transcribe-temp/a.wav".to_string(), 1),
+                ("transcribe-temp/b.wav".to_string(), 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_of_a_missing_key_succeeds() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = LocalStaging::new(dir.path());
+        staging.put("talk.wav", b"data".to_vec()).await.unwrap();
+
+        staging.delete("talk.wav").await.unwrap();
+        staging.delete("talk.wav").await.unwrap();
+
+        assert_eq!(staging.head("talk.wav").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn keys_cannot_leave_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = LocalStaging::new(dir.path().join("staging"));
+
+        assert!(staging.put("../escape.wav", Vec::new()).await.is_err());
+        assert!(staging.put("/etc/escape.wav", Vec::new()).await.is_err());
+        assert!(!dir.path().join("escape.wav").exists());
+    }
+}
@@ -0,0 +1,216 @@
+//! # In-Memory Staging
+//!
+//! This module implements [`MediaStaging`] in memory, as a fake for tests
+//! of code that stages media. It behaves like S3 and can be told to fail
+//! at specific steps.
+
+use super::{MediaStaging, StagedObject, UploadedPart};
+use crate::error::AppError;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+
+/// Media staged in memory
+#[derive(Debug, Default)]
+pub struct MemoryStaging {
+    name: String,
+    state: Mutex<State>,
+}
+
+/// Everything stored, and the failures to simulate
+#[derive(Debug, Default)]
+struct State {
+    objects: BTreeMap<String, Vec<u8>>,
+    uploads: HashMap<String, Upload>,
+    next_upload_id: u64,
+    completed_part_counts: Vec<usize>,
+    aborted_uploads: usize,
+    fail_on_part: Option<i32>,
+    fail_on_complete: bool,
+    fail_on_delete: bool,
+}
+
+/// A multipart upload in progress
+#[derive(Debug)]
+struct Upload {
+    key: String,
+    parts: BTreeMap<i32, Vec<u8>>,
+}
+
+impl MemoryStaging {
+    /// Create an empty staging area, named like a bucket
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Contents of a stored object
+    pub fn object(&self, key: &str) -> Option<Vec<u8>> {
+        self.state().objects.get(key).cloned()
+    }
+
+    /// Multipart uploads that were neither completed nor aborted
+    pub fn open_uploads(&self) -> usize {
+        self.state().uploads.len()
+    }
+
+    /// Number of parts of every completed multipart upload, in order
+    pub fn completed_part_counts(&self) -> Vec<usize> {
+        self.state().completed_part_counts.clone()
+    }
+
+    /// Number of multipart uploads that were aborted
+    pub fn aborted_uploads(&self) -> usize {
+        self.state().aborted_uploads
+    }
+
+    /// Fail the upload of the part with this number
+    pub fn fail_on_part(&self, part_number: i32) {
+        self.state().fail_on_part = Some(part_number);
+    }
+
+    /// Fail completing multipart uploads
+    pub fn fail_on_complete(&self) {
+        self.state().fail_on_complete = true;
+    }
+
+    /// Fail deleting objects
+    pub fn fail_on_delete(&self) {
+        self.state().fail_on_delete = true;
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panicking test must not hide the state from the others
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl MediaStaging for MemoryStaging {
+    fn location(&self) -> String {
+        self.name.clone()
+    }
+
+    fn uri(&self, key: &str) -> String {
+        format!("memory://{}/{}", self.name, key)
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), AppError> {
+        self.state().objects.insert(key.to_string(), body);
+        Ok(())
+    }
+
+    async fn create_multipart(&self, key: &str) -> Result<String, AppError> {
+        let mut state = self.state();
+        state.next_upload_id += 1;
+        let upload_id = format!("upload-{}", state.next_upload_id);
+        state.uploads.insert(
+            upload_id.clone(),
+            Upload {
+                key: key.to_string(),
+                parts: BTreeMap::new(),
+            },
+        );
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, AppError> {
+        let mut state = self.state();
+        if state.fail_on_part == Some(part_number) {
+            return Err(AppError::S3(format!("Failed to upload part {}", part_number)));
+        }
+        let upload = open_upload(&mut state.uploads, key, upload_id)?;
+        upload.parts.insert(part_number, body);
+
+        Ok(UploadedPart {
+            part_number,
+            etag: format!("\"{}-{}\"", upload_id, part_number),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), AppError> {
+        let mut state = self.state();
+        if state.fail_on_complete {
+            return Err(AppError::S3("Failed to complete multipart upload".to_string()));
+        }
+        if parts.is_empty() {
+            return Err(AppError::S3("A multipart upload needs at least one part".to_string()));
+        }
+
+        let upload = open_upload(&mut state.uploads, key, upload_id)?;
+        let mut body = Vec::new();
+        for part in &parts {
+            let data = upload.parts.get(&part.part_number).ok_or_else(|| {
+                AppError::S3(format!("Part {} was never uploaded", part.part_number))
+            })?;
+            body.extend_from_slice(data);
+        }
+
+        state.uploads.remove(upload_id);
+        state.objects.insert(key.to_string(), body);
+        state.completed_part_counts.push(parts.len());
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), AppError> {
+        let mut state = self.state();
+        open_upload(&mut state.uploads, key, upload_id)?;
+        state.uploads.remove(upload_id);
+        state.aborted_uploads += 1;
+        Ok(())
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<StagedObject>, AppError> {
+        Ok(self.state().objects.get(key).map(|body| StagedObject {
+            key: key.to_string(),
+            size_bytes: body.len() as u64,
+        }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let mut state = self.state();
+        if state.fail_on_delete {
+            return Err(AppError::S3(format!("Failed to delete {}", key)));
+        }
+        state.objects.remove(key);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StagedObject>, AppError> {
+        Ok(self
+            .state()
+            .objects
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, body)| StagedObject {
+                key: key.clone(),
+                size_bytes: body.len() as u64,
+            })
+            .collect())
+    }
+}
+
+/// The upload with this ID, which must belong to the key
+fn open_upload<'a>(
+    uploads: &'a mut HashMap<String, Upload>,
+    key: &str,
+    upload_id: &str,
+) -> Result<&'a mut Upload, AppError> {
+    uploads
+        .get_mut(upload_id)
+        .filter(|upload| upload.key == key)
+        .ok_or_else(|| AppError::NotFound(format!("Multipart upload {} of {}", upload_id, key)))
+}
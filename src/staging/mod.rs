@@ -0,0 +1,435 @@
+//! # Media Staging
+//!
+//! This module defines [`MediaStaging`], the storage media is put in while a
+//! transcription service reads it, and the upload and cleanup logic that
+//! runs on top of it.
+//!
+//! It provides:
+//! - The staging trait with plain puts, the multipart upload lifecycle,
+//!   head, delete and listing by prefix
+//! - [`upload_file`], which picks a simple or multipart upload and reports
+//!   progress, aborting multipart uploads that fail
+//! - [`cleanup`], which removes staged media without failing the run
+//! - Staging in S3 (see [`s3`]), in memory (see [`memory`]) and in a local
+//!   directory (see [`local`])
+//!
+//! The in-memory staging makes the upload logic testable without AWS.
+
+pub mod local;
+pub mod memory;
+pub mod s3;
+
+pub use local::LocalStaging;
+pub use memory::MemoryStaging;
+pub use s3::S3Staging;
+
+use crate::error::AppError;
+use crate::events::{self, Event};
+use crate::progress::{ProgressTracker, UploadStrategy};
+use async_trait::async_trait;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+
+/// A part of a multipart upload that has been stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedPart {
+    pub part_number: i32, // Starts at 1
+    pub etag: String,
+}
+
+/// An object in the staging storage
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedObject {
+    pub key: String,
+    pub size_bytes: u64,
+}
+
+/// Storage that media is staged in, e.g. an S3 bucket
+///
+/// Keys are relative to the storage, like S3 object keys. Deleting a key
+/// that doesn't exist is not an error.
+#[async_trait]
+pub trait MediaStaging: Send + Sync {
+    /// Name of the bucket or directory the media is staged in
+    fn location(&self) -> String;
+
+    /// URI of a staged key, e.g. `s3://bucket/key`
+    fn uri(&self, key: &str) -> String;
+
+    /// Store an object in one request
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), AppError>;
+
+    /// Start a multipart upload and return its upload ID
+    async fn create_multipart(&self, key: &str) -> Result<String, AppError>;
+
+    /// Store one part of a multipart upload
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, AppError>;
+
+    /// Join the uploaded parts into the object
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), AppError>;
+
+    /// Drop a multipart upload and every part stored for it
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), AppError>;
+
+    /// Look up an object, returning `None` when it doesn't exist
+    async fn head(&self, key: &str) -> Result<Option<StagedObject>, AppError>;
+
+    /// Remove an object
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+
+    /// Every object whose key starts with the prefix
+    async fn list(&self, prefix: &str) -> Result<Vec<StagedObject>, AppError>;
+}
+
+/// Upload a file under the given key and return its URI
+///
+/// Large files are uploaded in parts (see [`UploadStrategy`]). Progress is
+/// shown as a progress bar and emitted as [`Event::UploadProgress`].
+pub async fn upload_file(
+    staging: &dyn MediaStaging,
+    key: &str,
+    file_path: &Path,
+) -> Result<String, AppError> {
+    let file_size = tokio::fs::metadata(file_path).await?.len();
+
+    info!("📤 Uploading file to {}", staging.uri(key));
+    info!("📤 NOTE: This file will be deleted at the end");
+
+    match UploadStrategy::determine(file_size) {
+        UploadStrategy::Simple => upload_simple(staging, key, file_path, file_size).await,
+        UploadStrategy::Multipart { part_size } => {
+            upload_multipart(staging, key, file_path, file_size, part_size).await
+        }
+    }
+}
+
+/// Remove staged media, warning instead of failing when that doesn't work
+///
+/// The transcript is already in at this point, so a leftover file is not
+/// worth failing the run for.
+pub async fn cleanup(staging: &dyn MediaStaging, key: &str) -> Result<(), AppError> {
+    info!("🗑️  Cleaning up file: {}", staging.uri(key));
+
+    match staging.delete(key).await {
+        Ok(()) => {
+            info!("✅ File deleted successfully");
+            events::emit(Event::Cleanup {
+                bucket: staging.location(),
+                key: key.to_string(),
+            });
+        }
+        Err(e) => warn!("Failed to delete {}, please do so manually: {}", staging.uri(key), e),
+    }
+    Ok(())
+}
+
+/// Upload a file in a single request
+async fn upload_simple(
+    staging: &dyn MediaStaging,
+    key: &str,
+    file_path: &Path,
+    file_size: u64,
+) -> Result<String, AppError> {
+    let file_name = display_name(file_path);
+
+    // Create progress tracker with fallback support
+    let progress_tracker = if file_size == 0 {
+        ProgressTracker::new_indeterminate(&file_name)
+    } else {
+        ProgressTracker::new(file_size, &file_name)
+    };
+
+    // Read file in chunks to provide progress updates
+    let mut file = File::open(file_path).await?;
+    let mut buffer = Vec::with_capacity(file_size as usize);
+
+    const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+    let mut chunk_buffer = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let bytes_read = file.read(&mut chunk_buffer).await?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        buffer.extend_from_slice(&chunk_buffer[..bytes_read]);
+        progress_tracker.update_progress(bytes_read as u64);
+
+        // Small delay to make progress visible for small files
+        if file_size < 1024 * 1024 { // < 1MB
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    match staging.put(key, buffer).await {
+        Ok(()) => {
+            progress_tracker.finish();
+            Ok(staging.uri(key))
+        }
+        Err(e) => {
+            progress_tracker.abandon();
+            Err(e)
+        }
+    }
+}
+
+/// Upload a file in parts of `part_size` bytes
+///
+/// Any failure aborts the upload, so no orphaned parts are left behind.
+async fn upload_multipart(
+    staging: &dyn MediaStaging,
+    key: &str,
+    file_path: &Path,
+    file_size: u64,
+    part_size: usize,
+) -> Result<String, AppError> {
+    let progress_tracker = ProgressTracker::new(file_size, &display_name(file_path));
+    let upload_id = staging.create_multipart(key).await?;
+
+    let parts = match upload_parts(staging, key, &upload_id, file_path, part_size, &progress_tracker).await {
+        Ok(parts) if parts.is_empty() => {
+            // The file was emptied after its size was read
+            Err(AppError::File(format!("{} has no data to upload", file_path.display())))
+        }
+        result => result,
+    };
+    let result = match parts {
+        Ok(parts) => staging.complete_multipart(key, &upload_id, parts).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => {
+            progress_tracker.finish();
+            Ok(staging.uri(key))
+        }
+        Err(e) => {
+            progress_tracker.abandon();
+            if let Err(abort_err) = staging.abort_multipart(key, &upload_id).await {
+                warn!("Failed to abort multipart upload: {}", abort_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Read the file part by part and upload every part
+async fn upload_parts(
+    staging: &dyn MediaStaging,
+    key: &str,
+    upload_id: &str,
+    file_path: &Path,
+    part_size: usize,
+    progress_tracker: &ProgressTracker,
+) -> Result<Vec<UploadedPart>, AppError> {
+    let mut file = File::open(file_path).await?;
+    let mut part_number = 1;
+    let mut parts = Vec::new();
+    let mut buffer = vec![0u8; part_size];
+
+    loop {
+        // Read exactly part_size bytes, or whatever remains
+        let mut total_read = 0;
+        while total_read < part_size {
+            let bytes_read = file.read(&mut buffer[total_read..]).await?;
+            if bytes_read == 0 {
+                break; // End of file
+            }
+            total_read += bytes_read;
+        }
+
+        if total_read == 0 {
+            break; // No more data to read
+        }
+
+        let part = staging
+            .upload_part(key, upload_id, part_number, buffer[..total_read].to_vec())
+            .await?;
+        parts.push(part);
+
+        progress_tracker.update_progress(total_read as u64);
+        part_number += 1;
+    }
+
+    Ok(parts)
+}
+
+/// File name shown next to the progress bar
+fn display_name(file_path: &Path) -> String {
+    file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// A temporary file with the given contents
+    fn temp_file(contents: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents).unwrap();
+        file
+    }
+
+    /// Data where every byte tells its position, so misordered parts show up
+    fn numbered_bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn simple_upload_stores_the_whole_file() {
+        let staging = MemoryStaging::new("bucket");
+        let data = numbered_bytes(200_000);
+        let file = temp_file(&data);
+
+        let uri = upload_file(&staging, "media/talk.mp4", file.path()).await.unwrap();
+
+        assert_eq!(uri, "memory://bucket/media/talk.mp4");
+        assert_eq!(staging.object("media/talk.mp4"), Some(data));
+    }
+
+    #[tokio::test]
+    async fn multipart_upload_joins_the_parts_in_order() {
+        let staging = MemoryStaging::new("bucket");
+        let data = numbered_bytes(10_500);
+        let file = temp_file(&data);
+
+        let uri = upload_multipart(&staging, "big.mp4", file.path(), data.len() as u64, 1_000)
+            .await
+            .unwrap();
+
+        assert_eq!(uri, "memory://bucket/big.mp4");
+        assert_eq!(staging.object("big.mp4"), Some(data));
+        assert_eq!(staging.completed_part_counts(), vec![11]);
+        assert_eq!(staging.open_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn multipart_upload_of_an_exact_multiple_has_no_empty_part() {
+        let staging = MemoryStaging::new("bucket");
+        let data = numbered_bytes(3_000);
+        let file = temp_file(&data);
+
+        upload_multipart(&staging, "even.mp4", file.path(), data.len() as u64, 1_000)
+            .await
+            .unwrap();
+
+        assert_eq!(staging.object("even.mp4"), Some(data));
+        assert_eq!(staging.completed_part_counts(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn failed_part_aborts_the_upload() {
+        let staging = MemoryStaging::new("bucket");
+        staging.fail_on_part(3);
+        let file = temp_file(&numbered_bytes(5_000));
+
+        let result = upload_multipart(&staging, "big.mp4", file.path(), 5_000, 1_000).await;
+
+        assert!(result.is_err());
+        assert_eq!(staging.object("big.mp4"), None);
+        assert_eq!(staging.open_uploads(), 0);
+        assert_eq!(staging.aborted_uploads(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_completion_aborts_the_upload() {
+        let staging = MemoryStaging::new("bucket");
+        staging.fail_on_complete();
+        let file = temp_file(&numbered_bytes(2_500));
+
+        let result = upload_multipart(&staging, "big.mp4", file.path(), 2_500, 1_000).await;
+
+        assert!(result.is_err());
+        assert_eq!(staging.object("big.mp4"), None);
+        assert_eq!(staging.open_uploads(), 0);
+        assert_eq!(staging.aborted_uploads(), 1);
+    }
+
+    #[tokio::test]
+    async fn multipart_upload_without_parts_fails_and_aborts() {
+        let staging = MemoryStaging::new("bucket");
+        let file = temp_file(b"");
+
+        // The size was read before the file was emptied
+        let result = upload_multipart(&staging, "gone.mp4", file.path(), 5_000, 1_000).await;
+
+        assert!(matches!(result, Err(AppError::File(_))));
+        assert_eq!(staging.object("gone.mp4"), None);
+        assert_eq!(staging.completed_part_counts(), Vec::<usize>::new());
+        assert_eq!(staging.aborted_uploads(), 1);
+    }
+
+    #[tokio::test]
+    async fn empty_file_is_uploaded_in_one_request() {
+        let staging = MemoryStaging::new("bucket");
+        let file = temp_file(b"");
+
+        upload_file(&staging, "empty.wav", file.path()).await.unwrap();
+
+        assert_eq!(staging.object("empty.wav"), Some(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn cleanup_deletes_the_object_and_reports_it() {
+        let staging = MemoryStaging::new("bucket");
+        staging.put("media/talk.mp4", b"data".to_vec()).await.unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let seen = seen.clone();
+            Arc::new(move |event: &Event| seen.lock().unwrap().push(event.clone()))
+        };
+
+        events::scope(handler, cleanup(&staging, "media/talk.mp4")).await.unwrap();
+
+        assert_eq!(staging.head("media/talk.mp4").await.unwrap(), None);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![Event::Cleanup {
+                bucket: "bucket".to_string(),
+                key: "media/talk.mp4".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn cleanup_of_a_missing_object_succeeds() {
+        let staging = MemoryStaging::new("bucket");
+
+        cleanup(&staging, "never-uploaded.mp4").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_cleanup_only_warns() {
+        let staging = MemoryStaging::new("bucket");
+        staging.put("media/talk.mp4", b"data".to_vec()).await.unwrap();
+        staging.fail_on_delete();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let handler = {
+            let seen = seen.clone();
+            Arc::new(move |event: &Event| seen.lock().unwrap().push(event.clone()))
+        };
+
+        events::scope(handler, cleanup(&staging, "media/talk.mp4")).await.unwrap();
+
+        assert!(staging.object("media/talk.mp4").is_some());
+        assert!(seen.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! # S3 Staging
+//!
+//! This module implements [`MediaStaging`] for an Amazon S3 bucket, where
+//! Amazon Transcribe reads its media from.
+
+use super::{MediaStaging, StagedObject, UploadedPart};
+use crate::error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+
+/// Media staged in an S3 bucket
+#[derive(Debug, Clone)]
+pub struct S3Staging {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3Staging {
+    /// Stage media in the given bucket
+    pub fn new(client: aws_sdk_s3::Client, bucket: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaStaging for S3Staging {
+    fn location(&self) -> String {
+        self.bucket.clone()
+    }
+
+    fn uri(&self, key: &str) -> String {
+        format!("s3://{}/{}", self.bucket, key)
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<(), AppError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| AppError::s3("Failed to upload file to S3", e))?;
+        Ok(())
+    }
+
+    async fn create_multipart(&self, key: &str) -> Result<String, AppError> {
+        self.client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| AppError::s3("Failed to create multipart upload", e))?
+            .upload_id
+            .ok_or_else(|| AppError::S3("No upload ID returned".to_string()))
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<UploadedPart, AppError> {
+        let etag = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| AppError::s3(format!("Failed to upload part {}", part_number), e))?
+            .e_tag
+            .ok_or_else(|| AppError::S3(format!("No ETag returned for part {}", part_number)))?;
+
+        Ok(UploadedPart { part_number, etag })
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> Result<(), AppError> {
+        let parts = parts
+            .into_iter()
+            .map(|part| {
+                CompletedPart::builder()
+                    .part_number(part.part_number)
+                    .e_tag(part.etag)
+                    .build()
+            })
+            .collect();
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .map_err(|e| AppError::s3("Failed to complete multipart upload", e))?;
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), AppError> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(|e| AppError::s3("Failed to abort multipart upload", e))?;
+        Ok(())
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<StagedObject>, AppError> {
+        match self.client.head_object().bucket(&self.bucket).key(key).send().await {
+            Ok(output) => Ok(Some(StagedObject {
+                key: key.to_string(),
+                size_bytes: output.content_length.unwrap_or(0).max(0) as u64,
+            })),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(AppError::s3(format!("Failed to look up {}", self.uri(key)), e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| AppError::s3(format!("Failed to delete {}", self.uri(key)), e))?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StagedObject>, AppError> {
+        let mut objects = Vec::new();
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| {
+                AppError::s3(format!("Failed to list {}", self.uri(prefix)), e)
+            })?;
+            objects.extend(page.contents.unwrap_or_default().into_iter().filter_map(|object| {
+                Some(StagedObject {
+                    key: object.key?,
+                    size_bytes: object.size.unwrap_or(0).max(0) as u64,
+                })
+            }));
+        }
+        Ok(objects)
+    }
+}
@@ -0,0 +1,260 @@
+//! # Transcriber
+//!
+//! This module provides [`Transcriber`], the transcription pipeline of
+//! prepis for use as a library. It runs on any [`TranscriptionBackend`],
+//! Amazon Transcribe by default.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), prepis::AppError> {
+//! use prepis::aws::{create_aws_clients, AwsOptions};
+//! use prepis::Transcriber;
+//!
+//! let clients = create_aws_clients(&AwsOptions::default()).await?;
+//! let transcriber = Transcriber::builder()
+//!     .clients(clients)
+//!     .bucket("my-bucket")
+//!     .on_progress(|event| println!("{:?}", event))
+//!     .build()?;
+//!
+//! let transcript = transcriber.transcribe("talk.mp4").await?;
+//! println!("{}", transcript.text);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Transcriber::transcribe`] runs every step at once. The steps are also
+//! available one by one ([`prepare`](Transcriber::prepare),
+//! [`submit`](Transcriber::submit), [`wait`](Transcriber::wait) and
+//! [`finish`](Transcriber::finish)) for callers that need to act in between,
+//! like the CLI does for `--detach` and to save the transcript before the
+//! result is removed.
+//!
+//! Progress is reported as [`Event`]s to a callback or a channel. Human
+//! messages are logged with `tracing` and only show up when the caller
+//! installs a subscriber.
+
+use crate::aws::AwsClients;
+use crate::backend::{AmazonTranscribe, PreparedMedia, SubmittedJob, TranscriptionBackend};
+use crate::error::AppError;
+use crate::events::{self, Event, EventHandler};
+use crate::file;
+use crate::models::{JobOptions, PollOptions, Transcript, TranscriptionStatus};
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{info, warn};
+
+/// Transcribes media files with a transcription backend
+pub struct Transcriber {
+    backend: Box<dyn TranscriptionBackend>,
+    options: JobOptions,
+    poll_options: Option<PollOptions>, // Derived from the media length when not set
+    on_progress: Option<EventHandler>,
+}
+
+/// Builder for a [`Transcriber`]
+///
+/// Either give a [`backend`](Self::backend), or AWS clients and a bucket for
+/// the Amazon Transcribe backend.
+#[derive(Default)]
+pub struct TranscriberBuilder {
+    backend: Option<Box<dyn TranscriptionBackend>>,
+    clients: Option<AwsClients>,
+    bucket: Option<String>,
+    keep_output: bool,
+    options: JobOptions,
+    poll_options: Option<PollOptions>,
+    on_progress: Option<EventHandler>,
+}
+
+impl TranscriberBuilder {
+    /// Backend that does the transcription, e.g. `Box::new(AmazonTranscribe::new(...))`
+    pub fn backend(mut self, backend: Box<dyn TranscriptionBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// AWS clients for Amazon Transcribe, see [`crate::aws::create_aws_clients`]
+    pub fn clients(mut self, clients: AwsClients) -> Self {
+        self.clients = Some(clients);
+        self
+    }
+
+    /// S3 bucket for the temporary upload of the media to Amazon Transcribe
+    pub fn bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = Some(bucket.into());
+        self
+    }
+
+    /// Keep the Amazon Transcribe result JSON in the output bucket instead of deleting it
+    pub fn keep_output(mut self, keep_output: bool) -> Self {
+        self.keep_output = keep_output;
+        self
+    }
+
+    /// Options of the transcription job, e.g. the language
+    pub fn options(mut self, options: JobOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// How to wait for the job, derived from the media length by default
+    pub fn poll_options(mut self, poll_options: PollOptions) -> Self {
+        self.poll_options = Some(poll_options);
+        self
+    }
+
+    /// Call a function for every progress event
+    pub fn on_progress(mut self, callback: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Send every progress event to a channel
+    pub fn progress_channel(self, sender: UnboundedSender<Event>) -> Self {
+        self.on_progress(move |event| {
+            // A receiver that went away just stops listening
+            let _ = sender.send(event.clone());
+        })
+    }
+
+    /// Check the configuration and create the transcriber
+    pub fn build(self) -> Result<Transcriber, AppError> {
+        let backend = match (self.backend, self.clients, self.bucket) {
+            (Some(backend), _, _) => backend,
+            (None, Some(clients), Some(bucket)) => {
+                Box::new(AmazonTranscribe::new(clients, bucket).keep_output(self.keep_output))
+            }
+            (None, None, _) => {
+                return Err(AppError::Config(
+                    "No backend or AWS clients given to the transcriber".to_string(),
+                ));
+            }
+            (None, Some(_), None) => {
+                return Err(AppError::Config("No S3 bucket given to the transcriber".to_string()));
+            }
+        };
+        crate::aws::transcribe::validate_job_options(&self.options)?;
+
+        Ok(Transcriber {
+            backend,
+            options: self.options,
+            poll_options: self.poll_options,
+            on_progress: self.on_progress,
+        })
+    }
+}
+
+impl Transcriber {
+    /// Start building a transcriber
+    pub fn builder() -> TranscriberBuilder {
+        TranscriberBuilder::default()
+    }
+
+    /// Backend the transcriber runs on
+    pub fn backend(&self) -> &dyn TranscriptionBackend {
+        self.backend.as_ref()
+    }
+
+    /// Transcribe a media file from start to finish
+    ///
+    /// Validates the file, hands it to the backend, waits for the job,
+    /// fetches the transcript and cleans up after the backend.
+    pub async fn transcribe(&self, path: impl AsRef<Path>) -> Result<Transcript, AppError> {
+        let media = self.with_progress(async { Self::prepare(path.as_ref()) }).await?;
+        let job = self.submit(&media).await?;
+        let transcript = self.wait(&job).await?;
+        self.finish(&job, &transcript).await?;
+        Ok(transcript)
+    }
+
+    /// Validate a media file before anything is uploaded
+    ///
+    /// This needs no backend, so it can run before one is set up.
+    pub fn prepare(path: &Path) -> Result<PreparedMedia, AppError> {
+        let info = file::validate_video_file(path)?;
+        let media = PreparedMedia {
+            path: path.to_path_buf(),
+            size_bytes: std::fs::metadata(path)?.len(),
+            info,
+        };
+
+        events::emit(Event::Validated {
+            file: path.to_string_lossy().into_owned(),
+            size_bytes: media.size_bytes,
+            duration_seconds: media.duration().map(|d| d.as_secs_f64()),
+        });
+        Ok(media)
+    }
+
+    /// Stage a prepared file with the backend and start its transcription job
+    pub async fn submit(&self, media: &PreparedMedia) -> Result<SubmittedJob, AppError> {
+        self.with_progress(async {
+            let staged = self.backend.prepare_media(media).await?;
+            let job = match self.backend.submit(media, &staged, &self.options).await {
+                Ok(job) => job,
+                Err(e) => {
+                    // Don't leave the staged media behind for a job that never started
+                    if let Err(cleanup_err) = self.backend.cleanup(&staged).await {
+                        warn!("Failed to clean up {}: {}", staged.uri(), cleanup_err);
+                    }
+                    return Err(e);
+                }
+            };
+            events::emit(Event::JobStarted {
+                job_name: job.job_name.clone(),
+                s3_uri: staged.s3_uri(),
+                language: job.options.language.clone(),
+            });
+            Ok(job)
+        })
+        .await
+    }
+
+    /// Wait for a submitted job and fetch its transcript
+    ///
+    /// The result and the staged media are kept until [`finish`](Self::finish),
+    /// so the transcript can be fetched again if saving it fails.
+    pub async fn wait(&self, job: &SubmittedJob) -> Result<Transcript, AppError> {
+        self.with_progress(async {
+            let poll_options = self
+                .poll_options
+                .clone()
+                .unwrap_or_else(|| PollOptions::for_media(job.media_duration));
+
+            let result_uri = match self.backend.poll(job, &poll_options).await? {
+                TranscriptionStatus::Completed(result_uri) => result_uri,
+                TranscriptionStatus::Failed(reason) => {
+                    return Err(AppError::Transcribe(format!(
+                        "Transcription failed: {}",
+                        reason
+                    )));
+                }
+            };
+            info!("🎉 Transcription completed! Result URI: {}", result_uri);
+
+            self.backend.fetch_transcript(job, &result_uri).await
+        })
+        .await
+    }
+
+    /// Remove the result and the staged media of a job whose transcript is saved
+    pub async fn finish(&self, job: &SubmittedJob, transcript: &Transcript) -> Result<(), AppError> {
+        self.with_progress(async {
+            self.backend.remove_result(job, &transcript.result_uri).await?;
+            self.backend.cleanup(&job.staged).await
+        })
+        .await
+    }
+
+    /// Run a step with the progress handler of this transcriber, if any
+    ///
+    /// Without one, events go to the handler of the caller's scope.
+    async fn with_progress<F: Future>(&self, step: F) -> F::Output {
+        match &self.on_progress {
+            Some(handler) => events::scope(handler.clone(), step).await,
+            None => step.await,
+        }
+    }
+}
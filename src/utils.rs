@@ -5,14 +5,24 @@
 //!
 //! It includes:
 //! - Functions for generating unique identifiers
-//! - Time-based utilities
+//! - Time-based utilities and duration formatting
 //! - Path and filename manipulation helpers
+//! - XDG base directory lookup
+//! - S3 object URL parsing
 //!
 //! These utilities are designed to be reusable and independent of specific
 //! application logic.
 
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use percent_encoding::percent_decode_str;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Prefix under which media files are temporarily stored in S3
+pub const S3_TEMP_PREFIX: &str = "transcribe-temp/";
+
+/// Prefix of every transcription job name generated by prepis
+pub const JOB_NAME_PREFIX: &str = "transcribe-job-";
 
 /// Generate a unique S3 key based on filename and timestamp
 pub fn generate_s3_key(file_path: &Path) -> String {
@@ -26,7 +36,7 @@ pub fn generate_s3_key(file_path: &Path) -> String {
         .and_then(|name| name.to_str())
         .unwrap_or("unknown");
 
-    format!("transcribe-temp/{}-{}", timestamp, filename)
+    format!("{}{}-{}", S3_TEMP_PREFIX, timestamp, filename)
 }
 
 /// Generate a unique transcription job name using timestamp and filename
@@ -41,5 +51,131 @@ pub fn generate_job_name(file_path: &Path) -> String {
         .and_then(|name| name.to_str())
         .unwrap_or("unknown");
 
-    format!("transcribe-job-{}-{}", timestamp, filename)
+    format!("{}{}-{}", JOB_NAME_PREFIX, timestamp, filename)
+}
+
+/// Split an S3 object URL into its bucket and key
+///
+/// Supports `s3://bucket/key` URIs as well as path-style
+/// (`https://s3.<region>.amazonaws.com/bucket/key`) and virtual-hosted
+/// (`https://bucket.s3.<region>.amazonaws.com/key`) HTTPS URLs, which is
+/// what Amazon Transcribe reports for results written to our own bucket.
+/// URLs under a custom S3 endpoint, e.g. `http://localhost:4566/bucket/key`,
+/// are path-style too.
+pub fn parse_s3_object_url(object_url: &str, s3_endpoint: Option<&str>) -> Option<(String, String)> {
+    let url = Url::parse(object_url).ok()?;
+    let host = url.host_str()?;
+    let under_endpoint = s3_endpoint.and_then(|endpoint| path_under_endpoint(&url, endpoint));
+    let path = under_endpoint.unwrap_or(url.path()).trim_start_matches('/');
+    let path = percent_decode_str(path).decode_utf8().ok()?.into_owned();
+
+    let (bucket, key) = if url.scheme() == "s3" {
+        (host.to_string(), path)
+    } else if under_endpoint.is_some() || host.starts_with("s3.") || host.starts_with("s3-") {
+        let (bucket, key) = path.split_once('/')?;
+        (bucket.to_string(), key.to_string())
+    } else {
+        let (bucket, _) = host.split_once(".s3")?;
+        (bucket.to_string(), path)
+    };
+
+    if bucket.is_empty() || key.is_empty() {
+        return None;
+    }
+    Some((bucket, key))
+}
+
+/// Path of a URL below the given endpoint, `None` when the URL is elsewhere
+fn path_under_endpoint<'a>(url: &'a Url, endpoint: &str) -> Option<&'a str> {
+    let endpoint = Url::parse(endpoint).ok()?;
+    if url.scheme() != endpoint.scheme()
+        || url.host_str() != endpoint.host_str()
+        || url.port_or_known_default() != endpoint.port_or_known_default()
+    {
+        return None;
+    }
+    let path = url.path().strip_prefix(endpoint.path().trim_end_matches('/'))?;
+    path.starts_with('/').then_some(path)
+}
+
+/// Resolve an XDG base directory, e.g. `XDG_CONFIG_HOME` falling back to `~/.config`
+pub fn xdg_base_dir(env_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(env_var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))
+}
+
+/// Format a duration in whole seconds for display, e.g. `2m 5s`
+pub fn format_duration(duration: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(duration.as_secs().max(1))).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(bucket: &str, key: &str) -> Option<(String, String)> {
+        Some((bucket.to_string(), key.to_string()))
+    }
+
+    #[test]
+    fn path_style_urls_are_split_after_the_bucket() {
+        assert_eq!(
+            parse_s3_object_url("https://s3.us-east-1.amazonaws.com/my-bucket/results/job.json", None),
+            object("my-bucket", "results/job.json")
+        );
+        assert_eq!(
+            parse_s3_object_url("https://s3-eu-west-1.amazonaws.com/my-bucket/job.json", None),
+            object("my-bucket", "job.json")
+        );
+    }
+
+    #[test]
+    fn virtual_hosted_and_s3_urls_name_the_bucket_in_the_host() {
+        assert_eq!(
+            parse_s3_object_url("https://my-bucket.s3.eu-west-1.amazonaws.com/results/job.json", None),
+            object("my-bucket", "results/job.json")
+        );
+        assert_eq!(
+            parse_s3_object_url("s3://my-bucket/results/job.json", None),
+            object("my-bucket", "results/job.json")
+        );
+    }
+
+    #[test]
+    fn keys_are_percent_decoded() {
+        assert_eq!(
+            parse_s3_object_url("https://s3.us-east-1.amazonaws.com/my-bucket/my%20talk%C3%A9.json", None),
+            object("my-bucket", "my talké.json")
+        );
+    }
+
+    #[test]
+    fn urls_under_a_custom_endpoint_are_path_style() {
+        let endpoint = Some("http://localhost:4566");
+        assert_eq!(
+            parse_s3_object_url("http://localhost:4566/my-bucket/results/job.json", endpoint),
+            object("my-bucket", "results/job.json")
+        );
+        assert_eq!(
+            parse_s3_object_url("https://minio.local/s3/my-bucket/job.json", Some("https://minio.local/s3/")),
+            object("my-bucket", "job.json")
+        );
+        assert_eq!(
+            parse_s3_object_url("https://my-bucket.s3.eu-west-1.amazonaws.com/job.json", endpoint),
+            object("my-bucket", "job.json")
+        );
+        assert_eq!(parse_s3_object_url("http://localhost:9000/my-bucket/job.json", endpoint), None);
+        assert_eq!(parse_s3_object_url("http://localhost:4566/my-bucket", endpoint), None);
+    }
+
+    #[test]
+    fn urls_without_a_bucket_or_key_are_rejected() {
+        assert_eq!(parse_s3_object_url("https://s3.us-east-1.amazonaws.com/my-bucket", None), None);
+        assert_eq!(parse_s3_object_url("https://s3.us-east-1.amazonaws.com/my-bucket/", None), None);
+        assert_eq!(parse_s3_object_url("s3://my-bucket/", None), None);
+        assert_eq!(parse_s3_object_url("https://example.com/my-bucket/job.json", None), None);
+        assert_eq!(parse_s3_object_url("not a url", None), None);
+    }
 }
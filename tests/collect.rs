@@ -0,0 +1,108 @@
+//! End-to-end tests of `prepis --detach` and `prepis collect` against the
+//! stubbed S3 and Amazon Transcribe endpoints, see `common` for the stub.
+
+mod common;
+
+use common::{JobOutcome, Scenario, StubAws, TestEnv, stderr};
+use std::process::Stdio;
+use std::time::Duration;
+
+#[test]
+fn result_json_is_kept_until_the_transcript_is_saved() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &["--detach", "--output-bucket", "archive-bucket"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // The output appeared after submitting, so saving fails
+    let txt = env.dir.path().join("talk.txt");
+    std::fs::write(&txt, "keep me").unwrap();
+    let output = env.subcommand(&stub, &["collect"]);
+
+    assert!(stderr(&output).contains("already exists"), "{}", stderr(&output));
+    assert_eq!(stub.count("GET", "/archive-bucket/"), 1);
+    assert_eq!(stub.count("DELETE", "/archive-bucket/"), 0);
+    assert_eq!(std::fs::read_to_string(&txt).unwrap(), "keep me");
+
+    // The next run fetches the result again
+    std::fs::remove_file(&txt).unwrap();
+    let output = env.subcommand(&stub, &["collect"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&txt).unwrap(), "hello from the stub\n");
+    assert_eq!(stub.count("GET", "/archive-bucket/"), 2);
+    assert_eq!(stub.count("DELETE", "/archive-bucket/"), 1);
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 1);
+
+    let output = env.subcommand(&stub, &["collect"]);
+    assert!(stderr(&output).contains("No pending jobs"), "{}", stderr(&output));
+}
+
+#[test]
+fn jobs_submitted_during_collect_are_kept() {
+    let stub = StubAws::start(Scenario {
+        job: JobOutcome::Running,
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let first = env.wav("first.wav", 1);
+    let second = env.wav("second.wav", 1);
+
+    let output = env.prepis(&stub, &first, &["--detach"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Submit another job while collect is waiting for the first one
+    let collect = env
+        .command()
+        .args(["collect", "--wait", "--timeout", "3s"])
+        .args(["--endpoint-url", &stub.endpoint, "--region", "us-east-1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_secs(1));
+    let output = env.prepis(&stub, &second, &["--detach"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    collect.wait_with_output().unwrap();
+
+    let store = std::fs::read_to_string(env.dir.path().join("home/state/prepis/pending-jobs.json")).unwrap();
+    assert!(store.contains("first.wav"), "{}", store);
+    assert!(store.contains("second.wav"), "{}", store);
+}
+
+#[test]
+fn only_unknown_jobs_are_dropped_on_a_bad_request() {
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    // A bad request for any other reason says nothing about the job
+    let stub = StubAws::start(Scenario {
+        job: JobOutcome::Rejected("1 validation error detected".to_string()),
+        ..Scenario::default()
+    });
+    let output = env.prepis(&stub, &media, &["--detach"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = env.subcommand(&stub, &["collect"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Could not check"), "{}", stderr(&output));
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 0);
+
+    let stub = StubAws::start(Scenario {
+        job: JobOutcome::Rejected(
+            "The requested job couldn't be found. Check the job name and try your request again."
+                .to_string(),
+        ),
+        ..Scenario::default()
+    });
+    let output = env.subcommand(&stub, &["collect"]);
+
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(stderr(&output).contains("job no longer exists"), "{}", stderr(&output));
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 1);
+
+    let output = env.subcommand(&stub, &["collect"]);
+    assert!(stderr(&output).contains("No pending jobs"), "{}", stderr(&output));
+}
@@ -0,0 +1,601 @@
+//! # Test Harness
+//!
+//! A local HTTP stub that speaks the subset of the S3 and Amazon Transcribe
+//! APIs prepis uses, and helpers to run the `prepis` binary against it.
+//!
+//! Every test starts its own [`StubAws`] with a [`Scenario`], runs prepis
+//! with the endpoint pointed at the stub and checks the exit code, the
+//! output and the requests the stub received. [`StubWhisper`] does the same
+//! for the Whisper API backend. Result files for tests that
+//! need no stub are in `tests/fixtures`.
+
+#![allow(dead_code)] // Not every test binary uses every helper
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Bucket every test stages its media in
+pub const BUCKET: &str = "test-bucket";
+
+/// Operation name under which result downloads are recorded
+pub const RESULT_DOWNLOAD: &str = "ResultDownload";
+
+/// How the stubbed services behave
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub job: JobOutcome,
+    pub transcript: String,     // Text of the result JSON
+    pub fail_part: Option<u32>, // Upload part that fails with a 500
+    pub fail_delete: bool,      // Deleting the staged media fails with a 500
+    pub running_polls: u32,     // Status requests of a job answered with IN_PROGRESS first
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            job: JobOutcome::Completed,
+            transcript: "hello from the stub".to_string(),
+            fail_part: None,
+            fail_delete: false,
+            running_polls: 0,
+        }
+    }
+}
+
+/// What happens to a transcription job once it has started
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobOutcome {
+    Completed,
+    Failed(String), // With this failure reason
+    Running,        // Never finishes
+    Rejected(String), // Looking the job up fails with a BadRequestException with this message
+}
+
+/// A request the stub received
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,           // Including the query
+    pub target: Option<String>, // Transcribe operation, e.g. `StartTranscriptionJob`
+    pub body_len: usize,
+}
+
+impl Request {
+    /// Whether this is an S3 request with the method whose path contains the text
+    pub fn is(&self, method: &str, path_part: &str) -> bool {
+        self.method == method && self.target.is_none() && self.path.contains(path_part)
+    }
+}
+
+/// S3 and Transcribe on a local port
+pub struct StubAws {
+    pub endpoint: String,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: Vec<Request>,
+    jobs: HashMap<String, Value>, // Settings of every started job
+    polls: HashMap<String, u32>,  // Status requests of every job
+}
+
+impl StubAws {
+    /// Start the stub on a free port
+    pub fn start(scenario: Scenario) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let server_state = state.clone();
+        let server_endpoint = endpoint.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = server_state.clone();
+                let scenario = scenario.clone();
+                let endpoint = server_endpoint.clone();
+                thread::spawn(move || serve(stream, &scenario, &state, &endpoint));
+            }
+        });
+
+        Self { endpoint, state }
+    }
+
+    /// Every request received so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Number of received requests that match, see [`Request::is`]
+    pub fn count(&self, method: &str, path_part: &str) -> usize {
+        self.requests().iter().filter(|r| r.is(method, path_part)).count()
+    }
+
+    /// Number of calls of a Transcribe operation
+    pub fn calls(&self, operation: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter(|r| r.target.as_deref() == Some(operation))
+            .count()
+    }
+
+    /// Request body of a started job, as Transcribe received it
+    pub fn job(&self, job_name: &str) -> Option<Value> {
+        self.state.lock().unwrap().jobs.get(job_name).cloned()
+    }
+}
+
+/// An OpenAI-compatible Whisper server on a local port
+///
+/// Every transcription request gets the same answer, optionally after a
+/// delay to let the client time out.
+pub struct StubWhisper {
+    pub url: String,
+    received: Arc<Mutex<Vec<RequestWithBody>>>,
+}
+
+/// A request and the body it was sent with
+pub type RequestWithBody = (Request, Vec<u8>);
+
+impl StubWhisper {
+    /// Start the stub on a free port, answering with a status and a JSON body
+    pub fn start(status: u16, body: &str, delay: Duration) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let server_received = received.clone();
+        let body = body.as_bytes().to_vec();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let received = server_received.clone();
+                let body = body.clone();
+                thread::spawn(move || {
+                    let mut writer = stream.try_clone().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    while let Some(request) = read_request(&mut reader, &mut writer) {
+                        received.lock().unwrap().push(request);
+                        thread::sleep(delay);
+                        if write_response(&mut writer, (status, "application/json", body.clone())).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self { url, received }
+    }
+
+    /// Every request received so far, with its body
+    pub fn requests(&self) -> Vec<RequestWithBody> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+/// Answer the requests of one connection until the client closes it
+fn serve(stream: TcpStream, scenario: &Scenario, state: &Mutex<State>, endpoint: &str) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    while let Some((request, body)) = read_request(&mut reader, &mut writer) {
+        state.lock().unwrap().requests.push(request.clone());
+
+        let response = match request.target.as_deref() {
+            Some(RESULT_DOWNLOAD) => result(&request.path, scenario),
+            Some(operation) => transcribe(operation, &body, scenario, state, endpoint),
+            None => s3(&request.method, &request.path, scenario),
+        };
+        if write_response(&mut writer, response).is_err() {
+            return;
+        }
+    }
+}
+
+/// Status, content type and body of a response
+type Response = (u16, &'static str, Vec<u8>);
+
+/// Answer an S3 request
+fn s3(method: &str, path: &str, scenario: &Scenario) -> Response {
+    let query = path.split_once('?').map_or("", |(_, query)| query);
+    let param = |name: &str| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            .map(str::to_string)
+    };
+
+    match method {
+        "GET" if query.contains("location") => xml(
+            "<LocationConstraint xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\"></LocationConstraint>",
+        ),
+        // A result JSON Transcribe wrote to an output bucket
+        "GET" if path.split('?').next().is_some_and(|key| key.ends_with(".json")) => {
+            result(path.rsplit('/').next().unwrap_or_default(), scenario)
+        }
+        "POST" if query.contains("uploads") => xml(
+            "<InitiateMultipartUploadResult><Bucket>test-bucket</Bucket><Key>media</Key><UploadId>upload-1</UploadId></InitiateMultipartUploadResult>",
+        ),
+        "POST" if param("uploadId").is_some() => xml(
+            "<CompleteMultipartUploadResult><Bucket>test-bucket</Bucket><Key>media</Key><ETag>\"joined\"</ETag></CompleteMultipartUploadResult>",
+        ),
+        "PUT" => {
+            let part = param("partNumber").and_then(|part| part.parse::<u32>().ok());
+            match part {
+                Some(part) if scenario.fail_part == Some(part) => s3_error(),
+                _ => (200, "application/xml", Vec::new()),
+            }
+        }
+        "DELETE" if param("uploadId").is_some() => (204, "application/xml", Vec::new()),
+        "DELETE" if scenario.fail_delete => s3_error(),
+        "DELETE" => (204, "application/xml", Vec::new()),
+        _ => (404, "application/xml", Vec::new()),
+    }
+}
+
+/// Answer a Transcribe request of the JSON protocol
+fn transcribe(
+    operation: &str,
+    body: &[u8],
+    scenario: &Scenario,
+    state: &Mutex<State>,
+    endpoint: &str,
+) -> Response {
+    let request: Value = serde_json::from_slice(body).unwrap_or_default();
+    let job_name = request["TranscriptionJobName"].as_str().unwrap_or_default().to_string();
+
+    let output = match operation {
+        "GetTranscriptionJob" if let JobOutcome::Rejected(message) = &scenario.job => {
+            let error = json!({ "__type": "BadRequestException", "Message": message });
+            return (400, "application/x-amz-json-1.1", error.to_string().into_bytes());
+        }
+        "StartTranscriptionJob" => {
+            state.lock().unwrap().jobs.insert(job_name.clone(), request.clone());
+            json!({ "TranscriptionJob": {
+                "TranscriptionJobName": job_name,
+                "TranscriptionJobStatus": "IN_PROGRESS",
+            }})
+        }
+        "GetTranscriptionJob" => {
+            let mut job = json!({
+                "TranscriptionJobName": job_name,
+                "LanguageCode": "en-US",
+                "CreationTime": 1_700_000_000,
+            });
+            let (started, polls) = {
+                let mut state = state.lock().unwrap();
+                let polls = state.polls.entry(job_name.clone()).or_default();
+                *polls += 1;
+                let polls = *polls;
+                (state.jobs.get(&job_name).cloned().unwrap_or_default(), polls)
+            };
+            let outcome = match polls > scenario.running_polls {
+                true => &scenario.job,
+                false => &JobOutcome::Running,
+            };
+            match outcome {
+                JobOutcome::Completed => {
+                    job["TranscriptionJobStatus"] = json!("COMPLETED");
+                    // Results go to the output bucket when there is one, which
+                    // lives under the endpoint like with LocalStack
+                    let result_uri = match started["OutputBucketName"].as_str() {
+                        Some(bucket) => format!(
+                            "{}/{}/{}",
+                            endpoint,
+                            bucket,
+                            started["OutputKey"].as_str().map_or(format!("{}.json", job_name), str::to_string)
+                        ),
+                        None => format!("{}/result/{}.json?X-Amz-Signature=stub", endpoint, job_name),
+                    };
+                    job["Transcript"] = json!({ "TranscriptFileUri": result_uri });
+                }
+                JobOutcome::Failed(reason) => {
+                    job["TranscriptionJobStatus"] = json!("FAILED");
+                    job["FailureReason"] = json!(reason);
+                }
+                JobOutcome::Running | JobOutcome::Rejected(_) => {
+                    job["TranscriptionJobStatus"] = json!("IN_PROGRESS")
+                }
+            }
+            json!({ "TranscriptionJob": job })
+        }
+        _ => json!({}),
+    };
+    (200, "application/x-amz-json-1.1", output.to_string().into_bytes())
+}
+
+/// The result JSON of a job, downloaded from its presigned URI or the output bucket
+fn result(path: &str, scenario: &Scenario) -> Response {
+    let job_name = path
+        .trim_start_matches("/result/")
+        .split(".json")
+        .next()
+        .unwrap_or_default();
+    let result = json!({
+        "jobName": job_name,
+        "status": "COMPLETED",
+        "results": {
+            "transcripts": [{ "transcript": scenario.transcript }],
+            "items": [],
+        },
+    });
+    (200, "application/json", result.to_string().into_bytes())
+}
+
+/// An XML response with the declaration S3 puts in front
+fn xml(body: &str) -> Response {
+    let body = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}", body);
+    (200, "application/xml", body.into_bytes())
+}
+
+/// A 500 `InternalError` as S3 sends it
+fn s3_error() -> Response {
+    let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>InternalError</Code><Message>Injected failure</Message></Error>";
+    (500, "application/xml", body.as_bytes().to_vec())
+}
+
+/// Read the next request of a connection and its body
+fn read_request(
+    reader: &mut BufReader<TcpStream>,
+    writer: &mut TcpStream,
+) -> Option<(Request, Vec<u8>)> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':')?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    if headers.get("expect").is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue")) {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").ok()?;
+    }
+
+    let body = if headers.get("transfer-encoding").is_some_and(|te| te.contains("chunked")) {
+        read_chunked(reader)?
+    } else {
+        let len = headers.get("content-length").and_then(|len| len.parse().ok()).unwrap_or(0);
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).ok()?;
+        body
+    };
+
+    // Result downloads are presigned GETs outside of both APIs
+    let target = if method == "GET" && path.starts_with("/result/") {
+        Some(RESULT_DOWNLOAD.to_string())
+    } else {
+        headers
+            .get("x-amz-target")
+            .and_then(|target| target.rsplit('.').next())
+            .map(str::to_string)
+    };
+    let request = Request {
+        method,
+        path,
+        target,
+        body_len: body.len(),
+    };
+    Some((request, body))
+}
+
+/// Read a body sent with `Transfer-Encoding: chunked`
+fn read_chunked(reader: &mut BufReader<TcpStream>) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size = String::new();
+        reader.read_line(&mut size).ok()?;
+        let size = usize::from_str_radix(size.trim().split(';').next()?, 16).ok()?;
+        let mut chunk = vec![0; size + 2]; // With the trailing CRLF
+        reader.read_exact(&mut chunk).ok()?;
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+fn write_response(writer: &mut TcpStream, (status, content_type, body): Response) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nETag: \"stub-etag\"\r\nx-amzn-requestid: stub-request\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// A directory with media files and an isolated home for one test
+pub struct TestEnv {
+    pub dir: tempfile::TempDir,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        Self {
+            dir: tempfile::tempdir().unwrap(),
+        }
+    }
+
+    /// A short 16 kHz mono WAV file
+    pub fn wav(&self, name: &str, seconds: u32) -> PathBuf {
+        let path = self.dir.path().join(name);
+        std::fs::write(&path, prepis::file::silent_wav(seconds)).unwrap();
+        path
+    }
+
+    /// A file of the given size without any readable headers
+    pub fn large_file(&self, name: &str, size_bytes: u64) -> PathBuf {
+        let path = self.dir.path().join(name);
+        std::fs::File::create(&path).unwrap().set_len(size_bytes).unwrap();
+        path
+    }
+
+    /// Run prepis against the stub, isolated from the user's configuration
+    pub fn prepis(&self, stub: &StubAws, media: &Path, args: &[&str]) -> Output {
+        self.command()
+            .arg(media)
+            .arg(BUCKET)
+            .args(["--endpoint-url", &stub.endpoint, "--region", "us-east-1"])
+            .args(["--skip-preflight", "--max-attempts", "1", "--poll-interval", "100ms"])
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Run prepis with the Whisper API backend against the stub
+    pub fn prepis_whisper(&self, stub: &StubWhisper, media: &Path, args: &[&str]) -> Output {
+        self.command()
+            .arg(media)
+            .args(["--backend", "whisper-api", "--whisper-url", &stub.url])
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Run prepis with the whisper-cpp backend, see [`TestEnv::fake_whisper_cpp`]
+    pub fn prepis_whisper_cpp(&self, media: &Path, args: &[&str]) -> Output {
+        let bin = self.fake_whisper_cpp();
+        self.command()
+            .env("PATH", format!("{}:/usr/bin:/bin", bin.display()))
+            .arg(media)
+            .args(["--backend", "whisper-cpp", "--whisper-cpp-model"])
+            .arg(bin.join("model.bin"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Scripts standing in for `whisper-cli` and `ffmpeg`, and a model file
+    ///
+    /// The fake whisper.cpp answers with `tests/fixtures/whisper-cpp-full.json`
+    /// and appends the media it got, the directory of its result and that
+    /// directory's mode to `whisper-cpp.log`, see [`TestEnv::whisper_cpp_calls`].
+    pub fn fake_whisper_cpp(&self) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = self.dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let whisper_cli = format!(
+            r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --file) file="$2"; shift ;;
+        --output-file) out="$2"; shift ;;
+    esac
+    shift
+done
+dir=$(dirname "$out")
+echo "$file $dir $(stat -c %a "$dir")" >> "{log}"
+cp "{fixture}" "$out.json"
+"#,
+            log = self.dir.path().join("whisper-cpp.log").display(),
+            fixture = fixture("whisper-cpp-full.json").display(),
+        );
+        // Writes a placeholder to the output, the last argument
+        let ffmpeg = "#!/bin/sh
+for out; do :; done
+printf RIFF > \"$out\"\n";
+
+        for (name, script) in [("whisper-cli", whisper_cli.as_str()), ("ffmpeg", ffmpeg)] {
+            let path = bin.join(name);
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::write(bin.join("model.bin"), "").unwrap();
+        bin
+    }
+
+    /// Media, result directory and its mode of every fake whisper.cpp run
+    pub fn whisper_cpp_calls(&self) -> Vec<(PathBuf, PathBuf, String)> {
+        std::fs::read_to_string(self.dir.path().join("whisper-cpp.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(|line| {
+                let mut parts = line.split(' ');
+                let mut next = || parts.next().unwrap().to_string();
+                (PathBuf::from(next()), PathBuf::from(next()), next())
+            })
+            .collect()
+    }
+
+    /// Run a prepis subcommand against the stub, isolated from the user's configuration
+    pub fn subcommand(&self, stub: &StubAws, args: &[&str]) -> Output {
+        self.command()
+            .args(args)
+            .args(["--endpoint-url", &stub.endpoint, "--region", "us-east-1"])
+            .args(["--max-attempts", "1"])
+            .output()
+            .unwrap()
+    }
+
+    /// The prepis binary without arguments, isolated from the user's configuration
+    pub fn command(&self) -> Command {
+        let home = self.dir.path().join("home");
+        let mut command = Command::new(env!("CARGO_BIN_EXE_prepis"));
+        command
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join("config"))
+            .env("XDG_STATE_HOME", home.join("state"))
+            .env("AWS_ACCESS_KEY_ID", "test-access-key")
+            .env("AWS_SECRET_ACCESS_KEY", "test-secret-key")
+            .env("AWS_CONFIG_FILE", home.join("aws-config"))
+            .env("AWS_SHARED_CREDENTIALS_FILE", home.join("aws-credentials"))
+            .env("AWS_EC2_METADATA_DISABLED", "true")
+            .env("NO_COLOR", "1")
+            .current_dir(self.dir.path());
+        command
+    }
+}
+
+/// Path of a file in `tests/fixtures`
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// The JSON report prepis prints with `--output-format json`
+pub fn report(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "stdout is not a JSON report ({}): {}\nstderr: {}",
+            e,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+/// Everything prepis logged
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
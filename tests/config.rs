@@ -0,0 +1,61 @@
+//! Tests of `prepis config` and of how the command line is parsed.
+
+mod common;
+
+use common::{TestEnv, stderr};
+
+#[test]
+fn show_prints_no_emojis_with_plain() {
+    let env = TestEnv::new();
+
+    let output = env.command().args(["config", "show", "--plain"]).output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("User config: "), "{}", stdout);
+    assert!(!stdout.contains('📄') && !stdout.contains("🎛"), "{}", stdout);
+}
+
+#[test]
+fn file_arguments_next_to_a_subcommand_are_refused() {
+    let env = TestEnv::new();
+
+    let output = env.command().args(["talk.mp4", "my-bucket", "jobs", "list"]).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("'talk.mp4' cannot be used"), "{}", stderr(&output));
+
+    // Global options before the subcommand are still fine
+    let output = env.command().args(["--region", "eu-west-1", "config", "show"]).output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn no_flags_override_the_preset() {
+    let env = TestEnv::new();
+    std::fs::write(
+        env.dir.path().join(".prepis.toml"),
+        "[presets.archive]\nredact_pii = true\nkeep_output = true\n",
+    )
+    .unwrap();
+
+    let show = |args: &[&str]| {
+        let output = env
+            .command()
+            .args(["--preset", "archive"])
+            .args(args)
+            .args(["config", "show"])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = show(&[]);
+    assert!(stdout.contains("redact_pii              = true"), "{}", stdout);
+
+    let stdout = show(&["--no-redact-pii", "--no-keep-output"]);
+    assert!(stdout.contains("redact_pii              = false  (command line)"), "{}", stdout);
+    assert!(stdout.contains("keep_output             = false  (command line)"), "{}", stdout);
+}
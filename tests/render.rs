@@ -0,0 +1,95 @@
+//! Tests of `prepis render` with result files from `tests/fixtures`.
+
+mod common;
+
+use common::{Scenario, StubAws, TestEnv, fixture, stderr};
+
+/// Render the fixture with the given arguments and return stdout
+fn render(env: &TestEnv, args: &[&str]) -> String {
+    let output = env
+        .command()
+        .arg("render")
+        .arg(fixture("transcribe-result.json"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn renders_txt_by_default() {
+    let env = TestEnv::new();
+
+    assert_eq!(
+        render(&env, &[]),
+        "spk_0: Welcome to the show.\nspk_1: Thanks, glad to be here!\n"
+    );
+}
+
+#[test]
+fn renders_subtitles() {
+    let env = TestEnv::new();
+
+    assert_eq!(
+        render(&env, &["--format", "srt"]),
+        "1\n00:00:00,040 --> 00:00:01,190\nWelcome to the show.\n\n\
+         2\n00:00:01,900 --> 00:00:03,400\nThanks, glad to be here!\n"
+    );
+    assert_eq!(
+        render(&env, &["--format", "vtt"]),
+        "WEBVTT\n\n00:00:00.040 --> 00:00:01.190\n<v spk_0>Welcome to the show.\n\n\
+         00:00:01.900 --> 00:00:03.400\n<v spk_1>Thanks, glad to be here!\n"
+    );
+}
+
+#[test]
+fn renders_markdown_titled_with_the_job_name() {
+    let env = TestEnv::new();
+
+    assert_eq!(
+        render(&env, &["--format", "md"]),
+        "# prepis-interview\n\n**[00:00:00] spk_0:** Welcome to the show.\n\n\
+         **[00:00:01] spk_1:** Thanks, glad to be here!\n"
+    );
+}
+
+#[test]
+fn format_follows_the_output_extension() {
+    let env = TestEnv::new();
+    let srt = env.dir.path().join("interview.srt");
+    let docx = env.dir.path().join("interview.docx");
+
+    render(&env, &["-o", srt.to_str().unwrap()]);
+    render(&env, &["-o", docx.to_str().unwrap()]);
+
+    assert!(std::fs::read_to_string(srt).unwrap().starts_with("1\n00:00:00,040"));
+    assert!(std::fs::read(docx).unwrap().starts_with(b"PK"));
+}
+
+#[test]
+fn docx_is_not_printed() {
+    let env = TestEnv::new();
+
+    let output = env
+        .command()
+        .args(["render", "--format", "docx"])
+        .arg(fixture("transcribe-result.json"))
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn renders_a_result_from_a_url() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let url = format!("{}/result/talk.json?X-Amz-Signature=stub", stub.endpoint);
+
+    let output = env.command().args(["render", &url]).output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello from the stub\n");
+}
@@ -0,0 +1,301 @@
+//! End-to-end tests of `prepis <media> <bucket>` against stubbed S3 and
+//! Amazon Transcribe endpoints, see `common` for the stub.
+
+mod common;
+
+use common::{BUCKET, JobOutcome, RESULT_DOWNLOAD, Scenario, StubAws, TestEnv, report, stderr};
+use std::process::Stdio;
+use std::time::Duration;
+
+#[test]
+fn transcribes_media_and_cleans_up() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &["--output-format", "json"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report = report(&output);
+    assert_eq!(report["transcript"], "hello from the stub");
+    assert_eq!(report["status"], "completed");
+
+    let job_name = report["job_name"].as_str().unwrap();
+    let job = stub.job(job_name).expect("job was started");
+    let media_uri = job["Media"]["MediaFileUri"].as_str().unwrap();
+    assert!(media_uri.starts_with(&format!("s3://{}/", BUCKET)), "{}", media_uri);
+    assert_eq!(job["LanguageCode"], "en-US");
+
+    assert_eq!(stub.count("PUT", "-talk.wav"), 1);
+    assert_eq!(stub.calls(RESULT_DOWNLOAD), 1);
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 1);
+}
+
+#[test]
+fn failed_job_reports_the_reason() {
+    let stub = StubAws::start(Scenario {
+        job: JobOutcome::Failed("The media format is not supported".to_string()),
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &[]);
+
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(stderr(&output).contains("The media format is not supported"));
+    assert_eq!(stub.calls(RESULT_DOWNLOAD), 0);
+}
+
+#[test]
+fn job_that_never_finishes_times_out() {
+    let stub = StubAws::start(Scenario {
+        job: JobOutcome::Running,
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &["--timeout", "1s"]);
+
+    assert_eq!(output.status.code(), Some(8), "{}", stderr(&output));
+    assert!(stub.calls("GetTranscriptionJob") > 1);
+    assert_eq!(stub.calls(RESULT_DOWNLOAD), 0);
+}
+
+#[test]
+fn empty_transcript_is_an_error() {
+    let stub = StubAws::start(Scenario {
+        transcript: String::new(),
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &[]);
+
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(stderr(&output).contains("empty"), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert_eq!(stub.calls(RESULT_DOWNLOAD), 1);
+}
+
+#[test]
+fn failed_upload_part_aborts_the_upload() {
+    let stub = StubAws::start(Scenario {
+        fail_part: Some(2),
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    // Large enough for a multipart upload, the headers are never checked
+    let media = env.large_file("long.wav", 105 * 1024 * 1024);
+
+    let output = env.prepis(&stub, &media, &[]);
+
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(stderr(&output).contains("part 2"), "{}", stderr(&output));
+    assert_eq!(stub.count("POST", "?uploads"), 1);
+    assert_eq!(stub.count("DELETE", "uploadId="), 1);
+    assert_eq!(stub.count("POST", "uploadId="), 0);
+    assert_eq!(stub.calls("StartTranscriptionJob"), 0);
+}
+
+#[test]
+fn failed_cleanup_only_warns() {
+    let stub = StubAws::start(Scenario {
+        fail_delete: true,
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis(&stub, &media, &["--output-format", "json"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(report(&output)["transcript"], "hello from the stub");
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 1);
+    assert!(stderr(&output).contains("Failed to delete"), "{}", stderr(&output));
+}
+
+#[test]
+fn writes_every_format_from_one_result() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+    let out = env.dir.path().join("out");
+
+    let output = env.prepis(
+        &stub,
+        &media,
+        &[
+            "--formats", "txt,json,md",
+            "--output-dir", out.to_str().unwrap(),
+            "--output-template", "{stem}.{lang}.{ext}",
+            "-o", "extra.md",
+        ],
+    );
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stub.calls(RESULT_DOWNLOAD), 1);
+    let txt = std::fs::read_to_string(out.join("talk.en-US.txt")).unwrap();
+    assert_eq!(txt, "hello from the stub\n");
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.join("talk.en-US.json")).unwrap()).unwrap();
+    assert_eq!(json["results"]["transcripts"][0]["transcript"], "hello from the stub");
+    assert!(out.join("talk.en-US.md").exists());
+    assert!(out.join("extra.md").exists());
+}
+
+#[test]
+fn existing_output_is_refused_before_uploading() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+    std::fs::write(env.dir.path().join("talk.txt"), "keep me").unwrap();
+
+    let output = env.prepis(&stub, &media, &["--formats", "txt"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("--force"), "{}", stderr(&output));
+    assert!(stub.requests().is_empty());
+    assert_eq!(std::fs::read_to_string(env.dir.path().join("talk.txt")).unwrap(), "keep me");
+
+    let output = env.prepis(&stub, &media, &["--formats", "txt", "--force"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        std::fs::read_to_string(env.dir.path().join("talk.txt")).unwrap(),
+        "hello from the stub\n"
+    );
+}
+
+#[test]
+fn transcript_is_saved_next_to_the_media_by_default() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media_dir = env.dir.path().join("media");
+    std::fs::create_dir(&media_dir).unwrap();
+    let media = media_dir.join("talk.wav");
+    std::fs::rename(env.wav("talk.wav", 1), &media).unwrap();
+
+    // Piped stdout still gets the transcript
+    let output = env.prepis(&stub, &media, &[]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello from the stub\n");
+    assert_eq!(
+        std::fs::read_to_string(media_dir.join("talk.txt")).unwrap(),
+        "hello from the stub\n"
+    );
+    assert!(!env.dir.path().join("talk.txt").exists());
+
+    let output = env.prepis(&stub, &media, &["--force", "--no-print"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn unusable_outputs_are_refused_before_uploading() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    // A directory that cannot be created, because a file has its name
+    std::fs::write(env.dir.path().join("notes"), "").unwrap();
+    let output = env.prepis(&stub, &media, &["-o", "notes/talk.txt"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Cannot write"), "{}", stderr(&output));
+
+    // A template without any --formats to name
+    let output = env.prepis(&stub, &media, &["-o", "talk.srt", "--output-template", "{stem}.{lang}.{ext}"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("--formats"), "{}", stderr(&output));
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn output_bucket_options_without_a_bucket_are_refused() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    for args in [["--output-key", "archive/talk.json"].as_slice(), &["--keep-output"]] {
+        let output = env.prepis(&stub, &media, args);
+
+        assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+        assert!(stderr(&output).contains("output bucket"), "{}", stderr(&output));
+    }
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn dry_run_total_is_unknown_without_any_duration() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.large_file("talk.mp4", 1024);
+
+    let output = env.prepis(&stub, &media, &["--dry-run"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let total = stdout.lines().find(|line| line.starts_with("TOTAL")).unwrap();
+    assert_eq!(total.split_whitespace().collect::<Vec<_>>(), ["TOTAL", "unknown", "unknown"]);
+}
+
+#[test]
+fn transcribe_subcommand_takes_its_own_options() {
+    let stub = StubAws::start(Scenario::default());
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+    let media = media.to_str().unwrap();
+
+    let output = env.subcommand(
+        &stub,
+        &["transcribe", media, BUCKET, "--skip-preflight", "--language", "de-DE", "--output-format", "json"],
+    );
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let job_name = report(&output)["job_name"].as_str().unwrap().to_string();
+    assert_eq!(stub.job(&job_name).expect("job was started")["LanguageCode"], "de-DE");
+}
+
+#[test]
+fn result_and_media_are_kept_when_saving_fails() {
+    let stub = StubAws::start(Scenario {
+        running_polls: 5,
+        ..Scenario::default()
+    });
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let prepis = env
+        .command()
+        .arg(&media)
+        .arg(BUCKET)
+        .args(["--endpoint-url", &stub.endpoint, "--region", "us-east-1"])
+        .args(["--skip-preflight", "--max-attempts", "1"])
+        .args(["--poll-interval", "100ms", "--max-interval", "100ms"])
+        .args(["--output-bucket", "archive-bucket"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The output appears while the job is running
+    while stub.calls("StartTranscriptionJob") == 0 {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let txt = env.dir.path().join("talk.txt");
+    std::fs::write(&txt, "keep me").unwrap();
+    let output = prepis.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("already exists"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(&txt).unwrap(), "keep me");
+    assert_eq!(stub.count("GET", "/archive-bucket/"), 1);
+    assert_eq!(stub.count("DELETE", "/archive-bucket/"), 0);
+    assert_eq!(stub.count("DELETE", "-talk.wav"), 0);
+}
@@ -0,0 +1,120 @@
+//! End-to-end tests of the Whisper API backend against a stubbed
+//! OpenAI-compatible server, see `common` for the stub.
+
+mod common;
+
+use common::{StubWhisper, TestEnv, fixture, report, stderr};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+#[test]
+fn posts_the_media_and_splits_words_and_punctuation() {
+    let response = std::fs::read_to_string(fixture("whisper-verbose.json")).unwrap();
+    let stub = StubWhisper::start(200, &response, Duration::ZERO);
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis_whisper(
+        &stub,
+        &media,
+        &["--language", "de-DE", "--formats", "json,srt", "--output-format", "json"],
+    );
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(report(&output)["transcript"], "Hello, world. Don't panic!");
+
+    // One multipart request with the file and the options
+    let requests = stub.requests();
+    assert_eq!(requests.len(), 1);
+    let (request, body) = &requests[0];
+    assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/v1/audio/transcriptions"));
+    assert!(request.body_len > 32_000, "{}", request.body_len); // The whole WAV file
+    let body = String::from_utf8_lossy(body);
+    for field in [
+        "filename=\"talk.wav\"",
+        "name=\"model\"\r\n\r\nwhisper-1",
+        "name=\"language\"\r\n\r\nde\r\n",
+        "name=\"response_format\"\r\n\r\nverbose_json",
+        "name=\"timestamp_granularities[]\"\r\n\r\nword",
+    ] {
+        assert!(body.contains(field), "{} is missing", field);
+    }
+
+    // Punctuation is split off into items of its own, apostrophes are not
+    let result: Value =
+        serde_json::from_str(&std::fs::read_to_string(env.dir.path().join("talk.json")).unwrap()).unwrap();
+    let items: Vec<(&str, &str)> = result["results"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (item["type"].as_str().unwrap(), item["alternatives"][0]["content"].as_str().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        items,
+        [
+            ("pronunciation", "Hello"),
+            ("punctuation", ","),
+            ("pronunciation", "world"),
+            ("punctuation", "."),
+            ("pronunciation", "Don't"),
+            ("pronunciation", "panic"),
+            ("punctuation", "!"),
+        ]
+    );
+    assert_eq!(result["results"]["items"][0]["alternatives"][0]["confidence"], "0.980");
+    assert_eq!(result["results"]["audio_segments"][1]["transcript"], "Don't panic!");
+
+    let srt = std::fs::read_to_string(env.dir.path().join("talk.srt")).unwrap();
+    assert!(srt.starts_with("1\n00:00:00,000 --> "), "{}", srt);
+}
+
+#[test]
+fn server_error_messages_are_shown() {
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    // OpenAI style
+    let stub = StubWhisper::start(400, r#"{"error":{"message":"Model large-v9 not found"}}"#, Duration::ZERO);
+    let output = env.prepis_whisper(&stub, &media, &[]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Model large-v9 not found"), "{}", stderr(&output));
+
+    // FastAPI style, as faster-whisper servers answer
+    let stub = StubWhisper::start(500, r#"{"detail":"CUDA out of memory"}"#, Duration::ZERO);
+    let output = env.prepis_whisper(&stub, &media, &[]);
+
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(stderr(&output).contains("CUDA out of memory"), "{}", stderr(&output));
+    assert!(!env.dir.path().join("talk.txt").exists());
+}
+
+#[test]
+fn hung_server_times_out() {
+    let stub = StubWhisper::start(200, "{}", Duration::from_secs(30));
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let started = Instant::now();
+    let output = env.prepis_whisper(&stub, &media, &["--timeout", "1s"]);
+
+    assert_eq!(output.status.code(), Some(8), "{}", stderr(&output));
+    assert!(started.elapsed() < Duration::from_secs(10), "{:?}", started.elapsed());
+}
+
+#[test]
+fn second_argument_is_the_output_file() {
+    let response = std::fs::read_to_string(fixture("whisper-verbose.json")).unwrap();
+    let stub = StubWhisper::start(200, &response, Duration::ZERO);
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis_whisper(&stub, &media, &["out.txt"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let txt = std::fs::read_to_string(env.dir.path().join("out.txt")).unwrap();
+    assert!(txt.contains("Hello, world."), "{}", txt);
+    assert!(!env.dir.path().join("talk.txt").exists());
+}
@@ -0,0 +1,57 @@
+//! End-to-end tests of the whisper.cpp backend with fake `whisper-cli` and
+//! `ffmpeg` scripts, see `common` for the fakes.
+
+mod common;
+
+use common::{TestEnv, report, stderr};
+
+#[test]
+fn media_and_results_stay_in_private_directories() {
+    let env = TestEnv::new();
+    let wav = env.wav("talk.wav", 1);
+    let mp4 = env.large_file("talk.mp4", 1024);
+
+    // 16 kHz WAV files are passed as they are, everything else is converted
+    for media in [&wav, &mp4] {
+        let output = env.prepis_whisper_cpp(media, &["--force", "--output-format", "json"]);
+
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(report(&output)["transcript"], "Hello, world. It's fine.");
+    }
+
+    let calls = env.whisper_cpp_calls();
+    assert_eq!(calls.len(), 2);
+    let (passed, first_result_dir, mode) = &calls[0];
+    assert_eq!(passed, &wav);
+    assert_eq!(mode, "700");
+    let (converted, second_result_dir, mode) = &calls[1];
+    assert_ne!(converted, &mp4);
+    assert_eq!(mode, "700");
+    assert_ne!(first_result_dir, second_result_dir);
+
+    // Nothing is left behind, and the input is untouched
+    assert!(!first_result_dir.exists());
+    assert!(!second_result_dir.exists());
+    assert!(!converted.parent().unwrap().exists());
+    assert!(wav.exists() && mp4.exists());
+}
+
+#[test]
+fn second_argument_is_the_output_file() {
+    let env = TestEnv::new();
+    let media = env.wav("talk.wav", 1);
+
+    let output = env.prepis_whisper_cpp(&media, &["out.srt"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let srt = std::fs::read_to_string(env.dir.path().join("out.srt")).unwrap();
+    assert!(srt.starts_with("1\n00:00:00,000 --> "), "{}", srt);
+    assert!(!env.dir.path().join("talk.txt").exists());
+
+    // A bucket on top of the output file has no place to go
+    let output = env.prepis_whisper_cpp(&media, &["my-bucket", "other.txt"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output).contains("uses no S3 bucket"), "{}", stderr(&output));
+    assert!(!env.dir.path().join("other.txt").exists());
+}